@@ -1,15 +1,168 @@
 // gRPC health checker module
 // T057-T066: Complete gRPC health checking implementation
 
-use crate::config::AgentConfig;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{AgentConfig, BackoffJitter, ChannelKeyMode, DnsConfig, FirstFailureResponse};
+use crate::http_checker::HttpHealthChecker;
+#[cfg(feature = "metrics")]
 use crate::metrics;
-use crate::protocol::{HealthCheckRequest, HealthCheckResponse, HealthStatus, SslFlag};
+use crate::protocol::{
+    CheckProtocol, HealthCheckRequest, HealthCheckResponse, HealthStatus, SslFlag,
+};
+use crate::proxy;
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use hickory_resolver::TokioResolver;
+use hickory_resolver::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use hickory_resolver::net::runtime::TokioRuntimeProvider;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::Duration;
-use tonic::transport::{Channel, ClientTlsConfig};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::{Semaphore, SemaphorePermit, TryAcquireError, watch};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
+use tonic::transport::Channel;
+#[cfg(feature = "tls")]
+use tonic::transport::ClientTlsConfig;
 use tonic_prost::ProstCodec;
 
+/// A backend health check the agent server can answer HAProxy with.
+/// Implement this to reuse the agent protocol server with a non-gRPC probe.
+pub trait HealthChecker: Send + Sync + 'static {
+    fn check(
+        &self,
+        request: &HealthCheckRequest,
+    ) -> impl Future<Output = HealthCheckResponse> + Send;
+
+    /// Reserve a slot for one check without waiting.
+    /// Ok(None) means checks are unlimited; Err means the checker is at capacity.
+    fn try_acquire_check_permit(&self) -> Result<Option<SemaphorePermit<'_>>, TryAcquireError> {
+        Ok(None)
+    }
+
+    /// Open backend channels, for the stats command; None when the checker doesn't pool channels
+    fn active_channels(&self) -> Option<usize> {
+        None
+    }
+}
+
+// CheckError: taxonomy of health check failures
+#[derive(Debug, Error)]
+pub enum CheckError {
+    #[error("Invalid endpoint {endpoint}: {message}")]
+    InvalidEndpoint { endpoint: String, message: String },
+
+    #[error("DNS resolution failed for {host}: {message}")]
+    DnsResolution { host: String, message: String },
+
+    #[error("TLS configuration failed: {0}")]
+    TlsConfig(String),
+
+    #[error("Connection timeout to {0}")]
+    ConnectTimeout(String),
+
+    #[error("Connection failed to {endpoint}: {message}")]
+    ConnectFailed { endpoint: String, message: String },
+
+    #[error("TLS handshake failed with {endpoint}: {message}")]
+    TlsHandshake { endpoint: String, message: String },
+
+    #[error("Not reconnecting to {endpoint} for another {remaining:?} after a failed connect")]
+    ConnectBackoff {
+        endpoint: String,
+        remaining: Duration,
+    },
+
+    #[error("Health check RPC timeout after {0:?}")]
+    RpcTimeout(Duration),
+
+    #[error("Health check RPC failed with {}: {}", grpc_code_name(.0.code()), .0.message())]
+    RpcFailed(tonic::Status),
+
+    #[error("HTTP health check of {endpoint} failed: {message}")]
+    HttpFailed { endpoint: String, message: String },
+
+    #[error("No check result within the response deadline of {0:?}")]
+    DeadlineExceeded(Duration),
+
+    #[error("Backend version {} is below the required {minimum}", found.as_deref().unwrap_or("(not reported)"))]
+    VersionTooOld {
+        found: Option<String>,
+        minimum: String,
+    },
+}
+
+impl CheckError {
+    /// Label used for the CHECK_ERRORS_TOTAL error_type dimension
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            CheckError::ConnectTimeout(_)
+            | CheckError::RpcTimeout(_)
+            | CheckError::DeadlineExceeded(_) => "timeout",
+            CheckError::ConnectFailed { .. }
+            | CheckError::ConnectBackoff { .. }
+            | CheckError::DnsResolution { .. } => "unreachable",
+            CheckError::TlsHandshake { .. } => "tls",
+            CheckError::RpcFailed(_) | CheckError::HttpFailed { .. } => "rpc_error",
+            CheckError::VersionTooOld { .. } => "version",
+            CheckError::InvalidEndpoint { .. } | CheckError::TlsConfig(_) => "unknown",
+        }
+    }
+
+    /// Label used for the CHECK_OUTCOMES_TOTAL outcome dimension
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            CheckError::ConnectTimeout(_) => "connect_timeout",
+            CheckError::RpcTimeout(_) | CheckError::DeadlineExceeded(_) => "rpc_timeout",
+            // The backend answered; it is just not one HAProxy should send traffic to
+            CheckError::VersionTooOld { .. } => "not_serving",
+            CheckError::InvalidEndpoint { .. }
+            | CheckError::DnsResolution { .. }
+            | CheckError::TlsConfig(_)
+            | CheckError::ConnectFailed { .. }
+            | CheckError::TlsHandshake { .. }
+            | CheckError::ConnectBackoff { .. }
+            | CheckError::RpcFailed(_)
+            | CheckError::HttpFailed { .. } => "transport_error",
+        }
+    }
+
+    /// Short human-readable reason for verbose agent responses
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CheckError::InvalidEndpoint { .. } => "invalid endpoint",
+            CheckError::DnsResolution { .. } => "dns resolution failed",
+            CheckError::TlsConfig(_) => "tls config error",
+            CheckError::ConnectTimeout(_) => "connect timeout",
+            CheckError::ConnectFailed { .. } => "connect failed",
+            CheckError::TlsHandshake { .. } => "tls handshake failed",
+            CheckError::ConnectBackoff { .. } => "connect backoff",
+            CheckError::RpcTimeout(_) => "rpc timeout",
+            CheckError::RpcFailed(_) => "rpc error",
+            CheckError::HttpFailed { .. } => "http error",
+            CheckError::DeadlineExceeded(_) => "response deadline exceeded",
+            CheckError::VersionTooOld { .. } => "version too old",
+        }
+    }
+}
+
+/// Returns true if any error in the source chain is an I/O timeout
+fn is_timeout_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut current = Some(error);
+    while let Some(err) = current {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>()
+            && io_err.kind() == std::io::ErrorKind::TimedOut
+        {
+            return true;
+        }
+        current = err.source();
+    }
+    false
+}
+
 // T057: BackendChannelKey struct
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BackendChannelKey {
@@ -29,35 +182,262 @@ impl From<&HealthCheckRequest> for BackendChannelKey {
     }
 }
 
+/// Last check outcome for one backend, served by the admin endpoint
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendState {
+    pub server: String,
+    pub port: u16,
+    pub ssl: bool,
+    pub status: HealthStatus,
+    pub last_check_unix_ms: u64,
+    pub last_error: Option<String>,
+}
+
+/// Status an operator pinned a backend to through the admin endpoint, reported until cleared
+#[derive(Debug, Clone, Serialize)]
+pub struct AdminOverride {
+    pub server: String,
+    pub port: u16,
+    /// Wire token of the SSL flag the override applies to
+    pub ssl: &'static str,
+    pub status: HealthStatus,
+}
+
+/// Per-backend throttle for failure logs: the first failure is logged, later ones are
+/// counted and summarized at most once per interval until the backend recovers
+struct LogThrottle {
+    interval: Duration,
+    windows: DashMap<BackendChannelKey, (Instant, u64)>,
+}
+
+impl LogThrottle {
+    fn new(interval: Duration) -> Self {
+        LogThrottle {
+            interval,
+            windows: DashMap::new(),
+        }
+    }
+
+    /// Returns Some(suppressed_count) when a failure should be logged, None to stay quiet
+    fn on_failure(&self, key: &BackendChannelKey, now: Instant) -> Option<u64> {
+        if self.interval.is_zero() {
+            return Some(0);
+        }
+
+        match self.windows.entry(key.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert((now, 0));
+                Some(0)
+            }
+            Entry::Occupied(mut entry) => {
+                let (logged_at, suppressed) = entry.get_mut();
+                if now.duration_since(*logged_at) < self.interval {
+                    *suppressed += 1;
+                    return None;
+                }
+                let count = *suppressed;
+                *logged_at = now;
+                *suppressed = 0;
+                Some(count)
+            }
+        }
+    }
+
+    fn on_success(&self, key: &BackendChannelKey) {
+        self.windows.remove(key);
+    }
+}
+
+/// A cached gRPC channel and when a check last used it, so the sweep can find idle ones
+struct CachedChannel {
+    channel: Channel,
+    last_used: Instant,
+}
+
+/// Why a channel left the cache, reported in logs and grpc_channel_evictions_total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EvictionReason {
+    /// Idle past the sweep interval and found dead by the sweep probe
+    Ttl,
+    /// Not ready when a check tried to reuse it
+    Failure,
+    /// Least recently used when max_cached_channels was reached
+    Lru,
+    /// Evicted through evict_all_channels
+    Manual,
+}
+
+impl EvictionReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            EvictionReason::Ttl => "ttl",
+            EvictionReason::Failure => "failure",
+            EvictionReason::Lru => "lru",
+            EvictionReason::Manual => "manual",
+        }
+    }
+}
+
+/// Reconnect suppression for a backend whose last connect attempt failed
+struct ConnectBackoff {
+    retry_at: Instant,
+    delay: Duration,
+}
+
+/// Result cache key: backend plus the resolved gRPC service name
+type ResultCacheKey = (BackendChannelKey, CheckProtocol, String);
+
+/// A check result kept for reuse within its freshness window
+struct CachedResult {
+    response: HealthCheckResponse,
+    checked_at: Instant,
+}
+
+/// Leadership of an in-flight check: publishes the result to waiting identical checks and
+/// unregisters the flight when dropped, including when the leader's check is cancelled
+struct InFlightCheck<'a> {
+    in_flight: &'a DashMap<ResultCacheKey, watch::Receiver<Option<HealthCheckResponse>>>,
+    key: ResultCacheKey,
+    sender: watch::Sender<Option<HealthCheckResponse>>,
+}
+
+impl Drop for InFlightCheck<'_> {
+    // Runs before `sender` is dropped, so woken followers never see this flight's entry
+    fn drop(&mut self) {
+        let ours = self.sender.subscribe();
+        self.in_flight
+            .remove_if(&self.key, |_, receiver| receiver.same_channel(&ours));
+    }
+}
+
 // T059: ChannelCache using DashMap
 pub struct GrpcHealthChecker {
-    channel_cache: Arc<DashMap<BackendChannelKey, Channel>>,
+    channel_cache: Arc<DashMap<BackendChannelKey, CachedChannel>>,
+    backend_states: DashMap<BackendChannelKey, BackendState>,
+    result_cache: DashMap<ResultCacheKey, CachedResult>,
+    /// Checks currently running; identical concurrent checks wait for the same result
+    in_flight: DashMap<ResultCacheKey, watch::Receiver<Option<HealthCheckResponse>>>,
+    error_log_throttle: LogThrottle,
+    /// Backends not to reconnect to until their backoff elapses
+    connect_backoff: DashMap<BackendChannelKey, ConnectBackoff>,
+    /// When each recovering backend last went from not-up to up, for slow start
+    recovered_at: DashMap<BackendChannelKey, Instant>,
+    /// Whether each of a backend's last success_weight_window checks was up, oldest first
+    recent_outcomes: DashMap<BackendChannelKey, VecDeque<bool>>,
+    /// Operator-set statuses answered instead of checking, until cleared
+    admin_overrides: DashMap<BackendChannelKey, HealthStatus>,
+    /// Limits checks in flight; None when max_concurrent_checks is 0
+    check_permits: Option<Semaphore>,
+    health_check_path: PathAndQuery,
+    /// Metadata key for the proxy host; None when host_header_name is empty
+    host_header: Option<MetadataKey<Ascii>>,
+    /// Response metadata key and minimum version; None when version_metadata_key is empty
+    version_gate: Option<(MetadataKey<Ascii>, Vec<u64>)>,
+    /// Response metadata key for the backend's active connections; None when load_metadata_key is empty
+    load_key: Option<MetadataKey<Ascii>>,
+    /// Resolver for the [dns] nameservers; None uses the system resolver
+    resolver: Option<TokioResolver>,
+    /// Trust anchors from tls_ca_dir for verified ssl channels
+    #[cfg(feature = "tls")]
+    tls_ca_bundle: Option<tonic::transport::Certificate>,
+    /// Probe for `check=http` requests
+    http: HttpHealthChecker,
+    clock: Arc<dyn Clock>,
     config: AgentConfig,
 }
 
 impl GrpcHealthChecker {
     pub fn new(config: AgentConfig) -> Self {
+        #[cfg(feature = "chaos")]
+        if config.chaos.is_enabled() {
+            tracing::warn!(
+                latency_probability = config.chaos.latency_probability,
+                latency_ms = config.chaos.latency_ms,
+                down_probability = config.chaos.down_probability,
+                "Fault injection is enabled; checks will be delayed or failed on purpose. Never use this in production"
+            );
+        }
+
         GrpcHealthChecker {
             channel_cache: Arc::new(DashMap::new()),
+            backend_states: DashMap::new(),
+            result_cache: DashMap::new(),
+            in_flight: DashMap::new(),
+            error_log_throttle: LogThrottle::new(Duration::from_millis(
+                config.error_log_interval_ms,
+            )),
+            connect_backoff: DashMap::new(),
+            recovered_at: DashMap::new(),
+            recent_outcomes: DashMap::new(),
+            admin_overrides: DashMap::new(),
+            check_permits: (config.max_concurrent_checks > 0)
+                .then(|| Semaphore::new(config.max_concurrent_checks)),
+            health_check_path: parse_health_check_path(&config.grpc_health_check_path)
+                .unwrap_or_else(|| PathAndQuery::from_static(DEFAULT_HEALTH_CHECK_PATH)),
+            host_header: MetadataKey::from_bytes(config.host_header_name.as_bytes()).ok(),
+            version_gate: MetadataKey::from_bytes(config.version_metadata_key.as_bytes())
+                .ok()
+                .zip(parse_version(&config.min_backend_version)),
+            load_key: MetadataKey::from_bytes(config.load_metadata_key.as_bytes()).ok(),
+            resolver: build_resolver(&config.dns),
+            #[cfg(feature = "tls")]
+            tls_ca_bundle: config.tls_ca_dir.as_deref().and_then(|dir| {
+                crate::tls::load_ca_dir(std::path::Path::new(dir))
+                    .inspect_err(|e| {
+                        tracing::error!(error = %format!("{:#}", e), "Failed to load tls_ca_dir")
+                    })
+                    .ok()
+            }),
+            http: HttpHealthChecker::new(config.clone()),
+            clock: Arc::new(SystemClock),
             config,
         }
     }
 
+    /// Read time from `clock` instead of the system clock, so tests can step it
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Pause before the unknown_retry poll: unknown_retry_delay_ms, capped by what is left
+    /// until `deadline` on the checker's clock
+    fn unknown_retry_delay(&self, deadline: Option<Instant>) -> Duration {
+        let delay = Duration::from_millis(self.config.unknown_retry_delay_ms);
+        match deadline {
+            Some(deadline) => delay.min(deadline.saturating_duration_since(self.clock.now())),
+            None => delay,
+        }
+    }
+
+    /// Time elapsed since `since` on the checker's clock
+    fn elapsed(&self, since: Instant) -> Duration {
+        self.clock.now().saturating_duration_since(since)
+    }
+
     // T060-T061: get_or_create_channel with TLS configuration
+    // `deadline` is when the check's response is due, if response_deadline_ms is set
     async fn get_or_create_channel(
         &self,
         key: &BackendChannelKey,
         proxy_host: &str,
-    ) -> Result<Channel, anyhow::Error> {
+        deadline: Option<Instant>,
+    ) -> Result<Channel, CheckError> {
         // Only use cache when channel caching is enabled
         if self.config.grpc_channel_cache_enabled {
             // Check if channel exists in cache
-            if let Some(channel) = self.channel_cache.get(key) {
-                let channel_clone = channel.clone();
-                drop(channel); // Release the DashMap lock
+            if let Some(mut cached) = self.channel_cache.get_mut(key) {
+                cached.last_used = self.clock.now();
+                let channel_clone = cached.channel.clone();
+                drop(cached); // Release the DashMap lock
+
+                let ready_timeout = Duration::from_millis(self.config.channel_ready_timeout_ms);
+                if ready_timeout.is_zero() {
+                    return Ok(channel_clone);
+                }
 
                 // Try to get the channel ready with a very short timeout
-                let ready_check = tokio::time::timeout(Duration::from_millis(10), async {
+                let ready_check = tokio::time::timeout(ready_timeout, async {
                     let mut grpc = tonic::client::Grpc::new(channel_clone.clone());
                     grpc.ready().await
                 });
@@ -67,204 +447,2053 @@ impl GrpcHealthChecker {
                 }
 
                 // Channel not ready, remove from cache
-                self.channel_cache.remove(key);
-                metrics::GRPC_CHANNELS_ACTIVE.set(self.channel_cache.len() as f64);
+                if self.channel_cache.remove(key).is_some() {
+                    self.record_eviction(key, EvictionReason::Failure);
+                }
+            }
+        }
+
+        #[cfg(not(feature = "tls"))]
+        if key.ssl_flag.is_tls() {
+            return Err(CheckError::TlsConfig(
+                "TLS support is not compiled in (build with the `tls` feature)".to_string(),
+            ));
+        }
+
+        if let Some(backoff) = self.connect_backoff.get(key) {
+            let remaining = backoff.retry_at.saturating_duration_since(self.clock.now());
+            if !remaining.is_zero() {
+                return Err(CheckError::ConnectBackoff {
+                    endpoint: format!("{}:{}", key.server, key.port),
+                    remaining,
+                });
             }
         }
 
         // T061: Create new channel with connect timeout
+        let host = self.resolve_backend_host(&key.server).await?;
         let endpoint = format!(
             "http{}://{}:{}",
-            if matches!(key.ssl_flag, SslFlag::Ssl) {
+            // Insecure TLS is done by our own connector, so tonic sees a plain endpoint
+            if key.ssl_flag == SslFlag::Ssl {
                 "s"
             } else {
                 ""
             },
-            key.server,
+            host,
             key.port
         );
 
-        let connect_timeout = Duration::from_millis(self.config.grpc_connect_timeout_ms);
+        let mut connect_timeout =
+            Duration::from_millis(self.config.connect_timeout_ms(key.ssl_flag));
+        // A cold connect may only spend what is left of the check's budget
+        if self.config.connect_within_deadline
+            && let Some(deadline) = deadline
+        {
+            let remaining = deadline.saturating_duration_since(self.clock.now());
+            if remaining.is_zero() {
+                return Err(CheckError::ConnectTimeout(endpoint));
+            }
+            if remaining < connect_timeout {
+                tracing::debug!(
+                    connect_timeout_ms = connect_timeout.as_millis() as u64,
+                    remaining_ms = remaining.as_millis() as u64,
+                    "Capping connect timeout to the remaining response deadline"
+                );
+                connect_timeout = remaining;
+            }
+        }
 
         let mut channel_builder = Channel::from_shared(endpoint.clone())
-            .map_err(|e| anyhow::anyhow!("Invalid endpoint {}: {}", endpoint, e))?
-            .connect_timeout(connect_timeout);
+            .map_err(|e| CheckError::InvalidEndpoint {
+                endpoint: endpoint.clone(),
+                message: e.to_string(),
+            })?
+            .connect_timeout(connect_timeout)
+            .buffer_size(self.config.grpc_channel.buffer_size)
+            .initial_stream_window_size(self.config.grpc_channel.initial_stream_window_size)
+            .initial_connection_window_size(
+                self.config.grpc_channel.initial_connection_window_size,
+            );
 
-        // T060: Configure TLS if needed
-        if matches!(key.ssl_flag, SslFlag::Ssl) {
-            let tls_config = ClientTlsConfig::new().domain_name(proxy_host);
+        if let Some(limit) = self.config.grpc_channel.concurrency_limit {
+            channel_builder = channel_builder.concurrency_limit(limit);
+        }
+
+        // Lets backend access logs tell health checks apart from real clients
+        if !self.config.user_agent.is_empty() {
             channel_builder = channel_builder
-                .tls_config(tls_config)
-                .map_err(|e| anyhow::anyhow!("TLS configuration failed: {}", e))?;
+                .user_agent(self.config.user_agent.as_str())
+                .map_err(|e| CheckError::InvalidEndpoint {
+                    endpoint: endpoint.clone(),
+                    message: e.to_string(),
+                })?;
+        }
+
+        // T060: Configure TLS if needed
+        if key.ssl_flag == SslFlag::Ssl {
+            #[cfg(feature = "tls")]
+            {
+                let mut tls_config = ClientTlsConfig::new().domain_name(proxy_host);
+                if let Some(bundle) = &self.tls_ca_bundle {
+                    tls_config = tls_config.ca_certificate(bundle.clone());
+                }
+                channel_builder = channel_builder
+                    .tls_config(tls_config)
+                    .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+            }
         } else {
             // For non-TLS connections, set the :authority pseudoheader using origin()
             // This is needed for Istio and other service meshes that route based on Host/authority
-            let origin_uri = format!("http://{}", proxy_host);
-            channel_builder = channel_builder.origin(
-                origin_uri
-                    .parse()
-                    .map_err(|e| anyhow::anyhow!("Invalid origin URI {}: {}", origin_uri, e))?,
-            );
+            let scheme = if key.ssl_flag.is_tls() {
+                "https"
+            } else {
+                "http"
+            };
+            let origin_uri = format!("{}://{}", scheme, proxy_host);
+            let origin = origin_uri
+                .parse::<tonic::codegen::http::Uri>()
+                .map_err(|e| CheckError::InvalidEndpoint {
+                    endpoint: origin_uri.clone(),
+                    message: e.to_string(),
+                })?;
+            channel_builder = channel_builder.origin(origin);
         }
 
         // Connect to backend
-        let channel = channel_builder
-            .connect()
-            .await
-            .map_err(|e| anyhow::anyhow!("Connection failed to {}: {}", endpoint, e))?;
+        let connected = match key.ssl_flag {
+            #[cfg(feature = "tls")]
+            SslFlag::SslInsecure => {
+                tracing::warn!(
+                    backend_server = %key.server,
+                    backend_port = key.port,
+                    "Connecting with TLS certificate verification disabled (ssl-insecure)"
+                );
+                let connector = crate::tls::insecure_tls_connector(
+                    host,
+                    key.port,
+                    proxy_host,
+                    &self.config.tls_alpn_protocols,
+                    self.config.proxy.clone(),
+                )?;
+                channel_builder.connect_with_connector(connector).await
+            }
+            _ if self.config.proxy.host.is_some() => {
+                let connector = proxy::tunnel_connector(self.config.proxy.clone(), host, key.port);
+                channel_builder.connect_with_connector(connector).await
+            }
+            _ => channel_builder.connect().await,
+        };
+        let channel = connected.map_err(|e| {
+            self.back_off_connects(key);
+            if is_timeout_error(&e) {
+                return CheckError::ConnectTimeout(endpoint.clone());
+            }
+            #[cfg(feature = "tls")]
+            if let Some(failure) = crate::tls::handshake_failure(&e) {
+                tracing::warn!(
+                    backend_server = %key.server,
+                    backend_port = key.port,
+                    ssl = key.ssl_flag.as_token(),
+                    tls_error = %failure.reason,
+                    expected_name = failure.expected_name.as_deref(),
+                    presented_names = ?failure.presented_names,
+                    cert_not_before = failure.not_before,
+                    cert_not_after = failure.not_after,
+                    "TLS handshake failed"
+                );
+                return CheckError::TlsHandshake {
+                    endpoint: endpoint.clone(),
+                    message: failure.reason,
+                };
+            }
+            CheckError::ConnectFailed {
+                endpoint: endpoint.clone(),
+                message: e.to_string(),
+            }
+        })?;
+
+        self.connect_backoff.remove(key);
 
         // Only cache channel and update metric when caching is enabled
         if self.config.grpc_channel_cache_enabled {
-            self.channel_cache.insert(key.clone(), channel.clone());
+            self.make_room_in_channel_cache(key);
+            self.channel_cache.insert(
+                key.clone(),
+                CachedChannel {
+                    channel: channel.clone(),
+                    last_used: self.clock.now(),
+                },
+            );
             // T127: Update GRPC_CHANNELS_ACTIVE gauge
+            #[cfg(feature = "metrics")]
             metrics::GRPC_CHANNELS_ACTIVE.set(self.channel_cache.len() as f64);
         }
 
         Ok(channel)
     }
 
-    // T063: check_backend function
-    pub async fn check_backend(&self, request: &HealthCheckRequest) -> HealthCheckResponse {
-        // T066: Error handling - all errors map to Down status
-        match self.check_backend_internal(request).await {
-            Ok(status) => HealthCheckResponse::new(status),
-            Err(e) => {
-                // T125: Increment CHECK_ERRORS_TOTAL with error_type label
-                let error_str = e.to_string();
-                let error_type = if error_str.contains("timeout") {
-                    "timeout"
-                } else if error_str.contains("Connection failed")
-                    || error_str.contains("unreachable")
-                {
-                    "unreachable"
-                } else if error_str.contains("RPC failed") {
-                    "rpc_error"
-                } else {
-                    "unknown"
-                };
-
-                metrics::CHECK_ERRORS_TOTAL
-                    .with_label_values(&[error_type])
-                    .inc();
+    /// Evict least recently used channels until one more fits within max_cached_channels.
+    /// Concurrent connects may briefly overshoot the limit by a channel each.
+    fn make_room_in_channel_cache(&self, key: &BackendChannelKey) {
+        let max = self.config.max_cached_channels;
+        if max == 0 || self.channel_cache.contains_key(key) {
+            return;
+        }
 
-                tracing::error!(
-                    backend = %format!("{}:{}", request.backend_server, request.backend_port),
-                    error = %e,
-                    error_type = %error_type,
-                    "Health check failed"
-                );
-                HealthCheckResponse::new(HealthStatus::Down)
+        while self.channel_cache.len() >= max {
+            let Some(lru) = self
+                .channel_cache
+                .iter()
+                .min_by_key(|entry| entry.last_used)
+                .map(|entry| entry.key().clone())
+            else {
+                break;
+            };
+            if self.channel_cache.remove(&lru).is_some() {
+                self.record_eviction(&lru, EvictionReason::Lru);
             }
         }
     }
 
-    async fn check_backend_internal(
-        &self,
-        request: &HealthCheckRequest,
-    ) -> Result<HealthStatus, anyhow::Error> {
-        let key = BackendChannelKey::from(request);
-
-        // Get or create channel
-        let channel = self
-            .get_or_create_channel(&key, &request.proxy_host_name)
-            .await?;
+    /// Log and count a channel that has just been removed from the cache
+    fn record_eviction(&self, key: &BackendChannelKey, reason: EvictionReason) {
+        tracing::debug!(
+            backend_server = %key.server,
+            backend_port = key.port,
+            reason = reason.as_str(),
+            "Evicted cached channel"
+        );
+        #[cfg(feature = "metrics")]
+        {
+            metrics::GRPC_CHANNEL_EVICTIONS_TOTAL
+                .with_label_values(&[reason.as_str()])
+                .inc();
+            metrics::GRPC_CHANNELS_ACTIVE.set(self.channel_cache.len() as f64);
+        }
+    }
 
-        // T062-T064: Create gRPC Health Check client with timeout
-        let rpc_timeout = Duration::from_millis(self.config.grpc_rpc_timeout_ms);
+    /// Start or double the reconnect backoff for a backend after a failed connect,
+    /// capped at connect_backoff_max_ms. A no-op when connect_backoff_ms is 0.
+    /// With full jitter the backend is skipped for a random part of the doubled delay; the
+    /// delay itself keeps doubling, so the expected wait still grows.
+    fn back_off_connects(&self, key: &BackendChannelKey) {
+        let initial = Duration::from_millis(self.config.connect_backoff_ms);
+        if initial.is_zero() {
+            return;
+        }
+        let max = Duration::from_millis(self.config.connect_backoff_max_ms);
 
-        // Import the gRPC health checking protocol types
-        use tonic::Request as TonicRequest;
+        let delay = match self.connect_backoff.get(key) {
+            Some(backoff) => (backoff.delay * 2).min(max),
+            None => initial.min(max),
+        };
+        let wait = match self.config.connect_backoff_jitter {
+            BackoffJitter::None => delay,
+            BackoffJitter::Full => delay.mul_f64(fastrand::f64()),
+        };
+        tracing::debug!(
+            backend_server = %key.server,
+            backend_port = key.port,
+            backoff_ms = wait.as_millis() as u64,
+            "Backing off reconnects after failed connect"
+        );
+        self.connect_backoff.insert(
+            key.clone(),
+            ConnectBackoff {
+                retry_at: self.clock.now() + wait,
+                delay,
+            },
+        );
+    }
 
-        // Create health check request
-        // Note: :authority pseudoheader is set at channel level via origin() or TLS domain_name()
-        let health_request = TonicRequest::new(HealthCheckRequestGrpc {
-            service: String::new(), // Empty string means overall server health
-        });
+    /// Resolves a hostname backend through the configured nameservers.
+    /// IP backends, and every backend when no nameservers are configured, pass through unchanged.
+    async fn resolve_backend_host(&self, server: &str) -> Result<String, CheckError> {
+        let Some(resolver) = &self.resolver else {
+            return Ok(server.to_string());
+        };
 
-        // Create client with timeout
-        let mut client =
-            health_client::HealthClient::new(channel).max_decoding_message_size(usize::MAX);
+        let unbracketed = server.trim_start_matches('[').trim_end_matches(']');
+        if unbracketed.parse::<IpAddr>().is_ok() {
+            return Ok(server.to_string());
+        }
 
-        // T064: Call with timeout
-        let response = tokio::time::timeout(rpc_timeout, client.check(health_request))
-            .await
-            .map_err(|_| anyhow::anyhow!("Health check RPC timeout after {:?}", rpc_timeout))?
-            .map_err(|e| anyhow::anyhow!("Health check RPC failed: {}", e))?;
-
-        // T065: Map ServingStatus to HealthStatus
-        let serving_status = response.into_inner().status;
-        let health_status = match serving_status {
-            0 => HealthStatus::Down, // UNKNOWN
-            1 => HealthStatus::Up,   // SERVING
-            2 => HealthStatus::Down, // NOT_SERVING
-            3 => HealthStatus::Down, // SERVICE_UNKNOWN
-            _ => HealthStatus::Down, // Unknown status code
+        let dns_error = |message: String| CheckError::DnsResolution {
+            host: server.to_string(),
+            message,
         };
+        let lookup = resolver
+            .lookup_ip(server)
+            .await
+            .map_err(|e| dns_error(e.to_string()))?;
+        let ip = lookup
+            .iter()
+            .next()
+            .ok_or_else(|| dns_error("no addresses returned".to_string()))?;
+
+        tracing::debug!(host = %server, ip = %ip, "Resolved backend host");
 
-        Ok(health_status)
+        Ok(match ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        })
     }
-}
 
-// gRPC Health Check Protocol types
-// Based on: https://github.com/grpc/grpc-proto/blob/master/grpc/health/v1/health.proto
+    /// Apply [chaos] fault injection: maybe sleep, then maybe answer `down` instead of checking.
+    /// Injected answers are not recorded as backend state.
+    #[cfg(feature = "chaos")]
+    async fn inject_fault(&self) -> Option<HealthCheckResponse> {
+        let chaos = &self.config.chaos;
 
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealthCheckRequestGrpc {
-    #[prost(string, tag = "1")]
-    pub service: String,
-}
+        if chaos.latency_probability > 0.0 && fastrand::f64() < chaos.latency_probability {
+            tracing::debug!(latency_ms = chaos.latency_ms, "Injecting check latency");
+            tokio::time::sleep(Duration::from_millis(chaos.latency_ms)).await;
+        }
 
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealthCheckResponseGrpc {
-    #[prost(enumeration = "ServingStatus", tag = "1")]
-    pub status: i32,
-}
+        if chaos.down_probability > 0.0 && fastrand::f64() < chaos.down_probability {
+            tracing::debug!("Injecting down response");
+            return Some(
+                HealthCheckResponse::new(HealthStatus::Down).with_reason("injected fault"),
+            );
+        }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-#[repr(i32)]
-pub enum ServingStatus {
-    Unknown = 0,
-    Serving = 1,
-    NotServing = 2,
-    ServiceUnknown = 3,
-}
+        None
+    }
 
-// gRPC Health service client
-pub mod health_client {
-    use super::*;
-    use tonic::codegen::*;
+    /// Key for per-backend state (cached results, last status, log throttling).
+    /// Hostnames are lowercased unless channel_key_mode is exact.
+    fn backend_key(&self, request: &HealthCheckRequest) -> BackendChannelKey {
+        self.normalize_key(BackendChannelKey::from(request))
+    }
 
-    #[derive(Debug, Clone)]
-    pub struct HealthClient<T> {
-        inner: tonic::client::Grpc<T>,
+    fn normalize_key(&self, mut key: BackendChannelKey) -> BackendChannelKey {
+        if self.config.channel_key_mode != ChannelKeyMode::Exact {
+            key.server.make_ascii_lowercase();
+        }
+        key
     }
 
-    impl HealthClient<Channel> {
-        pub fn new(channel: Channel) -> Self {
-            let inner = tonic::client::Grpc::new(channel);
-            Self { inner }
+    /// Key for the channel cache; in resolved mode the server is replaced by its IP
+    async fn channel_key(
+        &self,
+        request: &HealthCheckRequest,
+    ) -> Result<BackendChannelKey, CheckError> {
+        let mut key = self.backend_key(request);
+        if self.config.channel_key_mode == ChannelKeyMode::Resolved {
+            key.server = self.resolve_to_ip(&key.server).await?;
         }
+        Ok(key)
+    }
 
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.inner = self.inner.max_decoding_message_size(limit);
-            self
+    /// Like resolve_backend_host, but falls back to the system resolver instead of passing through
+    async fn resolve_to_ip(&self, server: &str) -> Result<String, CheckError> {
+        if self.resolver.is_some() {
+            return self.resolve_backend_host(server).await;
         }
 
-        pub async fn check(
-            &mut self,
-            request: tonic::Request<HealthCheckRequestGrpc>,
-        ) -> Result<tonic::Response<HealthCheckResponseGrpc>, tonic::Status> {
-            self.inner.ready().await.map_err(|e| {
-                tonic::Status::new(
-                    tonic::Code::Unknown,
-                    format!("Service was not ready: {}", e),
-                )
-            })?;
+        let unbracketed = server.trim_start_matches('[').trim_end_matches(']');
+        if unbracketed.parse::<IpAddr>().is_ok() {
+            return Ok(server.to_string());
+        }
 
-            let codec = ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static("/grpc.health.v1.Health/Check");
+        let dns_error = |message: String| CheckError::DnsResolution {
+            host: server.to_string(),
+            message,
+        };
+        let addr = tokio::net::lookup_host((server, 0))
+            .await
+            .map_err(|e| dns_error(e.to_string()))?
+            .next()
+            .ok_or_else(|| dns_error("no addresses returned".to_string()))?;
+
+        Ok(match addr.ip() {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        })
+    }
+
+    /// Number of gRPC channels currently cached
+    pub fn cached_channel_count(&self) -> usize {
+        self.channel_cache.len()
+    }
+
+    /// Snapshot of the backends that currently have a cached channel, in no particular order
+    pub fn cached_channel_keys(&self) -> Vec<BackendChannelKey> {
+        self.channel_cache
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// Drop every cached channel so the next check of each backend reconnects.
+    /// Returns how many channels were evicted.
+    pub fn evict_all_channels(&self) -> usize {
+        let keys: Vec<BackendChannelKey> = self
+            .channel_cache
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        keys.iter()
+            .filter(|key| self.channel_cache.remove(*key).is_some())
+            .inspect(|key| self.record_eviction(key, EvictionReason::Manual))
+            .count()
+    }
+
+    /// Start the background channel sweep when channel_sweep_interval_ms is set.
+    /// The task holds a weak reference and exits once the checker is dropped.
+    pub fn spawn_channel_sweep(self: &Arc<Self>) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = Duration::from_millis(self.config.channel_sweep_interval_ms);
+        if interval.is_zero() || !self.config.grpc_channel_cache_enabled {
+            return None;
+        }
+
+        let checker = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            loop {
+                // ±20% jitter keeps sweeps of several agents from lining up
+                tokio::time::sleep(interval.mul_f64(0.8 + fastrand::f64() * 0.4)).await;
+                let Some(checker) = checker.upgrade() else {
+                    break;
+                };
+                checker.sweep_idle_channels(interval).await;
+            }
+        }))
+    }
+
+    /// Probe channels unused for at least `idle_after` and evict those whose connection is dead.
+    /// Only timeouts and UNAVAILABLE count as dead: any other answer, even an RPC error,
+    /// proves the connection still works.
+    async fn sweep_idle_channels(&self, idle_after: Duration) {
+        let idle: Vec<(BackendChannelKey, Channel)> = self
+            .channel_cache
+            .iter()
+            .filter(|entry| self.elapsed(entry.last_used) >= idle_after)
+            .map(|entry| (entry.key().clone(), entry.channel.clone()))
+            .collect();
+
+        for (key, channel) in idle {
+            let dead = match self
+                .call_health_check(channel, &self.config.default_grpc_service, None)
+                .await
+                .map(|(serving_status, _)| serving_status)
+            {
+                Err(CheckError::RpcTimeout(_)) => true,
+                Err(CheckError::RpcFailed(status)) => status.code() == tonic::Code::Unavailable,
+                _ => false,
+            };
+            if !dead {
+                continue;
+            }
 
-            self.inner.unary(request, path, codec).await
+            // A check may have replaced or used the channel while the probe ran
+            if self
+                .channel_cache
+                .remove_if(&key, |_, cached| {
+                    self.elapsed(cached.last_used) >= idle_after
+                })
+                .is_some()
+            {
+                self.record_eviction(&key, EvictionReason::Ttl);
+            }
+        }
+    }
+
+    /// Answer `status` for a backend instead of checking it, until the override is cleared
+    pub fn set_admin_override(
+        &self,
+        server: &str,
+        port: u16,
+        ssl_flag: SslFlag,
+        status: HealthStatus,
+    ) {
+        let key = self.normalize_key(BackendChannelKey {
+            server: server.to_string(),
+            port,
+            ssl_flag,
+        });
+        tracing::info!(
+            backend_server = %key.server,
+            backend_port = key.port,
+            ssl = ssl_flag.as_token(),
+            status = status.as_token(),
+            "Admin override set"
+        );
+        self.admin_overrides.insert(key, status);
+    }
+
+    /// Resume checking a backend. Returns false when it had no override.
+    pub fn clear_admin_override(&self, server: &str, port: u16, ssl_flag: SslFlag) -> bool {
+        let key = self.normalize_key(BackendChannelKey {
+            server: server.to_string(),
+            port,
+            ssl_flag,
+        });
+        let cleared = self.admin_overrides.remove(&key).is_some();
+        if cleared {
+            tracing::info!(
+                backend_server = %key.server,
+                backend_port = key.port,
+                ssl = ssl_flag.as_token(),
+                "Admin override cleared"
+            );
         }
+        cleared
+    }
+
+    /// Every admin override in place, sorted by address
+    pub fn admin_overrides(&self) -> Vec<AdminOverride> {
+        let mut overrides: Vec<AdminOverride> = self
+            .admin_overrides
+            .iter()
+            .map(|entry| AdminOverride {
+                server: entry.key().server.clone(),
+                port: entry.key().port,
+                ssl: entry.key().ssl_flag.as_token(),
+                status: *entry.value(),
+            })
+            .collect();
+        overrides.sort_by(|a, b| (&a.server, a.port, a.ssl).cmp(&(&b.server, b.port, b.ssl)));
+        overrides
+    }
+
+    /// Snapshot of the last check result for every backend seen so far, sorted by address
+    pub fn backend_states(&self) -> Vec<BackendState> {
+        let mut states: Vec<BackendState> = self
+            .backend_states
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        states.sort_by(|a, b| (&a.server, a.port, a.ssl).cmp(&(&b.server, b.port, b.ssl)));
+        states
+    }
+
+    fn record_backend_state(
+        &self,
+        request: &HealthCheckRequest,
+        status: HealthStatus,
+        last_error: Option<String>,
+    ) {
+        let last_check_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        let previous = self.backend_states.insert(
+            self.backend_key(request),
+            BackendState {
+                server: request.backend_server.clone(),
+                port: request.backend_port,
+                ssl: request.ssl_flag.is_tls(),
+                status,
+                last_check_unix_ms,
+                last_error,
+            },
+        );
+
+        // Transitions are observed when a check runs, so they lag the backend by at most one poll
+        if let Some(previous) = previous
+            && previous.status != status
+        {
+            let backend = format!("{}:{}", request.backend_server, request.backend_port);
+            tracing::info!(
+                backend = %backend,
+                from = %previous.status,
+                to = %status,
+                "Backend status changed"
+            );
+            #[cfg(feature = "metrics")]
+            metrics::BACKEND_STATUS_TRANSITIONS_TOTAL
+                .with_label_values(&[
+                    backend.as_str(),
+                    previous.status.as_token(),
+                    status.as_token(),
+                ])
+                .inc();
+        }
+    }
+
+    /// Configured status for a backend refusing the check with RESOURCE_EXHAUSTED, which means
+    /// it is shedding load rather than down; None for every other error
+    fn shed_status(&self, error: &CheckError) -> Option<HealthStatus> {
+        match error {
+            CheckError::RpcFailed(status) if status.code() == tonic::Code::ResourceExhausted => {
+                self.config.resource_exhausted_status
+            }
+            _ => None,
+        }
+    }
+
+    /// Response for backends matching force_down_backends or force_up_backends
+    fn forced_response(&self, request: &HealthCheckRequest) -> Option<HealthCheckResponse> {
+        let matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| {
+                backend_matches(pattern, &request.backend_server, request.backend_port)
+            })
+        };
+
+        if matches(&self.config.force_down_backends) {
+            Some(HealthCheckResponse::new(HealthStatus::Down).with_reason("forced down"))
+        } else if matches(&self.config.force_up_backends) {
+            Some(HealthCheckResponse::new(HealthStatus::Up))
+        } else {
+            None
+        }
+    }
+
+    /// Weight percentage while a recovered backend is within its slow-start window.
+    /// The ramp starts when a backend goes from not-up to up; backends first seen up get full weight.
+    fn slow_start_weight(&self, key: &BackendChannelKey, status: HealthStatus) -> Option<u8> {
+        let window = Duration::from_millis(self.config.slow_start_ms);
+        if window.is_zero() {
+            return None;
+        }
+
+        if status != HealthStatus::Up {
+            self.recovered_at.remove(key);
+            return None;
+        }
+
+        let previous = self.backend_states.get(key).map(|state| state.status);
+        if previous.is_some_and(|previous| previous != HealthStatus::Up) {
+            self.recovered_at.insert(key.clone(), self.clock.now());
+        }
+
+        let elapsed = self.elapsed(*self.recovered_at.get(key)?);
+        if elapsed >= window {
+            self.recovered_at.remove(key);
+            return None;
+        }
+
+        Some(slow_start_percent(elapsed, window))
+    }
+
+    /// Weight percentage from the share of a backend's recent checks that were up, counting this
+    /// one. None while it is not up, and once its whole window is up, so it gets full weight.
+    fn success_weight(&self, key: &BackendChannelKey, status: HealthStatus) -> Option<u8> {
+        let window = self.config.success_weight_window;
+        if window == 0 {
+            return None;
+        }
+
+        let mut outcomes = self.recent_outcomes.entry(key.clone()).or_default();
+        if outcomes.len() >= window {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(status == HealthStatus::Up);
+
+        let successes = outcomes.iter().filter(|up| **up).count();
+        if status != HealthStatus::Up || successes == outcomes.len() {
+            return None;
+        }
+        Some(success_percent(
+            successes,
+            outcomes.len(),
+            self.config.success_weight_min_percent,
+        ))
+    }
+
+    /// True for a failure of a backend no check has recorded yet, when first_failure_response
+    /// holds those back. Must run before record_backend_state records the backend.
+    fn is_first_failure(&self, key: &BackendChannelKey, status: HealthStatus) -> bool {
+        self.config.first_failure_response != FirstFailureResponse::Down
+            && status == HealthStatus::Down
+            && !self.backend_states.contains_key(key)
+    }
+
+    fn hold_back_first_failure(&self, response: HealthCheckResponse) -> HealthCheckResponse {
+        tracing::info!(
+            response = ?self.config.first_failure_response,
+            reason = response.reason,
+            "Holding back first failure of newly seen backend"
+        );
+        match self.config.first_failure_response {
+            FirstFailureResponse::Down => response,
+            FirstFailureResponse::Up => {
+                HealthCheckResponse::new(HealthStatus::Up).with_reason(FIRST_FAILURE_REASON)
+            }
+            FirstFailureResponse::Noop => response.into_noop(),
+        }
+    }
+
+    /// How long a result may be reused: half the request's interval hint, so every HAProxy
+    /// poll sees a result younger than its interval, or the configured result_cache_ttl_ms
+    fn result_freshness(&self, request: &HealthCheckRequest) -> Duration {
+        match request.interval_hint {
+            Some(interval) => interval / 2,
+            None => Duration::from_millis(self.config.result_cache_ttl_ms),
+        }
+    }
+
+    /// Returns the gRPC health service name to check for a request.
+    /// An empty name means overall server health.
+    fn resolve_service<'a>(&'a self, request: &'a HealthCheckRequest) -> &'a str {
+        if self.check_protocol(request) == CheckProtocol::Http {
+            return self.http.resolve_path(request);
+        }
+        request
+            .service
+            .as_deref()
+            .unwrap_or(&self.config.default_grpc_service)
+    }
+
+    fn check_protocol(&self, request: &HealthCheckRequest) -> CheckProtocol {
+        request.check_protocol.unwrap_or(self.config.check_protocol)
+    }
+
+    /// Check a backend, honouring admin overrides, the result cache and in-flight coalescing.
+    /// Every event emitted during the check inherits the backend fields of its `grpc_check` span.
+    ///
+    /// # Cancellation safety
+    ///
+    /// The returned future may be dropped at any await point, e.g. when HAProxy hangs up or a
+    /// caller's timeout fires. A dropped check leaves no state behind: the channel cache only
+    /// gains a channel once its connect has completed, the result cache and backend state are
+    /// only written after the check finishes, and a check leading a coalesced flight
+    /// unregisters it, so one of the checks waiting on it takes over instead of waiting
+    /// forever. A dropped check never poisons the channel it was using.
+    #[tracing::instrument(
+        name = "grpc_check",
+        skip_all,
+        fields(
+            backend_server = %request.backend_server,
+            backend_port = request.backend_port,
+            ssl = ?request.ssl_flag,
+            service = %self.resolve_service(request),
+        )
+    )]
+    pub async fn check_backend(&self, request: &HealthCheckRequest) -> HealthCheckResponse {
+        if let Some(status) = self
+            .admin_overrides
+            .get(&self.backend_key(request))
+            .map(|status| *status)
+        {
+            tracing::debug!(
+                backend_server = %request.backend_server,
+                backend_port = request.backend_port,
+                status = status.as_token(),
+                "Answering with admin override"
+            );
+            return HealthCheckResponse::new(status).with_reason("admin override");
+        }
+
+        // Applied outside the result cache, which requests with different weights share
+        apply_requested_weight(self.check_backend_bounded(request).await, request.weight)
+    }
+
+    /// The check, answered down once response_deadline_ms passes without a result
+    async fn check_backend_bounded(&self, request: &HealthCheckRequest) -> HealthCheckResponse {
+        let deadline = Duration::from_millis(self.config.response_deadline_ms);
+        if deadline.is_zero() {
+            return self.check_backend_within_deadline(request, None).await;
+        }
+
+        let due = self.clock.now() + deadline;
+        match tokio::time::timeout(
+            deadline,
+            self.check_backend_within_deadline(request, Some(due)),
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err(_) => {
+                let e = CheckError::DeadlineExceeded(deadline);
+                #[cfg(feature = "metrics")]
+                {
+                    metrics::CHECK_ERRORS_TOTAL
+                        .with_label_values(&[e.error_type()])
+                        .inc();
+                    metrics::CHECK_OUTCOMES_TOTAL
+                        .with_label_values(&[e.outcome()])
+                        .inc();
+                }
+                let key = self.backend_key(request);
+                // Throttled like every other failure, so a backend that keeps timing out
+                // doesn't log on every poll
+                if let Some(suppressed) = self.error_log_throttle.on_failure(&key, self.clock.now())
+                {
+                    tracing::error!(
+                        backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                        error = %e,
+                        error_type = %e.error_type(),
+                        suppressed,
+                        "Health check failed"
+                    );
+                }
+                let reason = e.reason();
+                // Timing out is how a degrading backend usually fails, so it counts against
+                // success_weight_window like any other failure
+                self.success_weight(&key, HealthStatus::Down);
+                let first_failure = self.is_first_failure(&key, HealthStatus::Down);
+                self.record_backend_state(request, HealthStatus::Down, Some(e.to_string()));
+                let response = HealthCheckResponse::new(HealthStatus::Down).with_reason(reason);
+                if first_failure {
+                    return self.hold_back_first_failure(response);
+                }
+                response
+            }
+        }
+    }
+
+    /// Everything response_deadline_ms bounds: overrides, cache lookup and the check itself
+    async fn check_backend_within_deadline(
+        &self,
+        request: &HealthCheckRequest,
+        deadline: Option<Instant>,
+    ) -> HealthCheckResponse {
+        // Operator overrides short-circuit before any cache lookup or RPC
+        if let Some(response) = self.forced_response(request) {
+            tracing::debug!(status = ?response.status, "Backend status forced by override list");
+            return response;
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(response) = self.inject_fault().await {
+            return response;
+        }
+
+        let freshness = self.result_freshness(request);
+        let cache_key = (
+            self.backend_key(request),
+            self.check_protocol(request),
+            self.resolve_service(request).to_string(),
+        );
+
+        if !freshness.is_zero()
+            && let Some(cached) = self.result_cache.get(&cache_key)
+            && self.elapsed(cached.checked_at) < freshness
+        {
+            tracing::debug!(status = ?cached.response.status, "Reusing cached check result");
+            return cached.response.clone();
+        }
+
+        let flight = loop {
+            match self.in_flight.entry(cache_key.clone()) {
+                Entry::Occupied(entry) => {
+                    let mut receiver = entry.get().clone();
+                    drop(entry); // Release the DashMap lock while waiting
+                    #[cfg(feature = "metrics")]
+                    let waiting_since = self.clock.now();
+                    let joined = receiver.wait_for(Option::is_some).await;
+                    #[cfg(feature = "metrics")]
+                    metrics::CHECK_QUEUE_WAIT_SECONDS
+                        .observe(self.elapsed(waiting_since).as_secs_f64());
+                    if let Ok(response) = joined
+                        && let Some(response) = response.as_ref()
+                    {
+                        tracing::debug!(status = ?response.status, "Joined in-flight check");
+                        return response.clone();
+                    }
+                    // The leading check was cancelled before finishing; the first waiter to
+                    // get here leads the next flight and the rest join it
+                    tracing::debug!("In-flight check was cancelled, checking again");
+                }
+                Entry::Vacant(entry) => {
+                    let (sender, receiver) = watch::channel(None);
+                    entry.insert(receiver);
+                    break InFlightCheck {
+                        in_flight: &self.in_flight,
+                        key: cache_key.clone(),
+                        sender,
+                    };
+                }
+            }
+        };
+
+        let mut last_error = None;
+        let mut shed_weight = None;
+        // A persistent misconfiguration, so its warning shares the failure log throttle
+        let mut service_unknown = false;
+
+        let mut result = self.check_backend_internal(request, deadline).await;
+        if self.config.unknown_retry && matches!(result, Ok((ServingStatus::Unknown, _))) {
+            let delay = self.unknown_retry_delay(deadline);
+            tracing::debug!(
+                delay_ms = delay.as_millis() as u64,
+                "Backend answered UNKNOWN, polling once more"
+            );
+            tokio::time::sleep(delay).await;
+            result = self.check_backend_internal(request, deadline).await;
+        }
+        let (result, load_weight) = match result {
+            Ok((serving_status, load_weight)) => (Ok(serving_status), load_weight),
+            Err(e) => (Err(e), None),
+        };
+        // An HTTP answer has no ServingStatus of its own to report
+        let serving_status_name = result
+            .as_ref()
+            .ok()
+            .filter(|_| self.check_protocol(request) == CheckProtocol::Grpc)
+            .copied()
+            .map(serving_status_name);
+
+        #[cfg(feature = "metrics")]
+        metrics::CHECK_OUTCOMES_TOTAL
+            .with_label_values(&[match &result {
+                Ok(ServingStatus::Serving) => "healthy",
+                Ok(_) => "not_serving",
+                Err(e) => e.outcome(),
+            }])
+            .inc();
+
+        // T066: Error handling - all errors map to Down status
+        let (status, reason) = match result {
+            // A named service the backend doesn't know is usually a config typo, not an outage
+            Ok(ServingStatus::ServiceUnknown) if !self.resolve_service(request).is_empty() => {
+                service_unknown = true;
+                if let Some(suppressed) = self
+                    .error_log_throttle
+                    .on_failure(&cache_key.0, self.clock.now())
+                {
+                    tracing::warn!(
+                        backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                        service = %self.resolve_service(request),
+                        suppressed,
+                        "Requested service not registered on backend"
+                    );
+                }
+                (
+                    self.config.service_unknown_status,
+                    serving_status_reason(ServingStatus::ServiceUnknown),
+                )
+            }
+            Ok(ServingStatus::Unknown) => (
+                self.config.unknown_status,
+                serving_status_reason(ServingStatus::Unknown),
+            ),
+            Ok(serving_status) => (
+                map_serving_status(serving_status),
+                serving_status_reason(serving_status),
+            ),
+            Err(e) if let Some(shed_status) = self.shed_status(&e) => {
+                // Shedding tends to hit the whole fleet at once, so it is throttled like failures
+                if let Some(suppressed) = self
+                    .error_log_throttle
+                    .on_failure(&cache_key.0, self.clock.now())
+                {
+                    tracing::warn!(
+                        backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                        error = %e,
+                        status = ?shed_status,
+                        suppressed,
+                        "Backend is shedding load"
+                    );
+                }
+                #[cfg(feature = "metrics")]
+                metrics::CHECK_ERRORS_TOTAL
+                    .with_label_values(&[e.error_type()])
+                    .inc();
+                shed_weight = self.config.resource_exhausted_weight;
+                last_error = Some(e.to_string());
+                (shed_status, Some("resource exhausted"))
+            }
+            Err(e) => {
+                // T125: Increment CHECK_ERRORS_TOTAL with error_type label
+                let error_type = e.error_type();
+
+                #[cfg(feature = "metrics")]
+                metrics::CHECK_ERRORS_TOTAL
+                    .with_label_values(&[error_type])
+                    .inc();
+
+                // During an outage only the first failure and periodic summaries are logged
+                if let Some(suppressed) = self
+                    .error_log_throttle
+                    .on_failure(&cache_key.0, self.clock.now())
+                {
+                    let status = match &e {
+                        CheckError::RpcFailed(status) => Some(status),
+                        _ => None,
+                    };
+                    tracing::error!(
+                        backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                        error = %e,
+                        error_type = %error_type,
+                        grpc_code = status.map(|s| grpc_code_name(s.code())),
+                        grpc_message = status.map(|s| s.message()),
+                        grpc_details = status.and_then(status_details_summary),
+                        suppressed,
+                        "Health check failed"
+                    );
+                }
+                last_error = Some(e.to_string());
+                (HealthStatus::Down, Some(e.reason()))
+            }
+        };
+
+        if last_error.is_none() && !service_unknown {
+            self.error_log_throttle.on_success(&cache_key.0);
+        }
+        // Must run before record_backend_state overwrites the previous status
+        // A backend that is ramping, loaded or flaky at once gets the lowest of those weights
+        let weight = shed_weight.or([
+            self.slow_start_weight(&cache_key.0, status),
+            load_weight,
+            self.success_weight(&cache_key.0, status),
+        ]
+        .into_iter()
+        .flatten()
+        .min());
+        let first_failure = self.is_first_failure(&cache_key.0, status);
+        self.record_backend_state(request, status, last_error);
+
+        // The reason is always attached; the server decides whether to send it
+        let mut response = HealthCheckResponse::new(status);
+        if let Some(reason) = reason {
+            response = response.with_reason(reason);
+        }
+        if let Some(weight) = weight {
+            response = response.with_weight(weight);
+        }
+        if let Some(name) = serving_status_name {
+            response = response.with_serving_status(name);
+        }
+
+        // A held-back failure isn't cached, so the next poll checks again
+        if first_failure {
+            response = self.hold_back_first_failure(response);
+        } else if !freshness.is_zero() {
+            self.result_cache.insert(
+                cache_key,
+                CachedResult {
+                    response: response.clone(),
+                    checked_at: self.clock.now(),
+                },
+            );
+        }
+
+        flight.sender.send_replace(Some(response.clone()));
+
+        response
+    }
+
+    /// Check RPC for one request: the serving status and any weight derived from reported load
+    async fn check_backend_internal(
+        &self,
+        request: &HealthCheckRequest,
+        deadline: Option<Instant>,
+    ) -> Result<(ServingStatus, Option<u8>), CheckError> {
+        // HTTP checks open a connection per check, so channel caching and backoff don't apply
+        if self.check_protocol(request) == CheckProtocol::Http {
+            let host = self.resolve_backend_host(&request.backend_server).await?;
+            let code = self.http.probe(&host, request).await?;
+            let serving_status = if crate::http_checker::is_success(code) {
+                ServingStatus::Serving
+            } else {
+                ServingStatus::NotServing
+            };
+            return Ok((serving_status, None));
+        }
+
+        let key = self.channel_key(request).await?;
+
+        // Get or create channel
+        let channel = self
+            .get_or_create_channel(&key, &request.proxy_host_name, deadline)
+            .await?;
+
+        let result = self
+            .call_health_check(
+                channel,
+                self.resolve_service(request),
+                Some(&request.proxy_host_name),
+            )
+            .await;
+
+        #[cfg(feature = "metrics")]
+        if let Some(code) = rpc_code(&result) {
+            metrics::GRPC_STATUS_CODES_TOTAL
+                .with_label_values(&[grpc_code_name(code)])
+                .inc();
+        }
+
+        result
+    }
+
+    // T062-T064: Health Check RPC over a channel, bounded by grpc_rpc_timeout_ms
+    async fn call_health_check(
+        &self,
+        channel: Channel,
+        service: &str,
+        proxy_host: Option<&str>,
+    ) -> Result<(ServingStatus, Option<u8>), CheckError> {
+        let rpc_timeout = Duration::from_millis(self.config.grpc_rpc_timeout_ms);
+
+        // Create health check request
+        // Note: :authority pseudoheader is set at channel level via origin() or TLS domain_name()
+        let host_header = self.host_header.as_ref().zip(proxy_host);
+        let health_request = build_health_request(service, host_header);
+
+        // Create client with timeout
+        let mut client = health_client::HealthClient::new(channel)
+            .with_path(self.health_check_path.clone())
+            .max_decoding_message_size(usize::MAX);
+
+        // T064: Call with timeout
+        let response = tokio::time::timeout(rpc_timeout, client.check(health_request))
+            .await
+            .map_err(|_| CheckError::RpcTimeout(rpc_timeout))?
+            .map_err(CheckError::RpcFailed)?;
+
+        // tonic merges trailers into the metadata of unary responses
+        let (metadata, message, _) = response.into_parts();
+
+        // Unrecognized status codes are treated like UNKNOWN
+        let serving_status =
+            ServingStatus::try_from(message.status).unwrap_or(ServingStatus::Unknown);
+
+        tracing::debug!(serving_status = ?serving_status, "gRPC health check RPC completed");
+
+        if serving_status == ServingStatus::Serving
+            && let Some((key, minimum)) = &self.version_gate
+        {
+            let found = metadata.get(key).and_then(|v| v.to_str().ok());
+            if !found
+                .and_then(parse_version)
+                .is_some_and(|v| version_at_least(&v, minimum))
+            {
+                return Err(CheckError::VersionTooOld {
+                    found: found.map(str::to_string),
+                    minimum: self.config.min_backend_version.clone(),
+                });
+            }
+        }
+
+        // Reported load that isn't a number is ignored rather than failing the check
+        let load_weight = match &self.load_key {
+            Some(key) if serving_status == ServingStatus::Serving => metadata
+                .get(key)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok())
+                .map(|load| load_weight(load, self.config.load_weight_capacity)),
+            _ => None,
+        };
+
+        Ok((serving_status, load_weight))
+    }
+}
+
+/// gRPC status code the Health Check RPC ended with, for GRPC_STATUS_CODES_TOTAL; None when no
+/// RPC was made, e.g. the connect failed. The agent's own RPC timeout counts as
+/// DEADLINE_EXCEEDED, as in gRPC clients that enforce their deadline locally.
+#[cfg(feature = "metrics")]
+fn rpc_code(result: &Result<(ServingStatus, Option<u8>), CheckError>) -> Option<tonic::Code> {
+    match result {
+        // The version gate rejects an RPC that itself succeeded
+        Ok(_) | Err(CheckError::VersionTooOld { .. }) => Some(tonic::Code::Ok),
+        Err(CheckError::RpcFailed(status)) => Some(status.code()),
+        Err(CheckError::RpcTimeout(_)) => Some(tonic::Code::DeadlineExceeded),
+        Err(_) => None,
+    }
+}
+
+/// Weight percentage for a backend reporting `load` active connections: 100% when idle, falling
+/// linearly to 1% at `capacity`. Never 0, which HAProxy would treat as drained.
+fn load_weight(load: u64, capacity: u64) -> u8 {
+    let left = capacity.saturating_sub(load);
+    (u128::from(left) * 100 / u128::from(capacity.max(1))).max(1) as u8
+}
+
+/// Parses a dotted numeric version ("2.4", "v2.4.1"), ignoring any "-pre" or "+build" suffix
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim();
+    let version = version.strip_prefix(['v', 'V']).unwrap_or(version);
+    let core = version.split(['-', '+']).next()?;
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Compares dotted versions numerically; missing trailing parts count as 0 ("2.4" == "2.4.0")
+fn version_at_least(version: &[u64], minimum: &[u64]) -> bool {
+    let len = version.len().max(minimum.len());
+    let part = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| part(version, i).cmp(&part(minimum, i)))
+        .find(|ordering| ordering.is_ne())
+        .is_none_or(|ordering| ordering.is_gt())
+}
+
+/// Health Check RPC request, with the proxy host under the configured metadata key.
+/// A proxy host that isn't a valid header value is left out rather than failing the check.
+fn build_health_request(
+    service: &str,
+    host_header: Option<(&MetadataKey<Ascii>, &str)>,
+) -> tonic::Request<HealthCheckRequestGrpc> {
+    let mut request = tonic::Request::new(HealthCheckRequestGrpc {
+        service: service.to_string(),
+    });
+    if let Some((key, host)) = host_header
+        && let Ok(value) = MetadataValue::try_from(host)
+    {
+        request.metadata_mut().insert(key.clone(), value);
+    }
+    request
+}
+
+/// Echo the weight HAProxy sent with the request on an up response, scaled by any slow-start ramp
+fn apply_requested_weight(
+    response: HealthCheckResponse,
+    requested: Option<u8>,
+) -> HealthCheckResponse {
+    let Some(requested) = requested else {
+        return response;
+    };
+    if response.status != HealthStatus::Up {
+        return response;
+    }
+    let weight = match response.weight {
+        // Keep a ramping backend above 0%, which HAProxy would treat as drained
+        Some(ramp) if requested > 0 => (u16::from(requested) * u16::from(ramp) / 100).max(1) as u8,
+        _ => requested,
+    };
+    response.with_weight(weight)
+}
+
+/// Linear ramp from SLOW_START_MIN_PERCENT to 100% across the slow-start window
+fn slow_start_percent(elapsed: Duration, window: Duration) -> u8 {
+    const SLOW_START_MIN_PERCENT: f64 = 10.0;
+    let progress = elapsed.as_secs_f64() / window.as_secs_f64();
+    let percent = SLOW_START_MIN_PERCENT + (100.0 - SLOW_START_MIN_PERCENT) * progress;
+    percent.clamp(SLOW_START_MIN_PERCENT, 100.0) as u8
+}
+
+/// Weight for `successes` up checks out of `total`: linear from `min_percent` at none to 100% at
+/// all. Never 0, which HAProxy would treat as drained.
+fn success_percent(successes: usize, total: usize, min_percent: u8) -> u8 {
+    let min = usize::from(min_percent.min(100));
+    (min + (100 - min) * successes / total.max(1)).clamp(1, 100) as u8
+}
+
+/// Matches a backend against a glob: "server:port" patterns match both parts, others the server only.
+/// Supports `*` (any run of characters) and `?` (one character).
+fn backend_matches(pattern: &str, server: &str, port: u16) -> bool {
+    if pattern.contains(':') {
+        glob_match(pattern, &format!("{}:{}", server, port))
+    } else {
+        glob_match(pattern, server)
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last '*' and the text index it is currently matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Builds a resolver for the configured nameservers; None means use the system resolver
+fn build_resolver(dns: &DnsConfig) -> Option<TokioResolver> {
+    let addrs = match dns.nameserver_addrs() {
+        Ok(addrs) if !addrs.is_empty() => addrs,
+        Ok(_) => return None,
+        Err(e) => {
+            tracing::warn!(error = %e, "Invalid DNS config, using the system resolver");
+            return None;
+        }
+    };
+
+    let name_servers = addrs
+        .iter()
+        .map(|addr| {
+            let mut name_server = NameServerConfig::udp_and_tcp(addr.ip());
+            for connection in &mut name_server.connections {
+                connection.port = addr.port();
+            }
+            name_server
+        })
+        .collect();
+
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_millis(dns.timeout_ms);
+
+    let resolver = TokioResolver::builder_with_config(
+        ResolverConfig::from_parts(None, Vec::new(), name_servers),
+        TokioRuntimeProvider::default(),
+    )
+    .with_options(options)
+    .build();
+
+    match resolver {
+        Ok(resolver) => Some(resolver),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to build DNS resolver, using the system resolver");
+            None
+        }
+    }
+}
+
+/// Parses a configured health Check method path; it must be absolute ("/package.Service/Method")
+pub fn parse_health_check_path(path: &str) -> Option<PathAndQuery> {
+    if !path.starts_with('/') {
+        return None;
+    }
+    PathAndQuery::try_from(path).ok()
+}
+
+impl HealthChecker for GrpcHealthChecker {
+    fn check(
+        &self,
+        request: &HealthCheckRequest,
+    ) -> impl Future<Output = HealthCheckResponse> + Send {
+        self.check_backend(request)
+    }
+
+    /// Err means max_concurrent_checks is reached
+    fn try_acquire_check_permit(&self) -> Result<Option<SemaphorePermit<'_>>, TryAcquireError> {
+        match &self.check_permits {
+            Some(permits) => permits.try_acquire().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn active_channels(&self) -> Option<usize> {
+        Some(self.cached_channel_count())
+    }
+}
+
+// T065: Map ServingStatus to HealthStatus
+fn map_serving_status(serving_status: ServingStatus) -> HealthStatus {
+    match serving_status {
+        ServingStatus::Serving => HealthStatus::Up,
+        ServingStatus::Unknown | ServingStatus::NotServing | ServingStatus::ServiceUnknown => {
+            HealthStatus::Down
+        }
+    }
+}
+
+/// The status's name in grpc.health.v1, for serving_status_comment
+fn serving_status_name(serving_status: ServingStatus) -> &'static str {
+    match serving_status {
+        ServingStatus::Unknown => "UNKNOWN",
+        ServingStatus::Serving => "SERVING",
+        ServingStatus::NotServing => "NOT_SERVING",
+        ServingStatus::ServiceUnknown => "SERVICE_UNKNOWN",
+    }
+}
+
+// Short reason for a non-serving status, used in verbose responses
+fn serving_status_reason(serving_status: ServingStatus) -> Option<&'static str> {
+    match serving_status {
+        ServingStatus::Serving => None,
+        ServingStatus::Unknown => Some("status unknown"),
+        ServingStatus::NotServing => Some("not serving"),
+        ServingStatus::ServiceUnknown => Some("service unknown"),
+    }
+}
+
+// gRPC Health Check Protocol types
+// Based on: https://github.com/grpc/grpc-proto/blob/master/grpc/health/v1/health.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthCheckRequestGrpc {
+    #[prost(string, tag = "1")]
+    pub service: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HealthCheckResponseGrpc {
+    #[prost(enumeration = "ServingStatus", tag = "1")]
+    pub status: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ServingStatus {
+    Unknown = 0,
+    Serving = 1,
+    NotServing = 2,
+    ServiceUnknown = 3,
+}
+
+// Rich error model carried in grpc-status-details-bin
+// Based on: https://github.com/googleapis/googleapis/blob/master/google/rpc/status.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RpcStatusGrpc {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(message, repeated, tag = "3")]
+    pub details: Vec<AnyGrpc>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnyGrpc {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+/// Canonical upper-case name of a gRPC status code, as in the gRPC spec
+pub fn grpc_code_name(code: tonic::Code) -> &'static str {
+    match code {
+        tonic::Code::Ok => "OK",
+        tonic::Code::Cancelled => "CANCELLED",
+        tonic::Code::Unknown => "UNKNOWN",
+        tonic::Code::InvalidArgument => "INVALID_ARGUMENT",
+        tonic::Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        tonic::Code::NotFound => "NOT_FOUND",
+        tonic::Code::AlreadyExists => "ALREADY_EXISTS",
+        tonic::Code::PermissionDenied => "PERMISSION_DENIED",
+        tonic::Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        tonic::Code::FailedPrecondition => "FAILED_PRECONDITION",
+        tonic::Code::Aborted => "ABORTED",
+        tonic::Code::OutOfRange => "OUT_OF_RANGE",
+        tonic::Code::Unimplemented => "UNIMPLEMENTED",
+        tonic::Code::Internal => "INTERNAL",
+        tonic::Code::Unavailable => "UNAVAILABLE",
+        tonic::Code::DataLoss => "DATA_LOSS",
+        tonic::Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+/// Summary of a status's grpc-status-details-bin for logs: the type of each detail message
+/// (e.g. "google.rpc.ErrorInfo"), or the raw bytes in base64 when they aren't a google.rpc.Status
+fn status_details_summary(status: &tonic::Status) -> Option<String> {
+    use base64::Engine;
+    use prost::Message;
+
+    let details = status.details();
+    if details.is_empty() {
+        return None;
+    }
+    match RpcStatusGrpc::decode(details) {
+        Ok(rpc_status) if !rpc_status.details.is_empty() => Some(
+            rpc_status
+                .details
+                .iter()
+                .map(|any| {
+                    let type_url = any.type_url.as_str();
+                    type_url.rsplit_once('/').map_or(type_url, |(_, name)| name)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Ok(_) => None,
+        Err(_) => Some(base64::engine::general_purpose::STANDARD.encode(details)),
+    }
+}
+
+/// Reason on the `up` answered in place of a held-back first failure
+pub(crate) const FIRST_FAILURE_REASON: &str = "first failure";
+
+/// Method path of the standard gRPC health Check RPC
+pub const DEFAULT_HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+// gRPC Health service client
+pub mod health_client {
+    use super::*;
+    use tonic::codegen::*;
+
+    #[derive(Debug, Clone)]
+    pub struct HealthClient<T> {
+        inner: tonic::client::Grpc<T>,
+        path: http::uri::PathAndQuery,
+    }
+
+    impl HealthClient<Channel> {
+        pub fn new(channel: Channel) -> Self {
+            let inner = tonic::client::Grpc::new(channel);
+            Self {
+                inner,
+                path: http::uri::PathAndQuery::from_static(DEFAULT_HEALTH_CHECK_PATH),
+            }
+        }
+
+        /// Call Check at a non-standard method path
+        pub fn with_path(mut self, path: http::uri::PathAndQuery) -> Self {
+            self.path = path;
+            self
+        }
+
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+
+        pub async fn check(
+            &mut self,
+            request: tonic::Request<HealthCheckRequestGrpc>,
+        ) -> Result<tonic::Response<HealthCheckResponseGrpc>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e),
+                )
+            })?;
+
+            let codec = ProstCodec::default();
+
+            self.inner.unary(request, self.path.clone(), codec).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    fn key() -> BackendChannelKey {
+        BackendChannelKey {
+            server: "backend".to_string(),
+            port: 50051,
+            ssl_flag: SslFlag::NoSsl,
+        }
+    }
+
+    #[test]
+    fn test_log_throttle_suppresses_repeats_until_recovery() {
+        let throttle = LogThrottle::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert_eq!(throttle.on_failure(&key(), now), Some(0));
+        assert_eq!(throttle.on_failure(&key(), now), None);
+        assert_eq!(throttle.on_failure(&key(), now), None);
+
+        throttle.on_success(&key());
+        assert_eq!(throttle.on_failure(&key(), now), Some(0));
+    }
+
+    #[test]
+    fn test_log_throttle_summarizes_after_interval() {
+        let throttle = LogThrottle::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert_eq!(throttle.on_failure(&key(), now), Some(0));
+        assert_eq!(
+            throttle.on_failure(&key(), now + Duration::from_secs(30)),
+            None
+        );
+        assert_eq!(
+            throttle.on_failure(&key(), now + Duration::from_secs(59)),
+            None
+        );
+        assert_eq!(
+            throttle.on_failure(&key(), now + Duration::from_secs(60)),
+            Some(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_backoff_counts_down_on_clock() {
+        let clock = Arc::new(MockClock::new());
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            connect_backoff_ms: 1000,
+            ..AgentConfig::default()
+        })
+        .with_clock(clock.clone());
+
+        checker.back_off_connects(&key());
+        clock.advance(Duration::from_millis(400));
+
+        match checker.get_or_create_channel(&key(), "proxy", None).await {
+            Err(CheckError::ConnectBackoff { remaining, .. }) => {
+                assert_eq!(remaining, Duration::from_millis(600));
+            }
+            other => panic!("expected a connect backoff, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_connect_backoff_full_jitter_stays_within_doubled_delay() {
+        let clock = Arc::new(MockClock::new());
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            connect_backoff_ms: 1000,
+            connect_backoff_max_ms: 4000,
+            connect_backoff_jitter: BackoffJitter::Full,
+            ..AgentConfig::default()
+        })
+        .with_clock(clock.clone());
+
+        for delay_ms in [1000, 2000, 4000, 4000] {
+            checker.back_off_connects(&key());
+            let backoff = checker.connect_backoff.get(&key()).unwrap();
+            assert_eq!(backoff.delay, Duration::from_millis(delay_ms));
+            assert!(backoff.retry_at - clock.now() <= backoff.delay);
+        }
+    }
+
+    #[test]
+    fn test_unknown_retry_delay_capped_by_deadline() {
+        let clock = Arc::new(MockClock::new());
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            unknown_retry_delay_ms: 100,
+            ..AgentConfig::default()
+        })
+        .with_clock(clock.clone());
+        let deadline = clock.now() + Duration::from_millis(500);
+
+        assert_eq!(
+            checker.unknown_retry_delay(None),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            checker.unknown_retry_delay(Some(deadline)),
+            Duration::from_millis(100)
+        );
+        clock.advance(Duration::from_millis(460));
+        assert_eq!(
+            checker.unknown_retry_delay(Some(deadline)),
+            Duration::from_millis(40)
+        );
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(checker.unknown_retry_delay(Some(deadline)), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_connect_within_deadline_fails_fast_when_budget_spent() {
+        let clock = Arc::new(MockClock::new());
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            response_deadline_ms: 500,
+            connect_within_deadline: true,
+            ..AgentConfig::default()
+        })
+        .with_clock(clock.clone());
+        let key = BackendChannelKey {
+            server: "127.0.0.1".to_string(),
+            ..key()
+        };
+
+        let deadline = clock.now() + Duration::from_millis(500);
+        clock.advance(Duration::from_millis(500));
+
+        match checker
+            .get_or_create_channel(&key, "proxy", Some(deadline))
+            .await
+        {
+            Err(CheckError::ConnectTimeout(endpoint)) => {
+                assert_eq!(endpoint, "http://127.0.0.1:50051");
+            }
+            other => panic!("expected a connect timeout, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aborted_checks_leave_no_state_behind() {
+        // Accepts TCP but never speaks HTTP/2, so checks stay in flight until aborted
+        let silent_backend = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = silent_backend.local_addr().unwrap().port();
+        let checker = Arc::new(GrpcHealthChecker::new(AgentConfig::default()));
+        let request =
+            crate::protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", port))
+                .unwrap();
+        let spawn_check = || {
+            let checker = Arc::clone(&checker);
+            let request = request.clone();
+            tokio::spawn(async move { checker.check_backend(&request).await })
+        };
+
+        for round in 0..20 {
+            let checks: Vec<_> = (0..4).map(|_| spawn_check()).collect();
+            // Vary the await point the checks are dropped at: connecting, calling or waiting
+            tokio::time::sleep(Duration::from_millis(round % 5)).await;
+            for check in &checks {
+                check.abort();
+                tokio::task::yield_now().await;
+            }
+            for check in checks {
+                assert!(check.await.unwrap_err().is_cancelled());
+            }
+            assert!(checker.in_flight.is_empty());
+            assert!(checker.cached_channel_count() <= 1);
+        }
+
+        // Cancelling the leader hands the flight to a waiting check rather than dropping it
+        let leader = spawn_check();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let followers: Vec<_> = (0..3).map(|_| spawn_check()).collect();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        leader.abort();
+        assert!(leader.await.unwrap_err().is_cancelled());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(checker.in_flight.len(), 1);
+        assert!(followers.iter().all(|check| !check.is_finished()));
+
+        for check in &followers {
+            check.abort();
+        }
+        for check in followers {
+            assert!(check.await.unwrap_err().is_cancelled());
+        }
+        assert!(checker.in_flight.is_empty());
+        assert!(checker.cached_channel_count() <= 1);
+        drop(silent_backend);
+    }
+
+    #[test]
+    fn test_slow_start_weight_ramps_on_clock() {
+        let clock = Arc::new(MockClock::new());
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            slow_start_ms: 100_000,
+            ..AgentConfig::default()
+        })
+        .with_clock(clock.clone());
+        let request = crate::protocol::parse_request("backend 50051 no-ssl proxy").unwrap();
+        let key = checker.backend_key(&request);
+
+        checker.record_backend_state(&request, HealthStatus::Down, None);
+        assert_eq!(checker.slow_start_weight(&key, HealthStatus::Up), Some(10));
+        checker.record_backend_state(&request, HealthStatus::Up, None);
+
+        clock.advance(Duration::from_secs(50));
+        assert_eq!(checker.slow_start_weight(&key, HealthStatus::Up), Some(55));
+
+        clock.advance(Duration::from_secs(50));
+        assert_eq!(checker.slow_start_weight(&key, HealthStatus::Up), None);
+    }
+
+    #[test]
+    fn test_success_weight_follows_recent_checks() {
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            success_weight_window: 4,
+            ..AgentConfig::default()
+        });
+        let request = crate::protocol::parse_request("backend 50051 no-ssl proxy").unwrap();
+        let key = checker.backend_key(&request);
+
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), None);
+        assert_eq!(checker.success_weight(&key, HealthStatus::Down), None);
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), Some(66));
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), Some(75));
+        // The first up check leaves the window, the failure is still in it
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), Some(75));
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), None);
+    }
+
+    #[tokio::test]
+    async fn test_success_weight_counts_deadline_failures() {
+        // Accepts TCP so the channel connects, but never answers the RPC
+        let silent_backend = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = silent_backend.local_addr().unwrap().port();
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            success_weight_window: 4,
+            response_deadline_ms: 100,
+            ..AgentConfig::default()
+        });
+        let request =
+            crate::protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost", port))
+                .unwrap();
+        let key = checker.backend_key(&request);
+
+        let response = checker.check_backend(&request).await;
+        assert_eq!(response.status, HealthStatus::Down);
+        // The timed-out check is in the window, so the next up answer is weighted
+        assert_eq!(checker.success_weight(&key, HealthStatus::Up), Some(50));
+        drop(silent_backend);
+    }
+
+    #[test]
+    fn test_success_percent() {
+        assert_eq!(success_percent(1, 2, 0), 50);
+        assert_eq!(success_percent(1, 2, 20), 60);
+        assert_eq!(success_percent(0, 4, 20), 20);
+        assert_eq!(success_percent(4, 4, 20), 100);
+        assert_eq!(success_percent(1, 1000, 0), 1);
+    }
+
+    #[test]
+    fn test_build_health_request_host_header() {
+        let key = MetadataKey::from_static("x-forwarded-host");
+
+        let request = build_health_request("svc", Some((&key, "api.example.com")));
+        assert_eq!(request.get_ref().service, "svc");
+        assert_eq!(
+            request.metadata().get("x-forwarded-host").unwrap(),
+            "api.example.com"
+        );
+
+        let request = build_health_request("", None);
+        assert!(request.metadata().is_empty());
+
+        // Not a valid header value: sent without the header
+        let request = build_health_request("", Some((&key, "bad\nhost")));
+        assert!(request.metadata().is_empty());
+    }
+
+    #[test]
+    fn test_shed_status_only_for_resource_exhausted() {
+        let exhausted = CheckError::RpcFailed(tonic::Status::resource_exhausted("busy"));
+        let unavailable = CheckError::RpcFailed(tonic::Status::unavailable("gone"));
+
+        let checker = GrpcHealthChecker::new(AgentConfig::default());
+        assert_eq!(checker.shed_status(&exhausted), None);
+
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            resource_exhausted_status: Some(HealthStatus::Drain),
+            ..AgentConfig::default()
+        });
+        assert_eq!(checker.shed_status(&exhausted), Some(HealthStatus::Drain));
+        assert_eq!(checker.shed_status(&unavailable), None);
+    }
+
+    #[test]
+    fn test_check_error_outcome() {
+        let timeout = Duration::from_millis(100);
+        assert_eq!(
+            CheckError::ConnectTimeout("http://backend:50051".to_string()).outcome(),
+            "connect_timeout"
+        );
+        assert_eq!(CheckError::RpcTimeout(timeout).outcome(), "rpc_timeout");
+        assert_eq!(
+            CheckError::DeadlineExceeded(timeout).outcome(),
+            "rpc_timeout"
+        );
+        assert_eq!(
+            CheckError::RpcFailed(tonic::Status::unavailable("gone")).outcome(),
+            "transport_error"
+        );
+        assert_eq!(
+            CheckError::ConnectBackoff {
+                endpoint: "backend:50051".to_string(),
+                remaining: timeout,
+            }
+            .outcome(),
+            "transport_error"
+        );
+        let too_old = CheckError::VersionTooOld {
+            found: None,
+            minimum: "2.4".to_string(),
+        };
+        assert_eq!(too_old.outcome(), "not_serving");
+        assert_eq!(
+            too_old.to_string(),
+            "Backend version (not reported) is below the required 2.4"
+        );
+    }
+
+    #[test]
+    fn test_backend_matches() {
+        assert!(backend_matches("10.0.1.*", "10.0.1.15", 50051));
+        assert!(!backend_matches("10.0.1.*", "10.0.2.15", 50051));
+        assert!(backend_matches(
+            "*.canary.svc:50051",
+            "api.canary.svc",
+            50051
+        ));
+        assert!(!backend_matches(
+            "*.canary.svc:50051",
+            "api.canary.svc",
+            50052
+        ));
+        assert!(backend_matches("api-?:*", "api-3", 8080));
+        assert!(!backend_matches("api-?", "api-10", 8080));
+    }
+
+    #[test]
+    fn test_rpc_failed_names_grpc_code() {
+        let e = CheckError::RpcFailed(tonic::Status::unavailable("draining"));
+        assert_eq!(
+            e.to_string(),
+            "Health check RPC failed with UNAVAILABLE: draining"
+        );
+    }
+
+    #[test]
+    fn test_status_details_summary() {
+        use prost::Message;
+
+        assert_eq!(
+            status_details_summary(&tonic::Status::unavailable("gone")),
+            None
+        );
+
+        let rpc_status = RpcStatusGrpc {
+            code: tonic::Code::Unavailable as i32,
+            message: "draining".to_string(),
+            details: vec![
+                AnyGrpc {
+                    type_url: "type.googleapis.com/google.rpc.ErrorInfo".to_string(),
+                    value: Vec::new(),
+                },
+                AnyGrpc {
+                    type_url: "type.googleapis.com/google.rpc.RetryInfo".to_string(),
+                    value: Vec::new(),
+                },
+            ],
+        };
+        let status = tonic::Status::with_details(
+            tonic::Code::Unavailable,
+            "draining",
+            rpc_status.encode_to_vec().into(),
+        );
+        assert_eq!(
+            status_details_summary(&status).as_deref(),
+            Some("google.rpc.ErrorInfo, google.rpc.RetryInfo")
+        );
+
+        let status =
+            tonic::Status::with_details(tonic::Code::Internal, "bad", vec![0xff, 0xff].into());
+        assert_eq!(status_details_summary(&status).as_deref(), Some("//8="));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("2.4.1"), Some(vec![2, 4, 1]));
+        assert_eq!(parse_version("v2.10"), Some(vec![2, 10]));
+        assert_eq!(parse_version("3.0.0-rc.1+build.7"), Some(vec![3, 0, 0]));
+        assert_eq!(parse_version(""), None);
+        assert_eq!(parse_version("2.x"), None);
+    }
+
+    #[test]
+    fn test_version_at_least() {
+        let minimum = parse_version("2.4").unwrap();
+        for (version, expected) in [
+            ("2.4", true),
+            ("2.4.0", true),
+            ("2.4.1", true),
+            ("2.10", true),
+            ("3", true),
+            ("2.3.9", false),
+            ("1.99", false),
+        ] {
+            assert_eq!(
+                version_at_least(&parse_version(version).unwrap(), &minimum),
+                expected,
+                "{}",
+                version
+            );
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_rpc_code() {
+        assert_eq!(
+            rpc_code(&Ok((ServingStatus::NotServing, None))),
+            Some(tonic::Code::Ok)
+        );
+        assert_eq!(
+            rpc_code(&Err(CheckError::RpcFailed(tonic::Status::unavailable(
+                "connection refused"
+            )))),
+            Some(tonic::Code::Unavailable)
+        );
+        assert_eq!(
+            rpc_code(&Err(CheckError::RpcTimeout(Duration::from_millis(500)))),
+            Some(tonic::Code::DeadlineExceeded)
+        );
+        assert_eq!(
+            rpc_code(&Err(CheckError::VersionTooOld {
+                found: None,
+                minimum: "2.4".to_string(),
+            })),
+            Some(tonic::Code::Ok)
+        );
+        assert_eq!(
+            rpc_code(&Err(CheckError::ConnectTimeout(
+                "http://backend:50051".to_string()
+            ))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load_weight() {
+        assert_eq!(load_weight(0, 200), 100);
+        assert_eq!(load_weight(50, 200), 75);
+        assert_eq!(load_weight(199, 200), 1);
+        assert_eq!(load_weight(200, 200), 1);
+        assert_eq!(load_weight(u64::MAX, 200), 1);
+        assert_eq!(load_weight(0, u64::MAX), 100);
+    }
+
+    #[test]
+    fn test_serving_status_name() {
+        assert_eq!(serving_status_name(ServingStatus::Serving), "SERVING");
+        assert_eq!(
+            serving_status_name(ServingStatus::NotServing),
+            "NOT_SERVING"
+        );
+        assert_eq!(serving_status_name(ServingStatus::Unknown), "UNKNOWN");
+        assert_eq!(
+            serving_status_name(ServingStatus::ServiceUnknown),
+            "SERVICE_UNKNOWN"
+        );
+    }
+
+    #[test]
+    fn test_apply_requested_weight() {
+        let up = HealthCheckResponse::new(HealthStatus::Up);
+        assert_eq!(apply_requested_weight(up.clone(), None).weight, None);
+        assert_eq!(
+            apply_requested_weight(up.clone(), Some(75)).weight,
+            Some(75)
+        );
+
+        let ramping = up.with_weight(50);
+        assert_eq!(
+            apply_requested_weight(ramping.clone(), Some(80)).weight,
+            Some(40)
+        );
+        assert_eq!(
+            apply_requested_weight(ramping.clone(), Some(1)).weight,
+            Some(1)
+        );
+        assert_eq!(apply_requested_weight(ramping, Some(0)).weight, Some(0));
+
+        let down = HealthCheckResponse::new(HealthStatus::Down);
+        assert_eq!(apply_requested_weight(down, Some(75)).weight, None);
+    }
+
+    #[test]
+    fn test_record_backend_state_counts_transitions() {
+        let checker = GrpcHealthChecker::new(AgentConfig::default());
+        let request =
+            crate::protocol::parse_request("transitions.test 50051 no-ssl proxy").unwrap();
+
+        checker.record_backend_state(&request, HealthStatus::Up, None);
+        checker.record_backend_state(&request, HealthStatus::Up, None);
+        checker.record_backend_state(&request, HealthStatus::Down, Some("gone".to_string()));
+        assert_eq!(checker.backend_states()[0].status, HealthStatus::Down);
+
+        #[cfg(feature = "metrics")]
+        {
+            let count = |from, to| {
+                metrics::BACKEND_STATUS_TRANSITIONS_TOTAL
+                    .with_label_values(&["transitions.test:50051", from, to])
+                    .get()
+            };
+            assert_eq!(count("up", "down"), 1.0);
+            assert_eq!(count("up", "up"), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_slow_start_percent() {
+        let window = Duration::from_secs(100);
+        assert_eq!(slow_start_percent(Duration::ZERO, window), 10);
+        assert_eq!(slow_start_percent(Duration::from_secs(50), window), 55);
+        assert_eq!(slow_start_percent(Duration::from_secs(100), window), 100);
+    }
+
+    #[test]
+    fn test_log_throttle_disabled() {
+        let throttle = LogThrottle::new(Duration::ZERO);
+        let now = Instant::now();
+
+        assert_eq!(throttle.on_failure(&key(), now), Some(0));
+        assert_eq!(throttle.on_failure(&key(), now), Some(0));
     }
 }
@@ -1,11 +1,16 @@
 // Configuration module for HAProxy gRPC Agent
 // T026-T034: Complete configuration implementation
 
+use crate::listener::{IpCidr, ListenAddr};
+use crate::protocol::{CheckProtocol, HealthStatus, SslFlag};
 use anyhow::{Context, Result};
 use clap::Parser;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use tonic::codegen::http::HeaderValue;
+use tonic::metadata::{Ascii, MetadataKey};
 
 // T028: LogLevel enum with serde derives
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, clap::ValueEnum)]
@@ -38,6 +43,144 @@ pub enum LogDestination {
     #[default]
     Console,
     File,
+    /// Console and file at once, each in its own format
+    Both,
+}
+
+impl LogDestination {
+    pub fn writes_console(self) -> bool {
+        matches!(self, LogDestination::Console | LogDestination::Both)
+    }
+
+    pub fn writes_file(self) -> bool {
+        matches!(self, LogDestination::File | LogDestination::Both)
+    }
+}
+
+// Response sent when a check can't start because max_concurrent_checks is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum OverCapacityResponse {
+    #[default]
+    Down,
+    Drain,
+    /// Close the connection without a status so HAProxy keeps its last agent state
+    Noop,
+}
+
+// Answer to a connection accepted while max_connections is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum OverloadResponse {
+    /// Close the connection without reading or answering
+    #[default]
+    Close,
+    Up,
+    Down,
+    Drain,
+}
+
+impl OverloadResponse {
+    /// Status to answer with, or None to close without one
+    pub fn status(self) -> Option<HealthStatus> {
+        match self {
+            OverloadResponse::Close => None,
+            OverloadResponse::Up => Some(HealthStatus::Up),
+            OverloadResponse::Down => Some(HealthStatus::Down),
+            OverloadResponse::Drain => Some(HealthStatus::Drain),
+        }
+    }
+}
+
+// Response to the first failed check of a backend the agent hasn't seen before
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum FirstFailureResponse {
+    /// No debounce: report down straight away
+    #[default]
+    Down,
+    /// Report up optimistically; a second consecutive failure reports down
+    Up,
+    /// Close the connection without a status so HAProxy keeps its current state
+    Noop,
+}
+
+// How backend servers are normalized into channel cache keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum ChannelKeyMode {
+    /// Server names are used exactly as sent
+    Exact,
+    /// Hostnames are lowercased, so names differing only in case share a channel
+    #[default]
+    Lowercase,
+    /// Hostnames are resolved and keyed on IP, so every name for one address shares a channel
+    Resolved,
+}
+
+// How the connect backoff window is drawn from the doubled delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum BackoffJitter {
+    /// Back off for exactly the doubled delay
+    #[default]
+    None,
+    /// Back off for a random time between 0 and the doubled delay, so agents don't reconnect in step
+    Full,
+}
+
+// Answer to a blank or whitespace-only request line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum BlankLineResponse {
+    /// Send nothing and keep reading, treating the line as a keepalive
+    #[default]
+    Ignore,
+    /// Answer up, for clients that expect a reply to every line
+    Up,
+    /// Reject it as a malformed request and answer down
+    Down,
+}
+
+// Byte that terminates each agent request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum RequestDelimiter {
+    /// "\n", as sent by HAProxy's agent-send
+    #[default]
+    Newline,
+    /// "\0", for clients that frame requests with a null byte
+    Null,
+}
+
+// How each agent request is framed on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum RequestFraming {
+    /// Requests end with request_delimiter, as HAProxy sends them
+    #[default]
+    Line,
+    /// A 2-byte big-endian length followed by exactly that many bytes of request
+    #[serde(rename = "length-prefixed")]
+    #[value(name = "length-prefixed")]
+    LengthPrefixed,
+}
+
+impl RequestDelimiter {
+    pub fn as_byte(self) -> u8 {
+        match self {
+            RequestDelimiter::Newline => b'\n',
+            RequestDelimiter::Null => b'\0',
+        }
+    }
 }
 
 // T003: LoggingConfig struct for the [logging] TOML section
@@ -52,6 +195,14 @@ pub struct LoggingConfig {
     #[serde(default)]
     pub format: Option<LogFormat>,
 
+    /// Format of console output, overriding format
+    #[serde(default)]
+    pub console_format: Option<LogFormat>,
+
+    /// Format of file output, overriding format
+    #[serde(default)]
+    pub file_format: Option<LogFormat>,
+
     #[serde(default)]
     pub file_path: Option<String>,
 
@@ -67,6 +218,276 @@ pub struct LoggingConfig {
     pub packages: HashMap<String, LogLevel>,
 }
 
+// AuditConfig for the [audit] TOML section.
+// Audit records are JSON lines kept apart from the operational log, with their own level.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Minimum record level: completed `up` checks are INFO, every other answer is WARN
+    #[serde(default)]
+    pub level: LogLevel,
+
+    /// File to append audit records to; stdout when unset
+    #[serde(default)]
+    pub file_path: Option<String>,
+}
+
+// ProxyConfig for the [proxy] TOML section.
+// With a host set, backend connections are tunnelled through the proxy with HTTP CONNECT.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ProxyConfig {
+    #[serde(default)]
+    pub host: Option<String>,
+
+    #[serde(default = "default_proxy_port")]
+    pub port: u16,
+
+    /// Basic auth credentials; both or neither must be set.
+    /// Either may be a `${ENV_VAR}` or `file:/path` reference, resolved at load time.
+    #[serde(default)]
+    pub username: Option<String>,
+
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_proxy_port() -> u16 {
+    3128
+}
+
+/// Resolve a secret reference: `${ENV_VAR}` reads an environment variable and `file:/path`
+/// reads a file (one trailing newline is dropped). Other values are returned as written.
+/// Relative file paths are looked up in systemd's `$CREDENTIALS_DIRECTORY` when it is set.
+pub fn resolve_secret(value: &str) -> Result<String> {
+    if let Some(name) = value
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+    {
+        return std::env::var(name)
+            .with_context(|| format!("Environment variable {} is not set", name));
+    }
+
+    if let Some(path) = value.strip_prefix("file:") {
+        let credentials_dir = std::env::var_os("CREDENTIALS_DIRECTORY").map(PathBuf::from);
+        let path = credential_path(path, credentials_dir.as_deref());
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+        let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+        return Ok(contents.strip_suffix('\r').unwrap_or(contents).to_string());
+    }
+
+    Ok(value.to_string())
+}
+
+/// Resolve a relative path against the systemd credentials directory (`LoadCredential=`),
+/// so unit files can name a credential instead of an absolute path
+fn credential_path(path: &str, credentials_dir: Option<&std::path::Path>) -> PathBuf {
+    match credentials_dir {
+        Some(dir) if std::path::Path::new(path).is_relative() => dir.join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+// Keeps the proxy password out of config dumps
+impl std::fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        ProxyConfig {
+            host: None,
+            port: default_proxy_port(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+// Wire format of metrics pushed to [statsd]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[derive(Default)]
+pub enum StatsdFormat {
+    /// Plain StatsD: label values are appended to the metric name
+    #[default]
+    Statsd,
+    /// DogStatsD: labels are sent as `#label:value` tags
+    Dogstatsd,
+}
+
+// StatsdConfig for the [statsd] TOML section.
+// With a host set, metrics are pushed over UDP every flush_interval_ms.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StatsdConfig {
+    #[serde(default)]
+    pub host: Option<String>,
+
+    #[serde(default = "default_statsd_port")]
+    pub port: u16,
+
+    /// Prepended to every metric name with a dot ("" = none)
+    #[serde(default)]
+    pub prefix: String,
+
+    #[serde(default)]
+    pub format: StatsdFormat,
+
+    #[serde(default = "default_statsd_flush_interval")]
+    pub flush_interval_ms: u64,
+}
+
+fn default_statsd_port() -> u16 {
+    8125
+}
+
+fn default_statsd_flush_interval() -> u64 {
+    10_000
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        StatsdConfig {
+            host: None,
+            port: default_statsd_port(),
+            prefix: String::new(),
+            format: StatsdFormat::default(),
+            flush_interval_ms: default_statsd_flush_interval(),
+        }
+    }
+}
+
+// ChaosConfig for the [chaos] TOML section: fault injection for staging and tests.
+// Only honored when built with the `chaos` feature.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChaosConfig {
+    /// Probability (0.0-1.0) of delaying a check by latency_ms before it runs
+    #[serde(default)]
+    pub latency_probability: f64,
+
+    #[serde(default)]
+    pub latency_ms: u64,
+
+    /// Probability (0.0-1.0) of answering `down` without checking the backend
+    #[serde(default)]
+    pub down_probability: f64,
+}
+
+impl ChaosConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.latency_probability > 0.0 || self.down_probability > 0.0
+    }
+}
+
+// SelfTestConfig for the [self_test] TOML section.
+// The self-test checks listeners can bind, TLS support initializes and an optional canary answers `up`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct SelfTestConfig {
+    /// Run the self-test before accepting traffic and refuse to start if it fails
+    #[serde(default)]
+    pub on_startup: bool,
+
+    /// Backend probed by the self-test, written as an agent request line
+    /// ("<server> <port> <ssl-flag> <proxy-host>"); it must answer `up`
+    #[serde(default)]
+    pub canary: Option<String>,
+
+    /// Run the self-test and exit instead of serving; set by --self-test only
+    #[serde(skip)]
+    pub exit_after: bool,
+}
+
+// DnsConfig for the [dns] TOML section.
+// With no nameservers, backend hostnames go through the system resolver.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DnsConfig {
+    /// Nameservers as "ip" or "ip:port" (port defaults to 53)
+    #[serde(default)]
+    pub nameservers: Vec<String>,
+
+    #[serde(default = "default_dns_timeout")]
+    pub timeout_ms: u64,
+}
+
+fn default_dns_timeout() -> u64 {
+    1000
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        DnsConfig {
+            nameservers: Vec::new(),
+            timeout_ms: default_dns_timeout(),
+        }
+    }
+}
+
+impl DnsConfig {
+    /// Parses the configured nameservers into socket addresses
+    pub fn nameserver_addrs(&self) -> Result<Vec<SocketAddr>> {
+        self.nameservers
+            .iter()
+            .map(|ns| {
+                ns.parse::<SocketAddr>()
+                    .or_else(|_| ns.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                    .with_context(|| format!("Invalid DNS nameserver: {}", ns))
+            })
+            .collect()
+    }
+}
+
+// GrpcChannelConfig for the [grpc_channel] TOML section.
+// Unset values keep tonic's defaults.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GrpcChannelConfig {
+    /// Maximum requests in flight on one channel
+    #[serde(default)]
+    pub concurrency_limit: Option<usize>,
+
+    /// Requests queued in front of the channel before callers wait
+    #[serde(default)]
+    pub buffer_size: Option<usize>,
+
+    /// HTTP/2 initial stream window size in bytes
+    #[serde(default)]
+    pub initial_stream_window_size: Option<u32>,
+
+    /// HTTP/2 initial connection window size in bytes
+    #[serde(default)]
+    pub initial_connection_window_size: Option<u32>,
+}
+
+// ListenerConfig: one entry of the [[listeners]] TOML array.
+// Unset overrides inherit the top-level value.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ListenerConfig {
+    #[serde(default)]
+    pub bind_address: Option<String>,
+
+    pub port: u16,
+
+    #[serde(default)]
+    pub verbose_response: Option<bool>,
+
+    #[serde(default)]
+    pub response_status_map: Option<HashMap<HealthStatus, HealthStatus>>,
+
+    #[serde(default)]
+    pub request_framing: Option<RequestFraming>,
+
+    #[serde(default)]
+    pub check_protocol: Option<CheckProtocol>,
+}
+
 // Log file rotation strategy
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, clap::ValueEnum)]
 #[serde(rename_all = "lowercase")]
@@ -100,6 +521,18 @@ impl LoggingConfig {
         self.format.unwrap_or(*top_level)
     }
 
+    /// Returns the console log format, preferring console_format over the resolved format
+    pub fn resolved_console_format(&self, top_level: &LogFormat) -> LogFormat {
+        self.console_format
+            .unwrap_or_else(|| self.resolved_format(top_level))
+    }
+
+    /// Returns the file log format, preferring file_format over the resolved format
+    pub fn resolved_file_format(&self, top_level: &LogFormat) -> LogFormat {
+        self.file_format
+            .unwrap_or_else(|| self.resolved_format(top_level))
+    }
+
     /// Builds an EnvFilter directive string from the resolved level and per-package overrides.
     /// Example output: "info,haproxy_grpc_agent::checker=debug,tonic=warn"
     pub fn build_env_filter_directive(&self, top_level: &LogLevel) -> String {
@@ -126,73 +559,647 @@ pub struct AgentConfig {
     #[serde(default = "default_bind_address")]
     pub server_bind_address: String,
 
+    /// Accept backlog for the agent listener socket
+    #[serde(default = "default_listen_backlog")]
+    pub listen_backlog: u32,
+
+    /// Enable TCP Fast Open on TCP agent listeners where the OS supports it, with a pending
+    /// queue as deep as listen_backlog
+    #[serde(default)]
+    pub tcp_fast_open: bool,
+
+    /// Keep retrying the listener bind with backoff for this long while the address is in use
+    /// or not yet available, e.g. held by the agent being replaced (0 = fail at once)
+    #[serde(default)]
+    pub bind_retry_ms: u64,
+
+    /// Request framing; responses are always newline-terminated
+    #[serde(default)]
+    pub request_delimiter: RequestDelimiter,
+
+    /// Line framing (the default) or a 2-byte length prefix, for non-HAProxy clients
+    #[serde(default)]
+    pub request_framing: RequestFraming,
+
+    /// Requests read ahead and checked concurrently on one connection, answered in order
+    /// (1 = one at a time). Above 1, checks in flight are deliberately not cancelled when the
+    /// peer closes: a pipelining client may half-close after its last request and still read
+    /// every answer, whereas at 1 a close cancels the check unless eof_grace_ms covers it
+    #[serde(default = "default_pipeline_depth")]
+    pub pipeline_depth: usize,
+
+    /// Keep connections open for further requests; when false, each connection gets exactly
+    /// one answer and is then closed
+    #[serde(default = "default_persistent_connections")]
+    pub persistent_connections: bool,
+
+    /// Answer to a blank request line; ignored by default so a keepalive never reports down
+    #[serde(default)]
+    pub blank_line_response: BlankLineResponse,
+
+    /// Keep a connection open this long after the peer's EOF and read once more before closing,
+    /// for HAProxy versions that briefly half-close connections they reuse (0 = close at once)
+    #[serde(default)]
+    pub eof_grace_ms: u64,
+
+    /// Stop the agent once no connection has been accepted and no check run for this long,
+    /// for deployments that scale to zero; unset runs forever
+    #[serde(default)]
+    pub idle_shutdown_ms: Option<u64>,
+
+    /// IPv4/IPv6 CIDRs allowed to connect to the agent port; empty allows everyone.
+    /// Does not apply to Unix sockets, which file permissions already guard.
+    #[serde(default)]
+    pub client_allowlist: Vec<String>,
+
+    /// Shared secret HAProxy must send as the first line of every connection ("" = no
+    /// authentication); a connection that opens with anything else is closed
+    #[serde(default)]
+    pub auth_token: String,
+
     #[serde(default = "default_grpc_connect_timeout")]
     pub grpc_connect_timeout_ms: u64,
 
+    /// Connect timeout for SSL backends, covering the TLS handshake; falls back to grpc_connect_timeout_ms
+    #[serde(default)]
+    pub grpc_tls_connect_timeout_ms: Option<u64>,
+
+    /// ALPN protocols offered in the TLS handshake with `ssl-insecure` backends.
+    /// Verified `ssl` channels use tonic's TLS stack, which always offers `h2` only.
+    #[serde(default = "default_tls_alpn_protocols")]
+    pub tls_alpn_protocols: Vec<String>,
+
+    /// Directory of PEM files whose certificates verified `ssl` channels trust
+    #[serde(default)]
+    pub tls_ca_dir: Option<String>,
+
     #[serde(default = "default_grpc_rpc_timeout")]
     pub grpc_rpc_timeout_ms: u64,
 
+    /// After a failed connect, answer `down` without reconnecting for this long, doubling on
+    /// each further failure (0 = disabled)
+    #[serde(default)]
+    pub connect_backoff_ms: u64,
+
+    /// Cap on the doubled connect backoff
+    #[serde(default = "default_connect_backoff_max")]
+    pub connect_backoff_max_ms: u64,
+
+    /// Whether the connect backoff is the doubled delay itself or a random time up to it
+    #[serde(default)]
+    pub connect_backoff_jitter: BackoffJitter,
+
+    /// End-to-end bound on producing a check response, after which the agent answers `down`
+    /// (0 = disabled)
+    #[serde(default)]
+    pub response_deadline_ms: u64,
+
+    /// Cap the connect timeout of a new channel to what is left of response_deadline_ms, so a
+    /// cold connect fails as a connect timeout, and backs off, rather than hitting the deadline
+    #[serde(default)]
+    pub connect_within_deadline: bool,
+
+    /// Testing knob: hold every status answer until this long after its request arrived, for
+    /// reproducible timing experiments against HAProxy (0 = disabled)
+    #[serde(default)]
+    pub response_min_latency_ms: u64,
+
     #[serde(default = "default_grpc_channel_cache_enabled")]
     pub grpc_channel_cache_enabled: bool,
 
-    #[serde(default = "default_metrics_port")]
-    pub metrics_port: u16,
+    /// Most channels kept cached; the least recently used is evicted to make room (0 = unlimited)
+    #[serde(default)]
+    pub max_cached_channels: usize,
 
-    #[serde(default = "default_bind_address")]
-    pub metrics_bind_address: String,
+    /// How long a cached channel gets to report ready before reuse; one that doesn't is evicted
+    /// and reconnected (0 = reuse without checking)
+    #[serde(default = "default_channel_ready_timeout")]
+    pub channel_ready_timeout_ms: u64,
 
+    /// Probe cached channels idle for this long, with jitter, and evict dead ones (0 = disabled)
     #[serde(default)]
-    pub log_level: LogLevel,
+    pub channel_sweep_interval_ms: u64,
 
+    /// How backend servers are normalized into channel cache keys
     #[serde(default)]
-    pub log_format: LogFormat,
+    pub channel_key_mode: ChannelKeyMode,
 
+    /// Method path of the health Check RPC, for backends serving it outside grpc.health.v1
+    #[serde(default = "default_grpc_health_check_path")]
+    pub grpc_health_check_path: String,
+
+    /// Request metadata header carrying the proxy host, alongside :authority ("" = not sent)
     #[serde(default)]
-    pub logging: LoggingConfig,
-}
+    pub host_header_name: String,
 
-// T027: Default functions for AgentConfig
-fn default_server_port() -> u16 {
-    5555
-}
+    /// user-agent sent on health checks, ahead of tonic's own ("" = tonic's alone)
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
 
-fn default_metrics_port() -> u16 {
-    9090
-}
+    /// Response metadata key carrying the backend's version ("" = no version gate).
+    /// A serving backend reporting a version below min_backend_version is reported down.
+    #[serde(default)]
+    pub version_metadata_key: String,
 
-fn default_bind_address() -> String {
-    "0.0.0.0".to_string()
-}
+    /// Minimum dotted numeric version, e.g. "2.4" or "2.4.1"
+    #[serde(default)]
+    pub min_backend_version: String,
 
-fn default_grpc_connect_timeout() -> u64 {
-    1000
-}
+    /// Response metadata key carrying the backend's active connection count ("" = not read).
+    /// A serving backend reporting it is weighted by how much of load_weight_capacity is left.
+    #[serde(default)]
+    pub load_metadata_key: String,
 
-fn default_grpc_rpc_timeout() -> u64 {
-    1500
-}
+    /// Active connections at which a backend's weight bottoms out at 1%
+    #[serde(default = "default_load_weight_capacity")]
+    pub load_weight_capacity: u64,
 
-fn default_grpc_channel_cache_enabled() -> bool {
-    true
-}
+    /// gRPC health service name used when a request doesn't specify one ("" = overall server)
+    #[serde(default)]
+    pub default_grpc_service: String,
 
-impl Default for AgentConfig {
-    fn default() -> Self {
-        AgentConfig {
-            server_port: default_server_port(),
-            server_bind_address: default_bind_address(),
-            grpc_connect_timeout_ms: default_grpc_connect_timeout(),
-            grpc_rpc_timeout_ms: default_grpc_rpc_timeout(),
-            grpc_channel_cache_enabled: default_grpc_channel_cache_enabled(),
-            metrics_port: default_metrics_port(),
-            metrics_bind_address: default_bind_address(),
+    /// How requests without a `check=` token are checked: the gRPC health protocol or HTTP GET
+    #[serde(default)]
+    pub check_protocol: CheckProtocol,
+
+    /// Path requested by HTTP checks that don't name one; any 2xx answer means up
+    #[serde(default = "default_http_check_path")]
+    pub http_check_path: String,
+
+    /// Status reported when the backend answers SERVICE_UNKNOWN for a named service
+    #[serde(default = "default_service_unknown_status")]
+    pub service_unknown_status: HealthStatus,
+
+    /// Status reported when the backend answers UNKNOWN, e.g. while it is still starting up
+    #[serde(default = "default_unknown_status")]
+    pub unknown_status: HealthStatus,
+
+    /// Poll once more before reporting an UNKNOWN answer, to ride out startup transitions
+    #[serde(default)]
+    pub unknown_retry: bool,
+
+    /// Pause before the unknown_retry poll, capped by what is left of response_deadline_ms
+    #[serde(default = "default_unknown_retry_delay_ms")]
+    pub unknown_retry_delay_ms: u64,
+
+    /// Status reported when the Check RPC fails with RESOURCE_EXHAUSTED, i.e. the backend is
+    /// shedding load (None = down, like any other RPC error)
+    #[serde(default)]
+    pub resource_exhausted_status: Option<HealthStatus>,
+
+    /// Weight percent sent along with resource_exhausted_status
+    #[serde(default)]
+    pub resource_exhausted_weight: Option<u8>,
+
+    /// Append a short `# reason` comment to non-up agent responses
+    #[serde(default)]
+    pub verbose_response: bool,
+
+    /// Comment every answer the backend gave with its raw ServingStatus name, e.g.
+    /// `up # SERVING`, in place of the reason
+    #[serde(default)]
+    pub serving_status_comment: bool,
+
+    /// Run checks, log and count their results as usual, but answer every one with `up`, to
+    /// watch the agent's decisions before letting it gate traffic
+    #[serde(default)]
+    pub report_only: bool,
+
+    /// Single-word request answered with a one-line stats summary instead of a check ("" = disabled)
+    #[serde(default = "default_stats_command")]
+    pub stats_command: String,
+
+    /// How long a check result is reused for the same backend and service (0 = disabled).
+    /// Requests carrying an interval hint use half the hinted interval instead.
+    #[serde(default)]
+    pub result_cache_ttl_ms: u64,
+
+    /// After a backend recovers, report `up` with a weight ramping from 10% to 100% over
+    /// this many milliseconds (0 = disabled)
+    #[serde(default)]
+    pub slow_start_ms: u64,
+
+    /// Weight `up` answers by how many of the backend's last this-many checks were up, so a
+    /// flaky backend gets less traffic while it is serving (0 = disabled)
+    #[serde(default)]
+    pub success_weight_window: usize,
+
+    /// Weight percent for a success rate of zero; success_weight_window scales linearly from
+    /// here to 100% when every recent check was up
+    #[serde(default)]
+    pub success_weight_min_percent: u8,
+
+    /// Response to the first failure of a newly seen backend, to ride out deploy-time races.
+    /// Only a second consecutive failure reports down.
+    #[serde(default)]
+    pub first_failure_response: FirstFailureResponse,
+
+    /// Backends always reported down without a check, as "server" or "server:port" globs
+    #[serde(default)]
+    pub force_down_backends: Vec<String>,
+
+    /// Backends always reported up without a check; force_down_backends wins on overlap
+    #[serde(default)]
+    pub force_up_backends: Vec<String>,
+
+    /// Minimum interval between failure logs for the same backend (0 = log every failure).
+    /// Suppressed failures are counted in the next log line.
+    #[serde(default)]
+    pub error_log_interval_ms: u64,
+
+    /// Maximum gRPC checks in flight across all connections (0 = unlimited)
+    #[serde(default)]
+    pub max_concurrent_checks: usize,
+
+    /// Response when a check is rejected because max_concurrent_checks is reached
+    #[serde(default)]
+    pub over_capacity_response: OverCapacityResponse,
+
+    /// Maximum open HAProxy connections per listener (0 = unlimited)
+    #[serde(default)]
+    pub max_connections: usize,
+
+    /// Answer to connections beyond max_connections; closed without a status by default
+    #[serde(default)]
+    pub overload_response: OverloadResponse,
+
+    /// Remaps final response statuses before they are sent, e.g. { drain = "up" }
+    #[serde(default)]
+    pub response_status_map: HashMap<HealthStatus, HealthStatus>,
+
+    /// Additional listeners; when empty, a single listener uses server_bind_address/server_port
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+
+    /// Attach trace_id exemplars to check_duration_seconds for OpenMetrics scrapers
+    #[serde(default)]
+    pub metrics_exemplars: bool,
+
+    /// Serve /metrics and the admin endpoints on metrics_port; off when only pushing to [statsd]
+    #[serde(default = "default_metrics_endpoint_enabled")]
+    pub metrics_endpoint_enabled: bool,
+
+    /// Let the metrics server's /admin/overrides endpoint pin backends to a status, e.g. maint
+    #[serde(default)]
+    pub admin_overrides_enabled: bool,
+
+    #[serde(default = "default_bind_address")]
+    pub metrics_bind_address: String,
+
+    /// PEM certificate chain for serving the metrics endpoint over HTTPS; plain HTTP when unset
+    #[serde(default)]
+    pub metrics_tls_cert: Option<String>,
+
+    /// PEM private key for metrics_tls_cert
+    #[serde(default)]
+    pub metrics_tls_key: Option<String>,
+
+    #[serde(default)]
+    pub log_level: LogLevel,
+
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    #[serde(default)]
+    pub audit: AuditConfig,
+
+    #[serde(default)]
+    pub dns: DnsConfig,
+
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+
+    #[serde(default)]
+    pub statsd: StatsdConfig,
+
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+
+    #[serde(default)]
+    pub self_test: SelfTestConfig,
+
+    /// Treat validation warnings as errors, so a risky config fails to load
+    #[serde(default)]
+    pub strict_validation: bool,
+
+    /// Log every setting's final value and where it came from (default, env, file or cli)
+    /// at startup
+    #[serde(default)]
+    pub log_config_sources: bool,
+
+    #[serde(default)]
+    pub grpc_channel: GrpcChannelConfig,
+
+    /// Where each setting came from; filled in by load()
+    #[serde(skip)]
+    pub sources: ConfigSources,
+}
+
+// Where a configuration value came from, in increasing precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigSource {
+    #[default]
+    Default,
+    Env,
+    File,
+    Cli,
+}
+
+impl ConfigSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfigSource::Default => "default",
+            ConfigSource::Env => "env",
+            ConfigSource::File => "file",
+            ConfigSource::Cli => "cli",
+        }
+    }
+}
+
+/// Source of every setting a load step changed, keyed by dotted path ("logging.level").
+/// A step that sets a value to what it already was leaves its source alone.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources(BTreeMap<String, ConfigSource>);
+
+impl ConfigSources {
+    pub fn get(&self, setting: &str) -> ConfigSource {
+        self.0.get(setting).copied().unwrap_or_default()
+    }
+
+    /// Attribute every setting that differs between the two snapshots to `source`
+    fn record(
+        &mut self,
+        before: &BTreeMap<String, serde_json::Value>,
+        after: &BTreeMap<String, serde_json::Value>,
+        source: ConfigSource,
+    ) {
+        for (setting, value) in after {
+            if before.get(setting) != Some(value) {
+                self.0.insert(setting.clone(), source);
+            }
+        }
+    }
+}
+
+/// Settings whose values are never logged
+fn is_secret_setting(setting: &str) -> bool {
+    setting.ends_with("password") || setting.ends_with("token")
+}
+
+/// Flatten the config into dotted paths and leaf values; sections and maps are walked, lists are leaves
+fn setting_values(config: &AgentConfig) -> BTreeMap<String, serde_json::Value> {
+    fn walk(
+        prefix: &str,
+        value: serde_json::Value,
+        settings: &mut BTreeMap<String, serde_json::Value>,
+    ) {
+        match value {
+            serde_json::Value::Object(fields) if !fields.is_empty() => {
+                for (name, value) in fields {
+                    let path = if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{}.{}", prefix, name)
+                    };
+                    walk(&path, value, settings);
+                }
+            }
+            value => {
+                settings.insert(prefix.to_string(), value);
+            }
+        }
+    }
+
+    let mut settings = BTreeMap::new();
+    match serde_json::to_value(config) {
+        Ok(value) => walk("", value, &mut settings),
+        Err(e) => tracing::debug!(error = %e, "Config could not be flattened for provenance"),
+    }
+    settings
+}
+
+// T027: Default functions for AgentConfig
+fn default_server_port() -> u16 {
+    5555
+}
+
+fn default_metrics_port() -> u16 {
+    9090
+}
+
+fn default_bind_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_listen_backlog() -> u32 {
+    1024
+}
+
+fn default_persistent_connections() -> bool {
+    true
+}
+
+fn default_pipeline_depth() -> usize {
+    1
+}
+
+fn default_grpc_connect_timeout() -> u64 {
+    1000
+}
+
+fn default_user_agent() -> String {
+    concat!("haproxy-grpc-agent/", env!("CARGO_PKG_VERSION")).to_string()
+}
+
+fn default_http_check_path() -> String {
+    "/healthz".to_string()
+}
+
+fn default_grpc_health_check_path() -> String {
+    crate::checker::DEFAULT_HEALTH_CHECK_PATH.to_string()
+}
+
+/// Splits a comma-separated environment value, dropping empty items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn default_connect_backoff_max() -> u64 {
+    30_000
+}
+
+fn default_grpc_rpc_timeout() -> u64 {
+    1500
+}
+
+fn default_tls_alpn_protocols() -> Vec<String> {
+    vec!["h2".to_string()]
+}
+
+fn default_stats_command() -> String {
+    "stats".to_string()
+}
+
+fn default_grpc_channel_cache_enabled() -> bool {
+    true
+}
+
+fn default_channel_ready_timeout() -> u64 {
+    10
+}
+
+fn default_load_weight_capacity() -> u64 {
+    100
+}
+
+fn default_metrics_endpoint_enabled() -> bool {
+    true
+}
+
+fn default_service_unknown_status() -> HealthStatus {
+    HealthStatus::Down
+}
+
+fn default_unknown_status() -> HealthStatus {
+    HealthStatus::Down
+}
+
+fn default_unknown_retry_delay_ms() -> u64 {
+    100
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        AgentConfig {
+            server_port: default_server_port(),
+            server_bind_address: default_bind_address(),
+            listen_backlog: default_listen_backlog(),
+            bind_retry_ms: 0,
+            tcp_fast_open: false,
+            request_delimiter: RequestDelimiter::default(),
+            request_framing: RequestFraming::default(),
+            pipeline_depth: default_pipeline_depth(),
+            persistent_connections: default_persistent_connections(),
+            blank_line_response: BlankLineResponse::default(),
+            eof_grace_ms: 0,
+            idle_shutdown_ms: None,
+            client_allowlist: Vec::new(),
+            auth_token: String::new(),
+            grpc_connect_timeout_ms: default_grpc_connect_timeout(),
+            grpc_tls_connect_timeout_ms: None,
+            tls_alpn_protocols: default_tls_alpn_protocols(),
+            tls_ca_dir: None,
+            grpc_rpc_timeout_ms: default_grpc_rpc_timeout(),
+            connect_backoff_ms: 0,
+            connect_backoff_max_ms: default_connect_backoff_max(),
+            connect_backoff_jitter: BackoffJitter::default(),
+            response_deadline_ms: 0,
+            connect_within_deadline: false,
+            response_min_latency_ms: 0,
+            grpc_channel_cache_enabled: default_grpc_channel_cache_enabled(),
+            max_cached_channels: 0,
+            channel_ready_timeout_ms: default_channel_ready_timeout(),
+            channel_sweep_interval_ms: 0,
+            channel_key_mode: ChannelKeyMode::default(),
+            grpc_health_check_path: default_grpc_health_check_path(),
+            host_header_name: String::new(),
+            user_agent: default_user_agent(),
+            version_metadata_key: String::new(),
+            min_backend_version: String::new(),
+            load_metadata_key: String::new(),
+            load_weight_capacity: default_load_weight_capacity(),
+            default_grpc_service: String::new(),
+            check_protocol: CheckProtocol::default(),
+            http_check_path: default_http_check_path(),
+            service_unknown_status: default_service_unknown_status(),
+            unknown_status: default_unknown_status(),
+            unknown_retry: false,
+            unknown_retry_delay_ms: default_unknown_retry_delay_ms(),
+            resource_exhausted_status: None,
+            resource_exhausted_weight: None,
+            verbose_response: false,
+            serving_status_comment: false,
+            report_only: false,
+            stats_command: default_stats_command(),
+            result_cache_ttl_ms: 0,
+            slow_start_ms: 0,
+            success_weight_window: 0,
+            success_weight_min_percent: 0,
+            first_failure_response: FirstFailureResponse::default(),
+            force_down_backends: Vec::new(),
+            force_up_backends: Vec::new(),
+            error_log_interval_ms: 0,
+            max_concurrent_checks: 0,
+            over_capacity_response: OverCapacityResponse::default(),
+            max_connections: 0,
+            overload_response: OverloadResponse::default(),
+            response_status_map: HashMap::new(),
+            listeners: Vec::new(),
+            metrics_port: default_metrics_port(),
+            metrics_exemplars: false,
+            metrics_endpoint_enabled: default_metrics_endpoint_enabled(),
+            admin_overrides_enabled: false,
+            metrics_bind_address: default_bind_address(),
+            metrics_tls_cert: None,
+            metrics_tls_key: None,
             log_level: LogLevel::default(),
             log_format: LogFormat::default(),
             logging: LoggingConfig::default(),
+            audit: AuditConfig::default(),
+            dns: DnsConfig::default(),
+            proxy: ProxyConfig::default(),
+            statsd: StatsdConfig::default(),
+            chaos: ChaosConfig::default(),
+            self_test: SelfTestConfig::default(),
+            strict_validation: false,
+            log_config_sources: false,
+            grpc_channel: GrpcChannelConfig::default(),
+            sources: ConfigSources::default(),
         }
     }
 }
 
+// Severity of a configuration validation issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// One problem found by validation; `code` is a stable identifier for tooling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
 // T031: CLI arguments structure
 #[derive(Debug, Parser)]
 #[command(name = "haproxy-grpc-agent")]
@@ -210,6 +1217,54 @@ pub struct CliArgs {
     #[arg(long)]
     pub server_bind: Option<String>,
 
+    /// Accept backlog for the agent listener socket
+    #[arg(long)]
+    pub listen_backlog: Option<u32>,
+
+    /// Enable TCP Fast Open on the agent listener
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub tcp_fast_open: Option<bool>,
+
+    /// Retry binding the listener for this many milliseconds while the address is in use (0 = no retry)
+    #[arg(long)]
+    pub bind_retry: Option<u64>,
+
+    /// Request delimiter (newline, null)
+    #[arg(long, value_enum)]
+    pub request_delimiter: Option<RequestDelimiter>,
+
+    /// Request framing (line, length-prefixed)
+    #[arg(long, value_enum)]
+    pub request_framing: Option<RequestFraming>,
+
+    /// Pipelined requests checked concurrently per connection (1 = one at a time)
+    #[arg(long)]
+    pub pipeline_depth: Option<usize>,
+
+    /// Keep connections open for further requests (false = one request per connection)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub persistent_connections: Option<bool>,
+
+    /// Answer to a blank request line (ignore, up, down)
+    #[arg(long, value_enum)]
+    pub blank_line_response: Option<BlankLineResponse>,
+
+    /// Grace after a peer EOF before closing the connection in milliseconds (0 = close at once)
+    #[arg(long)]
+    pub eof_grace: Option<u64>,
+
+    /// Exit after this many milliseconds without connections or checks
+    #[arg(long)]
+    pub idle_shutdown: Option<u64>,
+
+    /// Comma-separated CIDRs allowed to connect (empty = allow all)
+    #[arg(long, value_delimiter = ',')]
+    pub client_allowlist: Option<Vec<String>>,
+
+    /// Token every connection must send as its first line (empty = no authentication)
+    #[arg(long)]
+    pub auth_token: Option<String>,
+
     /// HTTP port for Prometheus metrics
     #[arg(long)]
     pub metrics_port: Option<u16>,
@@ -218,637 +1273,3327 @@ pub struct CliArgs {
     #[arg(long)]
     pub metrics_bind: Option<String>,
 
-    /// gRPC connection timeout in milliseconds
+    /// PEM certificate chain for serving metrics over HTTPS
     #[arg(long)]
-    pub grpc_connect_timeout: Option<u64>,
+    pub metrics_tls_cert: Option<String>,
 
-    /// gRPC RPC timeout in milliseconds
+    /// PEM private key for --metrics-tls-cert
     #[arg(long)]
-    pub grpc_rpc_timeout: Option<u64>,
+    pub metrics_tls_key: Option<String>,
 
-    /// Enable or disable gRPC channel caching (default: true)
+    /// Attach trace_id exemplars to the check latency histogram (OpenMetrics scrapes only)
     #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
-    pub grpc_channel_cache: Option<bool>,
+    pub metrics_exemplars: Option<bool>,
 
-    /// Log level
-    #[arg(long, value_enum)]
-    pub log_level: Option<LogLevel>,
+    /// Serve the metrics and admin HTTP endpoints (default: true)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub metrics_endpoint: Option<bool>,
 
-    /// Log format
-    #[arg(long, value_enum)]
-    pub log_format: Option<LogFormat>,
+    /// Allow setting and clearing per-backend status overrides on /admin/overrides
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub admin_overrides: Option<bool>,
 
-    /// Log destination (console or file)
-    #[arg(long, value_enum)]
-    pub log_destination: Option<LogDestination>,
+    /// gRPC connection timeout in milliseconds
+    #[arg(long)]
+    pub grpc_connect_timeout: Option<u64>,
 
-    /// Log file path (required when --log-destination=file)
+    /// gRPC connection timeout for SSL backends in milliseconds
     #[arg(long)]
-    pub log_file_path: Option<String>,
+    pub grpc_tls_connect_timeout: Option<u64>,
 
-    /// Log file rotation strategy (never, daily, hourly)
-    #[arg(long, value_enum)]
-    pub log_file_rotation: Option<LogRotation>,
+    /// Comma-separated ALPN protocols offered to ssl-insecure backends (default: h2)
+    #[arg(long, value_delimiter = ',')]
+    pub tls_alpn_protocols: Option<Vec<String>>,
 
-    /// Maximum number of rotated log files to keep
+    /// Directory of PEM CA certificates trusted for ssl backends
     #[arg(long)]
-    pub log_file_max_files: Option<usize>,
-}
+    pub tls_ca_dir: Option<String>,
 
-impl AgentConfig {
-    // T029: Config validation function
-    pub fn validate(&self) -> Result<()> {
-        // Validate server port
-        if self.server_port == 0 {
-            anyhow::bail!("server_port must be between 1 and 65535");
-        }
-
-        // Validate metrics port
-        if self.metrics_port == 0 {
-            anyhow::bail!("metrics_port must be between 1 and 65535");
+    /// gRPC RPC timeout in milliseconds
+    #[arg(long)]
+    pub grpc_rpc_timeout: Option<u64>,
+
+    /// Reconnect backoff after a failed connect in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub connect_backoff: Option<u64>,
+
+    /// Maximum reconnect backoff in milliseconds
+    #[arg(long)]
+    pub connect_backoff_max: Option<u64>,
+
+    /// Reconnect backoff jitter (none, full)
+    #[arg(long)]
+    pub connect_backoff_jitter: Option<BackoffJitter>,
+
+    /// End-to-end check response deadline in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub response_deadline: Option<u64>,
+
+    /// Cap new connects to the time left before the response deadline
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub connect_within_deadline: Option<bool>,
+
+    /// Minimum response latency in milliseconds, for testing only (0 = disabled)
+    #[arg(long)]
+    pub response_min_latency: Option<u64>,
+
+    /// Enable or disable gRPC channel caching (default: true)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub grpc_channel_cache: Option<bool>,
+
+    /// Maximum cached gRPC channels, evicting the least recently used (0 = unlimited)
+    #[arg(long)]
+    pub max_cached_channels: Option<usize>,
+
+    /// Readiness check budget for reusing a cached channel in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub channel_ready_timeout: Option<u64>,
+
+    /// Idle channel sweep interval in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub channel_sweep_interval: Option<u64>,
+
+    /// Channel cache key normalization (exact, lowercase, resolved)
+    #[arg(long, value_enum)]
+    pub channel_key_mode: Option<ChannelKeyMode>,
+
+    /// Method path of the gRPC health Check RPC
+    #[arg(long)]
+    pub grpc_health_check_path: Option<String>,
+
+    /// Metadata header carrying the proxy host (default: host; empty = not sent)
+    #[arg(long)]
+    pub host_header_name: Option<String>,
+
+    /// user-agent sent on health checks (default: haproxy-grpc-agent/<version>)
+    #[arg(long)]
+    pub user_agent: Option<String>,
+
+    /// Response metadata key carrying the backend version (empty = no version gate)
+    #[arg(long)]
+    pub version_metadata_key: Option<String>,
+
+    /// Minimum backend version reported under the version metadata key
+    #[arg(long)]
+    pub min_backend_version: Option<String>,
+
+    /// Response metadata key carrying the backend's active connections (empty = not read)
+    #[arg(long)]
+    pub load_metadata_key: Option<String>,
+
+    /// Active connections at which the derived weight bottoms out at 1%
+    #[arg(long)]
+    pub load_weight_capacity: Option<u64>,
+
+    /// Default gRPC health service name for requests without an explicit service
+    #[arg(long)]
+    pub default_grpc_service: Option<String>,
+
+    /// Protocol for requests without a check= token (grpc or http)
+    #[arg(long, value_enum)]
+    pub check_protocol: Option<CheckProtocol>,
+
+    /// Path requested by HTTP checks that don't name one
+    #[arg(long)]
+    pub http_check_path: Option<String>,
+
+    /// Append a failure reason comment to agent responses (e.g. "down # connect timeout")
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub verbose_response: Option<bool>,
+
+    /// Comment answers with the backend's raw ServingStatus (e.g. "up # SERVING")
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub serving_status_comment: Option<bool>,
+
+    /// Check as usual but answer every check with "up"
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub report_only: Option<bool>,
+
+    /// Keyword for the stats command; empty disables it
+    #[arg(long)]
+    pub stats_command: Option<String>,
+
+    /// Check result cache TTL in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub result_cache_ttl: Option<u64>,
+
+    /// Slow-start weight ramp after recovery in milliseconds (0 = disabled)
+    #[arg(long)]
+    pub slow_start: Option<u64>,
+
+    /// Weight up answers by the success rate of this many recent checks (0 = disabled)
+    #[arg(long)]
+    pub success_weight_window: Option<usize>,
+
+    /// Weight percent at a zero success rate, scaling linearly to 100%
+    #[arg(long)]
+    pub success_weight_min_percent: Option<u8>,
+
+    /// Response to the first failure of a newly seen backend
+    #[arg(long, value_enum)]
+    pub first_failure_response: Option<FirstFailureResponse>,
+
+    /// Comma-separated backend globs always reported down
+    #[arg(long, value_delimiter = ',')]
+    pub force_down_backends: Option<Vec<String>>,
+
+    /// Comma-separated backend globs always reported up
+    #[arg(long, value_delimiter = ',')]
+    pub force_up_backends: Option<Vec<String>>,
+
+    /// Minimum interval between failure logs per backend in milliseconds (0 = log all)
+    #[arg(long)]
+    pub error_log_interval: Option<u64>,
+
+    /// Maximum concurrent gRPC checks (0 = unlimited)
+    #[arg(long)]
+    pub max_concurrent_checks: Option<usize>,
+
+    /// Response when max concurrent checks is reached
+    #[arg(long, value_enum)]
+    pub over_capacity_response: Option<OverCapacityResponse>,
+
+    /// Maximum open HAProxy connections per listener (0 = unlimited)
+    #[arg(long)]
+    pub max_connections: Option<usize>,
+
+    /// Answer to connections beyond max connections (close, up, down, drain)
+    #[arg(long, value_enum)]
+    pub overload_response: Option<OverloadResponse>,
+
+    /// Log level
+    #[arg(long, value_enum)]
+    pub log_level: Option<LogLevel>,
+
+    /// Log format
+    #[arg(long, value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    /// Log destination (console, file or both)
+    #[arg(long, value_enum)]
+    pub log_destination: Option<LogDestination>,
+
+    /// Console log format, overriding --log-format
+    #[arg(long, value_enum)]
+    pub log_console_format: Option<LogFormat>,
+
+    /// File log format, overriding --log-format
+    #[arg(long, value_enum)]
+    pub log_file_format: Option<LogFormat>,
+
+    /// Log file path (required when --log-destination is file or both)
+    #[arg(long)]
+    pub log_file_path: Option<String>,
+
+    /// Log file rotation strategy (never, daily, hourly)
+    #[arg(long, value_enum)]
+    pub log_file_rotation: Option<LogRotation>,
+
+    /// Maximum number of rotated log files to keep
+    #[arg(long)]
+    pub log_file_max_files: Option<usize>,
+
+    /// Forward proxy host; backend connections are tunnelled through it with HTTP CONNECT
+    #[arg(long)]
+    pub proxy_host: Option<String>,
+
+    /// Forward proxy port
+    #[arg(long)]
+    pub proxy_port: Option<u16>,
+
+    /// StatsD host; metrics are pushed to it over UDP
+    #[arg(long)]
+    pub statsd_host: Option<String>,
+
+    /// StatsD port
+    #[arg(long)]
+    pub statsd_port: Option<u16>,
+
+    /// StatsD wire format (statsd, dogstatsd)
+    #[arg(long, value_enum)]
+    pub statsd_format: Option<StatsdFormat>,
+
+    /// Enable or disable the audit log of every request and response (default: false)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub audit_log: Option<bool>,
+
+    /// Minimum audit record level (info records every check, warn only failures)
+    #[arg(long, value_enum)]
+    pub audit_log_level: Option<LogLevel>,
+
+    /// Audit log file path (stdout when unset)
+    #[arg(long)]
+    pub audit_log_file: Option<String>,
+
+    /// Run the startup self-test, print the results and exit (non-zero on failure)
+    #[arg(long)]
+    pub self_test: bool,
+
+    /// Run the self-test before accepting traffic and refuse to start if it fails
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub self_test_on_startup: Option<bool>,
+
+    /// Canary backend for the self-test, as an agent request line ("<server> <port> <ssl-flag> <proxy-host>")
+    #[arg(long)]
+    pub self_test_canary: Option<String>,
+
+    /// Treat configuration warnings as errors
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Log each setting's value and source at startup
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub log_config_sources: Option<bool>,
+
+    /// Comma-separated DNS nameservers for backend hostnames (ip or ip:port)
+    #[arg(long, value_delimiter = ',')]
+    pub dns_nameservers: Option<Vec<String>>,
+
+    /// DNS query timeout in milliseconds
+    #[arg(long)]
+    pub dns_timeout: Option<u64>,
+}
+
+impl AgentConfig {
+    /// Longest a check can take: the slower connect timeout plus the RPC timeout,
+    /// capped by response_deadline_ms when that is set
+    pub fn check_timeout_budget_ms(&self) -> u64 {
+        let connect_timeout = self
+            .grpc_connect_timeout_ms
+            .max(self.grpc_tls_connect_timeout_ms.unwrap_or(0));
+        let budget = connect_timeout + self.grpc_rpc_timeout_ms;
+        match self.response_deadline_ms {
+            0 => budget,
+            deadline => budget.min(deadline),
         }
+    }
 
-        // Validate ports don't conflict
-        if self.server_port == self.metrics_port {
-            anyhow::bail!(
-                "server_port ({}) and metrics_port ({}) cannot be the same",
-                self.server_port,
-                self.metrics_port
-            );
+    /// Connect timeout for a backend, using the TLS-specific timeout for SSL backends when set
+    pub fn connect_timeout_ms(&self, ssl_flag: SslFlag) -> u64 {
+        match (ssl_flag, self.grpc_tls_connect_timeout_ms) {
+            (flag, Some(timeout)) if flag.is_tls() => timeout,
+            _ => self.grpc_connect_timeout_ms,
         }
+    }
 
-        // Validate timeouts
-        if self.grpc_connect_timeout_ms == 0 {
-            anyhow::bail!("grpc_connect_timeout_ms must be greater than 0");
+    /// Listener definitions to start: the [[listeners]] entries, or the single top-level listener
+    pub fn listener_configs(&self) -> Vec<ListenerConfig> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
         }
 
-        if self.grpc_rpc_timeout_ms == 0 {
-            anyhow::bail!("grpc_rpc_timeout_ms must be greater than 0");
+        vec![ListenerConfig {
+            bind_address: Some(self.server_bind_address.clone()),
+            port: self.server_port,
+            verbose_response: None,
+            response_status_map: None,
+            request_framing: None,
+            check_protocol: None,
+        }]
+    }
+
+    /// Effective config for one listener: this config with the listener's overrides applied
+    pub fn for_listener(&self, listener: &ListenerConfig) -> AgentConfig {
+        let mut config = self.clone();
+        config.listeners = Vec::new();
+
+        if let Some(bind_address) = &listener.bind_address {
+            config.server_bind_address = bind_address.clone();
         }
+        config.server_port = listener.port;
 
-        // Validate logging config
-        if matches!(self.logging.destination, LogDestination::File) {
-            match &self.logging.file_path {
-                None => anyhow::bail!(
-                    "logging.file_path is required when logging.destination is \"file\""
-                ),
-                Some(path) if path.is_empty() => anyhow::bail!(
-                    "logging.file_path must not be empty when logging.destination is \"file\""
-                ),
-                _ => {}
-            }
+        if let Some(verbose) = listener.verbose_response {
+            config.verbose_response = verbose;
+        }
+        if let Some(status_map) = &listener.response_status_map {
+            config.response_status_map = status_map.clone();
+        }
+        if let Some(framing) = listener.request_framing {
+            config.request_framing = framing;
+        }
+        if let Some(protocol) = listener.check_protocol {
+            config.check_protocol = protocol;
         }
 
-        if let Some(max_files) = self.logging.file_max_files
-            && max_files == 0
-        {
-            anyhow::bail!("logging.file_max_files must be greater than 0");
+        config
+    }
+
+    // T029: Config validation function
+    /// Fails on the first error. Warnings are printed to stderr, or fail validation as well
+    /// when strict_validation is set.
+    pub fn validate(&self) -> Result<()> {
+        self.check_errors()?;
+
+        let warnings = self.warnings();
+        if self.strict_validation && !warnings.is_empty() {
+            let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+            anyhow::bail!(
+                "strict validation treats warnings as errors: {}",
+                messages.join("; ")
+            );
         }
+        for warning in &warnings {
+            eprintln!("WARNING: {}", warning.message);
+        }
+
+        Ok(())
+    }
+
+    /// Every validation issue: the first error, if any, followed by all warnings
+    pub fn validation_issues(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if let Err(e) = self.check_errors() {
+            issues.push(ValidationIssue {
+                severity: Severity::Error,
+                code: "invalid_config",
+                message: format!("{:#}", e),
+            });
+        }
+        issues.extend(self.warnings());
+        issues
+    }
+
+    /// Settings that are valid but likely to misbehave
+    pub fn warnings(&self) -> Vec<ValidationIssue> {
+        let mut warnings = Vec::new();
+        let mut warn = |code, message| {
+            warnings.push(ValidationIssue {
+                severity: Severity::Warning,
+                code,
+                message,
+            })
+        };
 
-        // Warn if file_max_files is set without rotation
         if self.logging.file_max_files.is_some() && self.logging.file_rotation.is_none() {
-            eprintln!(
-                "WARNING: logging.file_max_files is set but logging.file_rotation is not. \
+            warn(
+                "file_max_files_without_rotation",
+                "logging.file_max_files is set but logging.file_rotation is not. \
                  file_max_files has no effect without rotation enabled."
+                    .to_string(),
             );
         }
 
-        // Validate total timeout is reasonable (should be < 2000ms for HAProxy)
-        let total_timeout = self.grpc_connect_timeout_ms + self.grpc_rpc_timeout_ms;
+        // Should be < 2000ms, HAProxy's default agent-check timeout
+        let total_timeout = self.check_timeout_budget_ms();
         if total_timeout >= 2000 {
-            eprintln!(
-                "WARNING: Total gRPC timeout ({}ms) is >= 2000ms (HAProxy default timeout). \
-                Consider reducing timeouts to avoid agent-check timeouts.",
-                total_timeout
+            warn(
+                "timeout_budget_too_high",
+                format!(
+                    "Total gRPC timeout ({}ms) is >= 2000ms (HAProxy default timeout). \
+                     Consider reducing timeouts, or setting response_deadline_ms below 2000, \
+                     to avoid agent-check timeouts.",
+                    total_timeout
+                ),
             );
         }
 
-        Ok(())
-    }
-
-    // T030-T033: Load configuration with precedence: CLI > file > env > defaults
-    pub fn load() -> Result<Self> {
-        // Parse CLI arguments
-        let cli_args = CliArgs::parse();
+        // Timeouts the response deadline always preempts are dead settings
+        if self.response_deadline_ms > 0 {
+            let connect_timeout = self
+                .grpc_connect_timeout_ms
+                .max(self.grpc_tls_connect_timeout_ms.unwrap_or(0));
+            if connect_timeout >= self.response_deadline_ms {
+                warn(
+                    "connect_timeout_exceeds_deadline",
+                    format!(
+                        "Connect timeout ({}ms) is >= response_deadline_ms ({}ms), so a slow \
+                         connect always runs into the deadline and no time is left for the RPC. \
+                         Set the connect timeout well below the deadline, e.g. under half of it.",
+                        connect_timeout, self.response_deadline_ms
+                    ),
+                );
+            }
+            if self.grpc_rpc_timeout_ms >= self.response_deadline_ms {
+                warn(
+                    "rpc_timeout_exceeds_deadline",
+                    format!(
+                        "grpc_rpc_timeout_ms ({}ms) is >= response_deadline_ms ({}ms), so a slow \
+                         RPC is always cut off by the deadline rather than its own timeout. \
+                         Lower grpc_rpc_timeout_ms below the deadline minus the connect timeout.",
+                        self.grpc_rpc_timeout_ms, self.response_deadline_ms
+                    ),
+                );
+            }
+        }
 
-        // Start with defaults
-        let mut config = AgentConfig::default();
+        if self.response_min_latency_ms > 0 {
+            warn(
+                "response_min_latency_set",
+                format!(
+                    "response_min_latency_ms ({}ms) delays every answer on purpose. It is a \
+                     testing knob; never use it in production.",
+                    self.response_min_latency_ms
+                ),
+            );
+        }
 
-        // T030: Load from environment variables (if not using config file)
-        if cli_args.config.is_none() {
-            config = Self::load_from_env(config)?;
+        if self.connect_within_deadline && self.response_deadline_ms == 0 {
+            warn(
+                "connect_within_deadline_without_deadline",
+                "connect_within_deadline is set but response_deadline_ms is not, so there is no \
+                 budget to cap connects to."
+                    .to_string(),
+            );
         }
 
-        // T032: Load from config file if specified
-        if let Some(config_path) = &cli_args.config {
-            config = Self::load_from_file(config_path)?;
+        if !self.persistent_connections && self.pipeline_depth > 1 {
+            warn(
+                "pipeline_depth_without_persistent_connections",
+                format!(
+                    "pipeline_depth is {} but persistent_connections is off, so each connection \
+                     carries a single request and nothing is pipelined.",
+                    self.pipeline_depth
+                ),
+            );
         }
 
-        // T031: Apply CLI overrides (highest precedence)
-        config = Self::apply_cli_overrides(config, cli_args);
+        // The TLS connect timeout covers the handshake on top of the TCP connect
+        if let Some(tls_timeout) = self.grpc_tls_connect_timeout_ms
+            && tls_timeout < self.grpc_connect_timeout_ms
+        {
+            warn(
+                "tls_connect_timeout_below_connect_timeout",
+                format!(
+                    "grpc_tls_connect_timeout_ms ({}ms) is shorter than grpc_connect_timeout_ms \
+                     ({}ms), but it has to cover the TLS handshake as well as the TCP connect. \
+                     Leave it unset to reuse grpc_connect_timeout_ms, or raise it.",
+                    tls_timeout, self.grpc_connect_timeout_ms
+                ),
+            );
+        }
 
-        // T034: Fail-fast validation
-        config
-            .validate()
-            .context("Configuration validation failed")?;
+        // /admin/overrides takes no credentials, so only the bind address keeps it private
+        let metrics_is_loopback = self.metrics_bind_address == "localhost"
+            || self
+                .metrics_bind_address
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        if self.admin_overrides_enabled && self.metrics_endpoint_enabled && !metrics_is_loopback {
+            warn(
+                "admin_overrides_exposed",
+                format!(
+                    "admin_overrides_enabled is set and the metrics server binds {}, so anyone \
+                     who can reach metrics_port can pin backends to a status without \
+                     authenticating. Bind metrics_bind_address to 127.0.0.1 or firewall the port.",
+                    self.metrics_bind_address
+                ),
+            );
+        }
 
-        Ok(config)
+        warnings
     }
 
-    // T030: Load configuration from environment variables
-    fn load_from_env(mut config: AgentConfig) -> Result<Self> {
-        if let Ok(port) = std::env::var("HAPROXY_AGENT_SERVER_PORT") {
-            config.server_port = port.parse().context("Invalid HAPROXY_AGENT_SERVER_PORT")?;
+    /// Hard validation errors; the first one found is returned
+    fn check_errors(&self) -> Result<()> {
+        // Unix socket listeners ignore server_port
+        let server_is_unix =
+            ListenAddr::parse(&self.server_bind_address, self.server_port).is_unix();
+
+        // Validate server port
+        if !server_is_unix && self.server_port == 0 {
+            anyhow::bail!("server_port must be between 1 and 65535");
         }
 
-        if let Ok(bind) = std::env::var("HAPROXY_AGENT_SERVER_BIND") {
-            config.server_bind_address = bind;
+        // Validate metrics port
+        if self.metrics_port == 0 {
+            anyhow::bail!("metrics_port must be between 1 and 65535");
         }
 
-        if let Ok(port) = std::env::var("HAPROXY_AGENT_METRICS_PORT") {
-            config.metrics_port = port.parse().context("Invalid HAPROXY_AGENT_METRICS_PORT")?;
+        // Validate ports don't conflict
+        if !server_is_unix && self.server_port == self.metrics_port {
+            anyhow::bail!(
+                "server_port ({}) and metrics_port ({}) cannot be the same",
+                self.server_port,
+                self.metrics_port
+            );
         }
 
-        if let Ok(bind) = std::env::var("HAPROXY_AGENT_METRICS_BIND") {
-            config.metrics_bind_address = bind;
+        if let Some(weight) = self.resource_exhausted_weight {
+            if self.resource_exhausted_status.is_none() {
+                anyhow::bail!("resource_exhausted_weight requires resource_exhausted_status");
+            }
+            if weight > 100 {
+                anyhow::bail!(
+                    "resource_exhausted_weight must be a percentage from 0 to 100, got {}",
+                    weight
+                );
+            }
         }
 
-        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_GRPC_CONNECT_TIMEOUT") {
-            config.grpc_connect_timeout_ms = timeout
-                .parse()
-                .context("Invalid HAPROXY_AGENT_GRPC_CONNECT_TIMEOUT")?;
+        if self.pipeline_depth == 0 {
+            anyhow::bail!("pipeline_depth must be at least 1");
         }
 
-        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_GRPC_RPC_TIMEOUT") {
-            config.grpc_rpc_timeout_ms = timeout
-                .parse()
-                .context("Invalid HAPROXY_AGENT_GRPC_RPC_TIMEOUT")?;
+        if self.listen_backlog == 0 {
+            anyhow::bail!("listen_backlog must be greater than 0");
         }
 
-        if let Ok(level) = std::env::var("HAPROXY_AGENT_LOG_LEVEL") {
-            config.log_level = match level.to_lowercase().as_str() {
-                "trace" => LogLevel::Trace,
-                "debug" => LogLevel::Debug,
-                "info" => LogLevel::Info,
-                "warn" => LogLevel::Warn,
-                "error" => LogLevel::Error,
-                _ => anyhow::bail!("Invalid log level: {}", level),
+        // Validate listener definitions
+        let mut listener_addrs = std::collections::HashSet::new();
+        for listener in &self.listeners {
+            let bind_address = listener
+                .bind_address
+                .as_deref()
+                .unwrap_or(&self.server_bind_address);
+            let addr = ListenAddr::parse(bind_address, listener.port);
+
+            if !addr.is_unix() && listener.port == 0 {
+                anyhow::bail!("listeners[].port must be between 1 and 65535");
+            }
+            if !addr.is_unix() && listener.port == self.metrics_port {
+                anyhow::bail!(
+                    "listener port ({}) and metrics_port ({}) cannot be the same",
+                    listener.port,
+                    self.metrics_port
+                );
+            }
+            if !listener_addrs.insert(addr.to_string()) {
+                anyhow::bail!("Duplicate listener address: {}", addr);
+            }
+        }
+
+        if crate::checker::parse_health_check_path(&self.grpc_health_check_path).is_none() {
+            anyhow::bail!(
+                "grpc_health_check_path must be an absolute path starting with '/', got '{}'",
+                self.grpc_health_check_path
+            );
+        }
+
+        if !self.http_check_path.starts_with('/')
+            || self.http_check_path.contains(char::is_whitespace)
+        {
+            anyhow::bail!(
+                "http_check_path must be an absolute path starting with '/' without whitespace, got '{}'",
+                self.http_check_path
+            );
+        }
+
+        // Pseudo-headers and binary (-bin) keys are rejected along with malformed names
+        if !self.host_header_name.is_empty()
+            && MetadataKey::<Ascii>::from_bytes(self.host_header_name.as_bytes()).is_err()
+        {
+            anyhow::bail!(
+                "host_header_name must be a valid ASCII metadata header name, got '{}'",
+                self.host_header_name
+            );
+        }
+
+        if HeaderValue::from_str(&self.user_agent).is_err() {
+            anyhow::bail!(
+                "user_agent must be printable ASCII, got '{}'",
+                self.user_agent.escape_debug()
+            );
+        }
+
+        if !self.version_metadata_key.is_empty() {
+            if MetadataKey::<Ascii>::from_bytes(self.version_metadata_key.as_bytes()).is_err() {
+                anyhow::bail!(
+                    "version_metadata_key must be a valid ASCII metadata header name, got '{}'",
+                    self.version_metadata_key
+                );
+            }
+            if crate::checker::parse_version(&self.min_backend_version).is_none() {
+                anyhow::bail!(
+                    "min_backend_version must be a dotted numeric version such as \"2.4.1\" when version_metadata_key is set, got '{}'",
+                    self.min_backend_version
+                );
+            }
+        }
+
+        if !self.load_metadata_key.is_empty()
+            && MetadataKey::<Ascii>::from_bytes(self.load_metadata_key.as_bytes()).is_err()
+        {
+            anyhow::bail!(
+                "load_metadata_key must be a valid ASCII metadata header name, got '{}'",
+                self.load_metadata_key
+            );
+        }
+
+        if self.load_weight_capacity == 0 {
+            anyhow::bail!("load_weight_capacity must be greater than 0");
+        }
+
+        if self.success_weight_min_percent > 100 {
+            anyhow::bail!(
+                "success_weight_min_percent must be at most 100, got {}",
+                self.success_weight_min_percent
+            );
+        }
+
+        // Validate timeouts
+        if self.grpc_connect_timeout_ms == 0 {
+            anyhow::bail!("grpc_connect_timeout_ms must be greater than 0");
+        }
+
+        if self.grpc_tls_connect_timeout_ms == Some(0) {
+            anyhow::bail!("grpc_tls_connect_timeout_ms must be greater than 0");
+        }
+
+        if self.idle_shutdown_ms == Some(0) {
+            anyhow::bail!("idle_shutdown_ms must be greater than 0");
+        }
+
+        if self.grpc_rpc_timeout_ms == 0 {
+            anyhow::bail!("grpc_rpc_timeout_ms must be greater than 0");
+        }
+
+        if self.connect_backoff_ms > 0 && self.connect_backoff_max_ms < self.connect_backoff_ms {
+            anyhow::bail!(
+                "connect_backoff_max_ms ({}) must not be less than connect_backoff_ms ({})",
+                self.connect_backoff_max_ms,
+                self.connect_backoff_ms
+            );
+        }
+
+        // Validate ALPN protocols (RFC 7301: 1-255 bytes each)
+        if self.tls_alpn_protocols.is_empty() {
+            anyhow::bail!("tls_alpn_protocols must not be empty");
+        }
+        if let Some(protocol) = self
+            .tls_alpn_protocols
+            .iter()
+            .find(|p| p.is_empty() || p.len() > 255)
+        {
+            anyhow::bail!(
+                "tls_alpn_protocols entries must be 1-255 bytes long, got '{}'",
+                protocol
+            );
+        }
+
+        // Loaded here too so a bad CA file fails startup instead of every ssl check
+        #[cfg(feature = "tls")]
+        if let Some(dir) = &self.tls_ca_dir {
+            crate::tls::load_ca_dir(std::path::Path::new(dir)).context("Invalid tls_ca_dir")?;
+        }
+
+        match (&self.metrics_tls_cert, &self.metrics_tls_key) {
+            (None, None) => {}
+            #[cfg(feature = "tls")]
+            (Some(cert), Some(key)) => {
+                crate::tls::load_server_config(
+                    std::path::Path::new(cert),
+                    std::path::Path::new(key),
+                )
+                .context("Invalid metrics_tls_cert/metrics_tls_key")?;
+            }
+            #[cfg(not(feature = "tls"))]
+            (Some(_), Some(_)) => anyhow::bail!(
+                "metrics_tls_cert is set but TLS support is not compiled in (build with the `tls` feature)"
+            ),
+            _ => anyhow::bail!("metrics_tls_cert and metrics_tls_key must be set together"),
+        }
+
+        // Validate StatsD config
+        if self.statsd.host.as_deref() == Some("") {
+            anyhow::bail!("statsd.host must not be empty when set");
+        }
+        if self.statsd.host.is_some() && self.statsd.flush_interval_ms == 0 {
+            anyhow::bail!("statsd.flush_interval_ms must be greater than 0");
+        }
+
+        // Validate proxy config
+        if self.proxy.host.as_deref() == Some("") {
+            anyhow::bail!("proxy.host must not be empty when set");
+        }
+        if self.proxy.port == 0 {
+            anyhow::bail!("proxy.port must be greater than 0");
+        }
+        if self.proxy.username.is_some() != self.proxy.password.is_some() {
+            anyhow::bail!("proxy.username and proxy.password must be set together");
+        }
+
+        if self.stats_command.split_whitespace().count() > 1 {
+            anyhow::bail!("stats_command must be a single word");
+        }
+
+        // Surrounding whitespace is trimmed from the line HAProxy sends, so it can't be part of the token
+        if self
+            .auth_token
+            .chars()
+            .any(|c| c.is_whitespace() || c == '\0')
+        {
+            anyhow::bail!("auth_token must not contain whitespace or NUL characters");
+        }
+
+        // Validate self-test canary
+        if let Some(canary) = &self.self_test.canary {
+            crate::protocol::parse_request(canary).map_err(|e| {
+                anyhow::anyhow!("self_test.canary is not a valid agent request: {}", e)
+            })?;
+        }
+
+        // Validate chaos config
+        for (name, probability) in [
+            ("chaos.latency_probability", self.chaos.latency_probability),
+            ("chaos.down_probability", self.chaos.down_probability),
+        ] {
+            if !(0.0..=1.0).contains(&probability) {
+                anyhow::bail!("{} must be between 0.0 and 1.0", name);
+            }
+        }
+        if cfg!(not(feature = "chaos")) && self.chaos.is_enabled() {
+            anyhow::bail!(
+                "[chaos] is set but fault injection is not compiled in (build with the `chaos` feature)"
+            );
+        }
+
+        // Validate DNS config
+        self.dns.nameserver_addrs()?;
+        if self.dns.timeout_ms == 0 {
+            anyhow::bail!("dns.timeout_ms must be greater than 0");
+        }
+
+        if let Some(cidr) = self
+            .client_allowlist
+            .iter()
+            .find(|cidr| IpCidr::parse(cidr).is_none())
+        {
+            anyhow::bail!(
+                "client_allowlist entries must be IP addresses or CIDRs such as 10.0.0.0/8, got '{}'",
+                cidr
+            );
+        }
+
+        if self
+            .force_down_backends
+            .iter()
+            .chain(&self.force_up_backends)
+            .any(|pattern| pattern.trim().is_empty())
+        {
+            anyhow::bail!(
+                "force_down_backends and force_up_backends must not contain empty patterns"
+            );
+        }
+
+        // Validate channel tuning
+        let channel = &self.grpc_channel;
+        if channel.concurrency_limit == Some(0)
+            || channel.buffer_size == Some(0)
+            || channel.initial_stream_window_size == Some(0)
+            || channel.initial_connection_window_size == Some(0)
+        {
+            anyhow::bail!("grpc_channel settings must be greater than 0 when set");
+        }
+
+        // Validate logging config
+        if self.logging.destination.writes_file() {
+            match &self.logging.file_path {
+                None => anyhow::bail!(
+                    "logging.file_path is required when logging.destination is \"file\" or \"both\""
+                ),
+                Some(path) if path.is_empty() => anyhow::bail!(
+                    "logging.file_path must not be empty when logging.destination is \"file\" or \"both\""
+                ),
+                _ => {}
+            }
+        }
+
+        if let Some(max_files) = self.logging.file_max_files
+            && max_files == 0
+        {
+            anyhow::bail!("logging.file_max_files must be greater than 0");
+        }
+
+        Ok(())
+    }
+
+    // T030-T033: Load configuration with precedence: CLI > file > env > defaults
+    pub fn load() -> Result<Self> {
+        // Parse CLI arguments
+        Self::load_with_cli(CliArgs::parse())
+    }
+
+    fn load_with_cli(cli_args: CliArgs) -> Result<Self> {
+        // Start with defaults
+        let mut config = AgentConfig::default();
+        let mut sources = ConfigSources::default();
+        let mut settings = setting_values(&config);
+
+        // T030: Load from environment variables (if not using config file)
+        if cli_args.config.is_none() {
+            config = Self::load_from_env(config)?;
+            let loaded = setting_values(&config);
+            sources.record(&settings, &loaded, ConfigSource::Env);
+            settings = loaded;
+        }
+
+        // T032: Load from config file if specified
+        if let Some(config_path) = &cli_args.config {
+            config = Self::load_from_file(config_path)?;
+            let loaded = setting_values(&config);
+            sources.record(&settings, &loaded, ConfigSource::File);
+            settings = loaded;
+        }
+
+        // T031: Apply CLI overrides (highest precedence)
+        config = Self::apply_cli_overrides(config, cli_args);
+        sources.record(&settings, &setting_values(&config), ConfigSource::Cli);
+
+        // Resolved secrets keep the source of the reference that named them
+        config.resolve_secrets()?;
+        config.sources = sources;
+
+        // T034: Fail-fast validation
+        config
+            .validate()
+            .context("Configuration validation failed")?;
+
+        Ok(config)
+    }
+
+    /// Every setting as (dotted path, value, source), secrets redacted, for --log-config-sources
+    pub fn setting_sources(&self) -> Vec<(String, String, ConfigSource)> {
+        setting_values(self)
+            .into_iter()
+            .map(|(setting, value)| {
+                let value = if is_secret_setting(&setting) && !value.is_null() {
+                    "<redacted>".to_string()
+                } else {
+                    value.to_string()
+                };
+                let source = self.sources.get(&setting);
+                (setting, value, source)
+            })
+            .collect()
+    }
+
+    /// Replace `${ENV_VAR}` and `file:/path` references in secret-bearing fields with their values
+    fn resolve_secrets(&mut self) -> Result<()> {
+        for (field, value) in [
+            ("proxy.username", &mut self.proxy.username),
+            ("proxy.password", &mut self.proxy.password),
+        ] {
+            if let Some(value) = value {
+                *value = resolve_secret(value)
+                    .with_context(|| format!("Failed to resolve {}", field))?;
+            }
+        }
+
+        if !self.auth_token.is_empty() {
+            self.auth_token =
+                resolve_secret(&self.auth_token).context("Failed to resolve auth_token")?;
+        }
+
+        let credentials_dir = std::env::var_os("CREDENTIALS_DIRECTORY").map(PathBuf::from);
+        self.resolve_credential_paths(credentials_dir.as_deref())
+    }
+
+    /// Point settings naming TLS files and directories at them: a `${ENV_VAR}` reference is replaced by the
+    /// variable's value, a `file:` prefix is dropped, and a relative path is looked up in
+    /// `credentials_dir` (systemd's `$CREDENTIALS_DIRECTORY`) when there is one
+    fn resolve_credential_paths(
+        &mut self,
+        credentials_dir: Option<&std::path::Path>,
+    ) -> Result<()> {
+        for (field, value) in [
+            ("tls_ca_dir", &mut self.tls_ca_dir),
+            ("metrics_tls_cert", &mut self.metrics_tls_cert),
+            ("metrics_tls_key", &mut self.metrics_tls_key),
+        ] {
+            if let Some(value) = value {
+                let path = if value.starts_with("${") {
+                    resolve_secret(value).with_context(|| format!("Failed to resolve {}", field))?
+                } else {
+                    value.clone()
+                };
+                let path = path.strip_prefix("file:").unwrap_or(&path);
+                *value = credential_path(path, credentials_dir)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
+        Ok(())
+    }
+
+    // T030: Load configuration from environment variables
+    fn load_from_env(mut config: AgentConfig) -> Result<Self> {
+        if let Ok(port) = std::env::var("HAPROXY_AGENT_SERVER_PORT") {
+            config.server_port = port.parse().context("Invalid HAPROXY_AGENT_SERVER_PORT")?;
+        }
+
+        if let Ok(bind) = std::env::var("HAPROXY_AGENT_SERVER_BIND") {
+            config.server_bind_address = bind;
+        }
+
+        if let Ok(backlog) = std::env::var("HAPROXY_AGENT_LISTEN_BACKLOG") {
+            config.listen_backlog = backlog
+                .parse()
+                .context("Invalid HAPROXY_AGENT_LISTEN_BACKLOG")?;
+        }
+
+        if let Ok(retry) = std::env::var("HAPROXY_AGENT_BIND_RETRY") {
+            config.bind_retry_ms = retry.parse().context("Invalid HAPROXY_AGENT_BIND_RETRY")?;
+        }
+
+        if let Ok(fast_open) = std::env::var("HAPROXY_AGENT_TCP_FAST_OPEN") {
+            config.tcp_fast_open = match fast_open.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_TCP_FAST_OPEN value: {} (expected 'true' or 'false')",
+                    fast_open
+                ),
             };
         }
 
-        if let Ok(format) = std::env::var("HAPROXY_AGENT_LOG_FORMAT") {
-            config.log_format = match format.to_lowercase().as_str() {
-                "json" => LogFormat::Json,
-                "pretty" => LogFormat::Pretty,
-                _ => anyhow::bail!("Invalid log format: {}", format),
+        if let Ok(delimiter) = std::env::var("HAPROXY_AGENT_REQUEST_DELIMITER") {
+            config.request_delimiter = match delimiter.to_lowercase().as_str() {
+                "newline" => RequestDelimiter::Newline,
+                "null" => RequestDelimiter::Null,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_REQUEST_DELIMITER value: {} (expected 'newline' or 'null')",
+                    delimiter
+                ),
+            };
+        }
+
+        if let Ok(depth) = std::env::var("HAPROXY_AGENT_PIPELINE_DEPTH") {
+            config.pipeline_depth = depth
+                .parse()
+                .context("Invalid HAPROXY_AGENT_PIPELINE_DEPTH")?;
+        }
+
+        if let Ok(persistent) = std::env::var("HAPROXY_AGENT_PERSISTENT_CONNECTIONS") {
+            config.persistent_connections = match persistent.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_PERSISTENT_CONNECTIONS value: {} (expected 'true' or 'false')",
+                    persistent
+                ),
+            };
+        }
+
+        if let Ok(framing) = std::env::var("HAPROXY_AGENT_REQUEST_FRAMING") {
+            config.request_framing = match framing.to_lowercase().as_str() {
+                "line" => RequestFraming::Line,
+                "length-prefixed" => RequestFraming::LengthPrefixed,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_REQUEST_FRAMING value: {} (expected 'line' or 'length-prefixed')",
+                    framing
+                ),
+            };
+        }
+
+        if let Ok(response) = std::env::var("HAPROXY_AGENT_BLANK_LINE_RESPONSE") {
+            config.blank_line_response = match response.to_lowercase().as_str() {
+                "ignore" => BlankLineResponse::Ignore,
+                "up" => BlankLineResponse::Up,
+                "down" => BlankLineResponse::Down,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_BLANK_LINE_RESPONSE value: {} (expected 'ignore', 'up', or 'down')",
+                    response
+                ),
+            };
+        }
+
+        if let Ok(grace) = std::env::var("HAPROXY_AGENT_EOF_GRACE") {
+            config.eof_grace_ms = grace.parse().context("Invalid HAPROXY_AGENT_EOF_GRACE")?;
+        }
+
+        if let Ok(idle) = std::env::var("HAPROXY_AGENT_IDLE_SHUTDOWN") {
+            config.idle_shutdown_ms = Some(
+                idle.parse()
+                    .context("Invalid HAPROXY_AGENT_IDLE_SHUTDOWN")?,
+            );
+        }
+
+        if let Ok(cidrs) = std::env::var("HAPROXY_AGENT_CLIENT_ALLOWLIST") {
+            config.client_allowlist = split_list(&cidrs);
+        }
+
+        if let Ok(token) = std::env::var("HAPROXY_AGENT_AUTH_TOKEN") {
+            config.auth_token = token;
+        }
+
+        if let Ok(port) = std::env::var("HAPROXY_AGENT_METRICS_PORT") {
+            config.metrics_port = port.parse().context("Invalid HAPROXY_AGENT_METRICS_PORT")?;
+        }
+
+        if let Ok(bind) = std::env::var("HAPROXY_AGENT_METRICS_BIND") {
+            config.metrics_bind_address = bind;
+        }
+
+        if let Ok(cert) = std::env::var("HAPROXY_AGENT_METRICS_TLS_CERT") {
+            config.metrics_tls_cert = Some(cert);
+        }
+
+        if let Ok(key) = std::env::var("HAPROXY_AGENT_METRICS_TLS_KEY") {
+            config.metrics_tls_key = Some(key);
+        }
+
+        if let Ok(exemplars) = std::env::var("HAPROXY_AGENT_METRICS_EXEMPLARS") {
+            config.metrics_exemplars = match exemplars.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_METRICS_EXEMPLARS value: {} (expected 'true' or 'false')",
+                    exemplars
+                ),
+            };
+        }
+
+        if let Ok(enabled) = std::env::var("HAPROXY_AGENT_METRICS_ENDPOINT") {
+            config.metrics_endpoint_enabled = match enabled.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_METRICS_ENDPOINT value: {} (expected 'true' or 'false')",
+                    enabled
+                ),
             };
         }
 
-        if let Ok(cache) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_CACHE") {
-            config.grpc_channel_cache_enabled = match cache.to_lowercase().as_str() {
-                "true" => true,
-                "false" => false,
-                _ => anyhow::bail!(
-                    "Invalid HAPROXY_AGENT_GRPC_CHANNEL_CACHE value: {} (expected 'true' or 'false')",
-                    cache
-                ),
-            };
-        }
+        if let Ok(overrides) = std::env::var("HAPROXY_AGENT_ADMIN_OVERRIDES") {
+            config.admin_overrides_enabled = match overrides.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_ADMIN_OVERRIDES value: {} (expected 'true' or 'false')",
+                    overrides
+                ),
+            };
+        }
+
+        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_GRPC_CONNECT_TIMEOUT") {
+            config.grpc_connect_timeout_ms = timeout
+                .parse()
+                .context("Invalid HAPROXY_AGENT_GRPC_CONNECT_TIMEOUT")?;
+        }
+
+        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_GRPC_TLS_CONNECT_TIMEOUT") {
+            config.grpc_tls_connect_timeout_ms = Some(
+                timeout
+                    .parse()
+                    .context("Invalid HAPROXY_AGENT_GRPC_TLS_CONNECT_TIMEOUT")?,
+            );
+        }
+
+        if let Ok(protocols) = std::env::var("HAPROXY_AGENT_TLS_ALPN_PROTOCOLS") {
+            config.tls_alpn_protocols = split_list(&protocols);
+        }
+
+        if let Ok(dir) = std::env::var("HAPROXY_AGENT_TLS_CA_DIR") {
+            config.tls_ca_dir = Some(dir);
+        }
+
+        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_GRPC_RPC_TIMEOUT") {
+            config.grpc_rpc_timeout_ms = timeout
+                .parse()
+                .context("Invalid HAPROXY_AGENT_GRPC_RPC_TIMEOUT")?;
+        }
+
+        if let Ok(backoff) = std::env::var("HAPROXY_AGENT_CONNECT_BACKOFF") {
+            config.connect_backoff_ms = backoff
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CONNECT_BACKOFF")?;
+        }
+
+        if let Ok(backoff) = std::env::var("HAPROXY_AGENT_CONNECT_BACKOFF_MAX") {
+            config.connect_backoff_max_ms = backoff
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CONNECT_BACKOFF_MAX")?;
+        }
+
+        if let Ok(jitter) = std::env::var("HAPROXY_AGENT_CONNECT_BACKOFF_JITTER") {
+            config.connect_backoff_jitter = match jitter.to_lowercase().as_str() {
+                "none" => BackoffJitter::None,
+                "full" => BackoffJitter::Full,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_CONNECT_BACKOFF_JITTER value: {} (expected 'none' or 'full')",
+                    jitter
+                ),
+            };
+        }
+
+        if let Ok(deadline) = std::env::var("HAPROXY_AGENT_RESPONSE_DEADLINE") {
+            config.response_deadline_ms = deadline
+                .parse()
+                .context("Invalid HAPROXY_AGENT_RESPONSE_DEADLINE")?;
+        }
+
+        if let Ok(latency) = std::env::var("HAPROXY_AGENT_RESPONSE_MIN_LATENCY") {
+            config.response_min_latency_ms = latency
+                .parse()
+                .context("Invalid HAPROXY_AGENT_RESPONSE_MIN_LATENCY")?;
+        }
+
+        if let Ok(capped) = std::env::var("HAPROXY_AGENT_CONNECT_WITHIN_DEADLINE") {
+            config.connect_within_deadline = match capped.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_CONNECT_WITHIN_DEADLINE value: {} (expected 'true' or 'false')",
+                    capped
+                ),
+            };
+        }
+
+        if let Ok(level) = std::env::var("HAPROXY_AGENT_LOG_LEVEL") {
+            config.log_level = match level.to_lowercase().as_str() {
+                "trace" => LogLevel::Trace,
+                "debug" => LogLevel::Debug,
+                "info" => LogLevel::Info,
+                "warn" => LogLevel::Warn,
+                "error" => LogLevel::Error,
+                _ => anyhow::bail!("Invalid log level: {}", level),
+            };
+        }
+
+        if let Ok(format) = std::env::var("HAPROXY_AGENT_LOG_FORMAT") {
+            config.log_format = match format.to_lowercase().as_str() {
+                "json" => LogFormat::Json,
+                "pretty" => LogFormat::Pretty,
+                _ => anyhow::bail!("Invalid log format: {}", format),
+            };
+        }
+
+        if let Ok(cache) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_CACHE") {
+            config.grpc_channel_cache_enabled = match cache.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_GRPC_CHANNEL_CACHE value: {} (expected 'true' or 'false')",
+                    cache
+                ),
+            };
+        }
+
+        if let Ok(max) = std::env::var("HAPROXY_AGENT_MAX_CACHED_CHANNELS") {
+            config.max_cached_channels = max
+                .parse()
+                .context("Invalid HAPROXY_AGENT_MAX_CACHED_CHANNELS")?;
+        }
+
+        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_CHANNEL_READY_TIMEOUT") {
+            config.channel_ready_timeout_ms = timeout
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CHANNEL_READY_TIMEOUT")?;
+        }
+
+        if let Ok(interval) = std::env::var("HAPROXY_AGENT_CHANNEL_SWEEP_INTERVAL") {
+            config.channel_sweep_interval_ms = interval
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CHANNEL_SWEEP_INTERVAL")?;
+        }
+
+        if let Ok(mode) = std::env::var("HAPROXY_AGENT_CHANNEL_KEY_MODE") {
+            config.channel_key_mode = match mode.to_lowercase().as_str() {
+                "exact" => ChannelKeyMode::Exact,
+                "lowercase" => ChannelKeyMode::Lowercase,
+                "resolved" => ChannelKeyMode::Resolved,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_CHANNEL_KEY_MODE value: {} (expected 'exact', 'lowercase', or 'resolved')",
+                    mode
+                ),
+            };
+        }
+
+        if let Ok(path) = std::env::var("HAPROXY_AGENT_GRPC_HEALTH_CHECK_PATH") {
+            config.grpc_health_check_path = path;
+        }
+
+        if let Ok(name) = std::env::var("HAPROXY_AGENT_HOST_HEADER_NAME") {
+            config.host_header_name = name;
+        }
+
+        if let Ok(user_agent) = std::env::var("HAPROXY_AGENT_USER_AGENT") {
+            config.user_agent = user_agent;
+        }
+
+        if let Ok(key) = std::env::var("HAPROXY_AGENT_VERSION_METADATA_KEY") {
+            config.version_metadata_key = key;
+        }
+
+        if let Ok(version) = std::env::var("HAPROXY_AGENT_MIN_BACKEND_VERSION") {
+            config.min_backend_version = version;
+        }
+
+        if let Ok(key) = std::env::var("HAPROXY_AGENT_LOAD_METADATA_KEY") {
+            config.load_metadata_key = key;
+        }
+
+        if let Ok(capacity) = std::env::var("HAPROXY_AGENT_LOAD_WEIGHT_CAPACITY") {
+            config.load_weight_capacity = capacity
+                .parse()
+                .context("Invalid HAPROXY_AGENT_LOAD_WEIGHT_CAPACITY")?;
+        }
+
+        if let Ok(command) = std::env::var("HAPROXY_AGENT_STATS_COMMAND") {
+            config.stats_command = command;
+        }
+
+        if let Ok(service) = std::env::var("HAPROXY_AGENT_DEFAULT_GRPC_SERVICE") {
+            config.default_grpc_service = service;
+        }
+
+        if let Ok(protocol) = std::env::var("HAPROXY_AGENT_CHECK_PROTOCOL") {
+            config.check_protocol = match CheckProtocol::from_token(&protocol.to_lowercase()) {
+                Some(protocol) => protocol,
+                None => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_CHECK_PROTOCOL value: {} (expected 'grpc' or 'http')",
+                    protocol
+                ),
+            };
+        }
+
+        if let Ok(path) = std::env::var("HAPROXY_AGENT_HTTP_CHECK_PATH") {
+            config.http_check_path = path;
+        }
+
+        if let Ok(status) = std::env::var("HAPROXY_AGENT_SERVICE_UNKNOWN_STATUS") {
+            config.service_unknown_status = HealthStatus::from_token(&status).with_context(|| {
+                format!(
+                    "Invalid HAPROXY_AGENT_SERVICE_UNKNOWN_STATUS: {} (expected 'up', 'down', 'drain', or 'maint')",
+                    status
+                )
+            })?;
+        }
+
+        if let Ok(status) = std::env::var("HAPROXY_AGENT_UNKNOWN_STATUS") {
+            config.unknown_status = HealthStatus::from_token(&status).with_context(|| {
+                format!(
+                    "Invalid HAPROXY_AGENT_UNKNOWN_STATUS: {} (expected 'up', 'down', 'drain', or 'maint')",
+                    status
+                )
+            })?;
+        }
+
+        if let Ok(status) = std::env::var("HAPROXY_AGENT_RESOURCE_EXHAUSTED_STATUS") {
+            config.resource_exhausted_status =
+                Some(HealthStatus::from_token(&status).with_context(|| {
+                    format!(
+                        "Invalid HAPROXY_AGENT_RESOURCE_EXHAUSTED_STATUS: {} (expected 'up', 'down', 'drain', or 'maint')",
+                        status
+                    )
+                })?);
+        }
+
+        if let Ok(weight) = std::env::var("HAPROXY_AGENT_RESOURCE_EXHAUSTED_WEIGHT") {
+            config.resource_exhausted_weight = Some(
+                weight
+                    .parse()
+                    .context("Invalid HAPROXY_AGENT_RESOURCE_EXHAUSTED_WEIGHT")?,
+            );
+        }
+
+        if let Ok(retry) = std::env::var("HAPROXY_AGENT_UNKNOWN_RETRY") {
+            config.unknown_retry = match retry.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_UNKNOWN_RETRY value: {} (expected 'true' or 'false')",
+                    retry
+                ),
+            };
+        }
+
+        if let Ok(delay) = std::env::var("HAPROXY_AGENT_UNKNOWN_RETRY_DELAY") {
+            config.unknown_retry_delay_ms = delay
+                .parse()
+                .context("Invalid HAPROXY_AGENT_UNKNOWN_RETRY_DELAY")?;
+        }
+
+        if let Ok(verbose) = std::env::var("HAPROXY_AGENT_VERBOSE_RESPONSE") {
+            config.verbose_response = match verbose.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_VERBOSE_RESPONSE value: {} (expected 'true' or 'false')",
+                    verbose
+                ),
+            };
+        }
+
+        if let Ok(comment) = std::env::var("HAPROXY_AGENT_SERVING_STATUS_COMMENT") {
+            config.serving_status_comment = match comment.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_SERVING_STATUS_COMMENT value: {} (expected 'true' or 'false')",
+                    comment
+                ),
+            };
+        }
+
+        if let Ok(report_only) = std::env::var("HAPROXY_AGENT_REPORT_ONLY") {
+            config.report_only = match report_only.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_REPORT_ONLY value: {} (expected 'true' or 'false')",
+                    report_only
+                ),
+            };
+        }
+
+        if let Ok(ttl) = std::env::var("HAPROXY_AGENT_RESULT_CACHE_TTL") {
+            config.result_cache_ttl_ms = ttl
+                .parse()
+                .context("Invalid HAPROXY_AGENT_RESULT_CACHE_TTL")?;
+        }
+
+        if let Ok(slow_start) = std::env::var("HAPROXY_AGENT_SLOW_START") {
+            config.slow_start_ms = slow_start
+                .parse()
+                .context("Invalid HAPROXY_AGENT_SLOW_START")?;
+        }
+
+        if let Ok(window) = std::env::var("HAPROXY_AGENT_SUCCESS_WEIGHT_WINDOW") {
+            config.success_weight_window = window
+                .parse()
+                .context("Invalid HAPROXY_AGENT_SUCCESS_WEIGHT_WINDOW")?;
+        }
+
+        if let Ok(min) = std::env::var("HAPROXY_AGENT_SUCCESS_WEIGHT_MIN_PERCENT") {
+            config.success_weight_min_percent = min
+                .parse()
+                .context("Invalid HAPROXY_AGENT_SUCCESS_WEIGHT_MIN_PERCENT")?;
+        }
+
+        if let Ok(response) = std::env::var("HAPROXY_AGENT_FIRST_FAILURE_RESPONSE") {
+            config.first_failure_response = match response.to_lowercase().as_str() {
+                "down" => FirstFailureResponse::Down,
+                "up" => FirstFailureResponse::Up,
+                "noop" => FirstFailureResponse::Noop,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_FIRST_FAILURE_RESPONSE value: {} (expected 'down', 'up', or 'noop')",
+                    response
+                ),
+            };
+        }
+
+        if let Ok(patterns) = std::env::var("HAPROXY_AGENT_FORCE_DOWN_BACKENDS") {
+            config.force_down_backends = split_list(&patterns);
+        }
+
+        if let Ok(patterns) = std::env::var("HAPROXY_AGENT_FORCE_UP_BACKENDS") {
+            config.force_up_backends = split_list(&patterns);
+        }
+
+        if let Ok(interval) = std::env::var("HAPROXY_AGENT_ERROR_LOG_INTERVAL") {
+            config.error_log_interval_ms = interval
+                .parse()
+                .context("Invalid HAPROXY_AGENT_ERROR_LOG_INTERVAL")?;
+        }
+
+        if let Ok(max) = std::env::var("HAPROXY_AGENT_MAX_CONCURRENT_CHECKS") {
+            config.max_concurrent_checks = max
+                .parse()
+                .context("Invalid HAPROXY_AGENT_MAX_CONCURRENT_CHECKS")?;
+        }
+
+        if let Ok(response) = std::env::var("HAPROXY_AGENT_OVER_CAPACITY_RESPONSE") {
+            config.over_capacity_response = match response.to_lowercase().as_str() {
+                "down" => OverCapacityResponse::Down,
+                "drain" => OverCapacityResponse::Drain,
+                "noop" => OverCapacityResponse::Noop,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_OVER_CAPACITY_RESPONSE value: {} (expected 'down', 'drain', or 'noop')",
+                    response
+                ),
+            };
+        }
+
+        if let Ok(max) = std::env::var("HAPROXY_AGENT_MAX_CONNECTIONS") {
+            config.max_connections = max
+                .parse()
+                .context("Invalid HAPROXY_AGENT_MAX_CONNECTIONS")?;
+        }
+
+        if let Ok(response) = std::env::var("HAPROXY_AGENT_OVERLOAD_RESPONSE") {
+            config.overload_response = match response.to_lowercase().as_str() {
+                "close" => OverloadResponse::Close,
+                "up" => OverloadResponse::Up,
+                "down" => OverloadResponse::Down,
+                "drain" => OverloadResponse::Drain,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_OVERLOAD_RESPONSE value: {} (expected 'close', 'up', 'down', or 'drain')",
+                    response
+                ),
+            };
+        }
+
+        if let Ok(dest) = std::env::var("HAPROXY_AGENT_LOG_DESTINATION") {
+            config.logging.destination = match dest.to_lowercase().as_str() {
+                "console" => LogDestination::Console,
+                "file" => LogDestination::File,
+                "both" => LogDestination::Both,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_LOG_DESTINATION: {} (expected 'console', 'file', or 'both')",
+                    dest
+                ),
+            };
+        }
+
+        if let Ok(format) = std::env::var("HAPROXY_AGENT_LOG_CONSOLE_FORMAT") {
+            config.logging.console_format = Some(match format.to_lowercase().as_str() {
+                "json" => LogFormat::Json,
+                "pretty" => LogFormat::Pretty,
+                _ => anyhow::bail!("Invalid HAPROXY_AGENT_LOG_CONSOLE_FORMAT: {}", format),
+            });
+        }
+
+        if let Ok(format) = std::env::var("HAPROXY_AGENT_LOG_FILE_FORMAT") {
+            config.logging.file_format = Some(match format.to_lowercase().as_str() {
+                "json" => LogFormat::Json,
+                "pretty" => LogFormat::Pretty,
+                _ => anyhow::bail!("Invalid HAPROXY_AGENT_LOG_FILE_FORMAT: {}", format),
+            });
+        }
+
+        if let Ok(path) = std::env::var("HAPROXY_AGENT_LOG_FILE_PATH") {
+            config.logging.file_path = Some(path);
+        }
+
+        if let Ok(rotation) = std::env::var("HAPROXY_AGENT_LOG_FILE_ROTATION") {
+            config.logging.file_rotation = Some(match rotation.to_lowercase().as_str() {
+                "never" => LogRotation::Never,
+                "daily" => LogRotation::Daily,
+                "hourly" => LogRotation::Hourly,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_LOG_FILE_ROTATION: {} (expected 'never', 'daily', or 'hourly')",
+                    rotation
+                ),
+            });
+        }
+
+        if let Ok(max_files) = std::env::var("HAPROXY_AGENT_LOG_FILE_MAX_FILES") {
+            config.logging.file_max_files = Some(
+                max_files
+                    .parse()
+                    .context("Invalid HAPROXY_AGENT_LOG_FILE_MAX_FILES")?,
+            );
+        }
+
+        if let Ok(nameservers) = std::env::var("HAPROXY_AGENT_DNS_NAMESERVERS") {
+            config.dns.nameservers = split_list(&nameservers);
+        }
+
+        if let Ok(limit) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_CONCURRENCY_LIMIT") {
+            config.grpc_channel.concurrency_limit = Some(
+                limit
+                    .parse()
+                    .context("Invalid HAPROXY_AGENT_GRPC_CHANNEL_CONCURRENCY_LIMIT")?,
+            );
+        }
+
+        if let Ok(size) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_BUFFER_SIZE") {
+            config.grpc_channel.buffer_size = Some(
+                size.parse()
+                    .context("Invalid HAPROXY_AGENT_GRPC_CHANNEL_BUFFER_SIZE")?,
+            );
+        }
+
+        if let Ok(size) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_STREAM_WINDOW") {
+            config.grpc_channel.initial_stream_window_size = Some(
+                size.parse()
+                    .context("Invalid HAPROXY_AGENT_GRPC_CHANNEL_STREAM_WINDOW")?,
+            );
+        }
+
+        if let Ok(size) = std::env::var("HAPROXY_AGENT_GRPC_CHANNEL_CONNECTION_WINDOW") {
+            config.grpc_channel.initial_connection_window_size = Some(
+                size.parse()
+                    .context("Invalid HAPROXY_AGENT_GRPC_CHANNEL_CONNECTION_WINDOW")?,
+            );
+        }
+
+        if let Ok(timeout) = std::env::var("HAPROXY_AGENT_DNS_TIMEOUT") {
+            config.dns.timeout_ms = timeout
+                .parse()
+                .context("Invalid HAPROXY_AGENT_DNS_TIMEOUT")?;
+        }
+
+        if let Ok(on_startup) = std::env::var("HAPROXY_AGENT_SELF_TEST_ON_STARTUP") {
+            config.self_test.on_startup = match on_startup.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_SELF_TEST_ON_STARTUP value: {} (expected 'true' or 'false')",
+                    on_startup
+                ),
+            };
+        }
+
+        if let Ok(canary) = std::env::var("HAPROXY_AGENT_SELF_TEST_CANARY") {
+            config.self_test.canary = Some(canary);
+        }
+
+        if let Ok(strict) = std::env::var("HAPROXY_AGENT_STRICT_VALIDATION") {
+            config.strict_validation = match strict.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_STRICT_VALIDATION value: {} (expected 'true' or 'false')",
+                    strict
+                ),
+            };
+        }
+
+        if let Ok(log_sources) = std::env::var("HAPROXY_AGENT_LOG_CONFIG_SOURCES") {
+            config.log_config_sources = match log_sources.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_LOG_CONFIG_SOURCES value: {} (expected 'true' or 'false')",
+                    log_sources
+                ),
+            };
+        }
+
+        if let Ok(probability) = std::env::var("HAPROXY_AGENT_CHAOS_LATENCY_PROBABILITY") {
+            config.chaos.latency_probability = probability
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CHAOS_LATENCY_PROBABILITY")?;
+        }
+
+        if let Ok(latency) = std::env::var("HAPROXY_AGENT_CHAOS_LATENCY") {
+            config.chaos.latency_ms = latency
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CHAOS_LATENCY")?;
+        }
+
+        if let Ok(probability) = std::env::var("HAPROXY_AGENT_CHAOS_DOWN_PROBABILITY") {
+            config.chaos.down_probability = probability
+                .parse()
+                .context("Invalid HAPROXY_AGENT_CHAOS_DOWN_PROBABILITY")?;
+        }
+
+        if let Ok(host) = std::env::var("HAPROXY_AGENT_PROXY_HOST") {
+            config.proxy.host = Some(host);
+        }
+
+        if let Ok(port) = std::env::var("HAPROXY_AGENT_PROXY_PORT") {
+            config.proxy.port = port.parse().context("Invalid HAPROXY_AGENT_PROXY_PORT")?;
+        }
+
+        if let Ok(host) = std::env::var("HAPROXY_AGENT_STATSD_HOST") {
+            config.statsd.host = Some(host);
+        }
+
+        if let Ok(port) = std::env::var("HAPROXY_AGENT_STATSD_PORT") {
+            config.statsd.port = port.parse().context("Invalid HAPROXY_AGENT_STATSD_PORT")?;
+        }
+
+        if let Ok(prefix) = std::env::var("HAPROXY_AGENT_STATSD_PREFIX") {
+            config.statsd.prefix = prefix;
+        }
+
+        if let Ok(format) = std::env::var("HAPROXY_AGENT_STATSD_FORMAT") {
+            config.statsd.format = match format.to_lowercase().as_str() {
+                "statsd" => StatsdFormat::Statsd,
+                "dogstatsd" => StatsdFormat::Dogstatsd,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_STATSD_FORMAT value: {} (expected 'statsd' or 'dogstatsd')",
+                    format
+                ),
+            };
+        }
+
+        if let Ok(interval) = std::env::var("HAPROXY_AGENT_STATSD_FLUSH_INTERVAL") {
+            config.statsd.flush_interval_ms = interval
+                .parse()
+                .context("Invalid HAPROXY_AGENT_STATSD_FLUSH_INTERVAL")?;
+        }
+
+        if let Ok(username) = std::env::var("HAPROXY_AGENT_PROXY_USERNAME") {
+            config.proxy.username = Some(username);
+        }
+
+        if let Ok(password) = std::env::var("HAPROXY_AGENT_PROXY_PASSWORD") {
+            config.proxy.password = Some(password);
+        }
+
+        if let Ok(enabled) = std::env::var("HAPROXY_AGENT_AUDIT_LOG") {
+            config.audit.enabled = match enabled.to_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => anyhow::bail!(
+                    "Invalid HAPROXY_AGENT_AUDIT_LOG value: {} (expected 'true' or 'false')",
+                    enabled
+                ),
+            };
+        }
+
+        if let Ok(level) = std::env::var("HAPROXY_AGENT_AUDIT_LOG_LEVEL") {
+            config.audit.level = match level.to_lowercase().as_str() {
+                "trace" => LogLevel::Trace,
+                "debug" => LogLevel::Debug,
+                "info" => LogLevel::Info,
+                "warn" => LogLevel::Warn,
+                "error" => LogLevel::Error,
+                _ => anyhow::bail!("Invalid audit log level: {}", level),
+            };
+        }
+
+        if let Ok(path) = std::env::var("HAPROXY_AGENT_AUDIT_LOG_FILE") {
+            config.audit.file_path = Some(path);
+        }
+
+        Ok(config)
+    }
+
+    // T032: Load configuration from TOML file
+    fn load_from_file(path: &PathBuf) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+
+        let config: AgentConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+
+        Ok(config)
+    }
+
+    // T031: Apply CLI argument overrides
+    fn apply_cli_overrides(mut config: AgentConfig, cli: CliArgs) -> Self {
+        if let Some(port) = cli.server_port {
+            config.server_port = port;
+        }
+
+        if let Some(bind) = cli.server_bind {
+            config.server_bind_address = bind;
+        }
+
+        if let Some(backlog) = cli.listen_backlog {
+            config.listen_backlog = backlog;
+        }
+
+        if let Some(retry) = cli.bind_retry {
+            config.bind_retry_ms = retry;
+        }
+
+        if let Some(fast_open) = cli.tcp_fast_open {
+            config.tcp_fast_open = fast_open;
+        }
+
+        if let Some(delimiter) = cli.request_delimiter {
+            config.request_delimiter = delimiter;
+        }
+
+        if let Some(framing) = cli.request_framing {
+            config.request_framing = framing;
+        }
+
+        if let Some(depth) = cli.pipeline_depth {
+            config.pipeline_depth = depth;
+        }
+
+        if let Some(persistent) = cli.persistent_connections {
+            config.persistent_connections = persistent;
+        }
+
+        if let Some(response) = cli.blank_line_response {
+            config.blank_line_response = response;
+        }
+
+        if let Some(grace) = cli.eof_grace {
+            config.eof_grace_ms = grace;
+        }
+
+        if let Some(idle) = cli.idle_shutdown {
+            config.idle_shutdown_ms = Some(idle);
+        }
+
+        if let Some(cidrs) = cli.client_allowlist {
+            config.client_allowlist = cidrs;
+        }
+
+        if let Some(token) = cli.auth_token {
+            config.auth_token = token;
+        }
+
+        if let Some(port) = cli.metrics_port {
+            config.metrics_port = port;
+        }
+
+        if let Some(bind) = cli.metrics_bind {
+            config.metrics_bind_address = bind;
+        }
+
+        if let Some(cert) = cli.metrics_tls_cert {
+            config.metrics_tls_cert = Some(cert);
+        }
+
+        if let Some(key) = cli.metrics_tls_key {
+            config.metrics_tls_key = Some(key);
+        }
+
+        if let Some(exemplars) = cli.metrics_exemplars {
+            config.metrics_exemplars = exemplars;
+        }
+
+        if let Some(enabled) = cli.metrics_endpoint {
+            config.metrics_endpoint_enabled = enabled;
+        }
+
+        if let Some(overrides) = cli.admin_overrides {
+            config.admin_overrides_enabled = overrides;
+        }
+
+        if let Some(timeout) = cli.grpc_connect_timeout {
+            config.grpc_connect_timeout_ms = timeout;
+        }
+
+        if let Some(timeout) = cli.grpc_tls_connect_timeout {
+            config.grpc_tls_connect_timeout_ms = Some(timeout);
+        }
+
+        if let Some(protocols) = cli.tls_alpn_protocols {
+            config.tls_alpn_protocols = protocols;
+        }
+
+        if let Some(dir) = cli.tls_ca_dir {
+            config.tls_ca_dir = Some(dir);
+        }
+
+        if let Some(timeout) = cli.grpc_rpc_timeout {
+            config.grpc_rpc_timeout_ms = timeout;
+        }
+
+        if let Some(backoff) = cli.connect_backoff {
+            config.connect_backoff_ms = backoff;
+        }
+
+        if let Some(backoff) = cli.connect_backoff_max {
+            config.connect_backoff_max_ms = backoff;
+        }
+
+        if let Some(jitter) = cli.connect_backoff_jitter {
+            config.connect_backoff_jitter = jitter;
+        }
+
+        if let Some(deadline) = cli.response_deadline {
+            config.response_deadline_ms = deadline;
+        }
+
+        if let Some(capped) = cli.connect_within_deadline {
+            config.connect_within_deadline = capped;
+        }
+
+        if let Some(latency) = cli.response_min_latency {
+            config.response_min_latency_ms = latency;
+        }
+
+        if let Some(level) = cli.log_level {
+            config.log_level = level;
+        }
+
+        if let Some(format) = cli.log_format {
+            config.log_format = format;
+        }
+
+        if let Some(cache) = cli.grpc_channel_cache {
+            config.grpc_channel_cache_enabled = cache;
+        }
+
+        if let Some(max) = cli.max_cached_channels {
+            config.max_cached_channels = max;
+        }
+
+        if let Some(timeout) = cli.channel_ready_timeout {
+            config.channel_ready_timeout_ms = timeout;
+        }
+
+        if let Some(interval) = cli.channel_sweep_interval {
+            config.channel_sweep_interval_ms = interval;
+        }
+
+        if let Some(mode) = cli.channel_key_mode {
+            config.channel_key_mode = mode;
+        }
+
+        if let Some(path) = cli.grpc_health_check_path {
+            config.grpc_health_check_path = path;
+        }
+
+        if let Some(name) = cli.host_header_name {
+            config.host_header_name = name;
+        }
+
+        if let Some(user_agent) = cli.user_agent {
+            config.user_agent = user_agent;
+        }
+
+        if let Some(key) = cli.version_metadata_key {
+            config.version_metadata_key = key;
+        }
+
+        if let Some(version) = cli.min_backend_version {
+            config.min_backend_version = version;
+        }
+
+        if let Some(key) = cli.load_metadata_key {
+            config.load_metadata_key = key;
+        }
+
+        if let Some(capacity) = cli.load_weight_capacity {
+            config.load_weight_capacity = capacity;
+        }
+
+        if let Some(service) = cli.default_grpc_service {
+            config.default_grpc_service = service;
+        }
+
+        if let Some(protocol) = cli.check_protocol {
+            config.check_protocol = protocol;
+        }
+
+        if let Some(path) = cli.http_check_path {
+            config.http_check_path = path;
+        }
+
+        if let Some(verbose) = cli.verbose_response {
+            config.verbose_response = verbose;
+        }
+
+        if let Some(comment) = cli.serving_status_comment {
+            config.serving_status_comment = comment;
+        }
+
+        if let Some(report_only) = cli.report_only {
+            config.report_only = report_only;
+        }
+
+        if let Some(command) = cli.stats_command {
+            config.stats_command = command;
+        }
+
+        if let Some(ttl) = cli.result_cache_ttl {
+            config.result_cache_ttl_ms = ttl;
+        }
+
+        if let Some(slow_start) = cli.slow_start {
+            config.slow_start_ms = slow_start;
+        }
+
+        if let Some(window) = cli.success_weight_window {
+            config.success_weight_window = window;
+        }
+
+        if let Some(min) = cli.success_weight_min_percent {
+            config.success_weight_min_percent = min;
+        }
+
+        if let Some(response) = cli.first_failure_response {
+            config.first_failure_response = response;
+        }
+
+        if let Some(patterns) = cli.force_down_backends {
+            config.force_down_backends = patterns;
+        }
+
+        if let Some(patterns) = cli.force_up_backends {
+            config.force_up_backends = patterns;
+        }
+
+        if let Some(interval) = cli.error_log_interval {
+            config.error_log_interval_ms = interval;
+        }
+
+        if let Some(max) = cli.max_concurrent_checks {
+            config.max_concurrent_checks = max;
+        }
+
+        if let Some(response) = cli.over_capacity_response {
+            config.over_capacity_response = response;
+        }
+
+        if let Some(max) = cli.max_connections {
+            config.max_connections = max;
+        }
+
+        if let Some(response) = cli.overload_response {
+            config.overload_response = response;
+        }
+
+        if let Some(dest) = cli.log_destination {
+            config.logging.destination = dest;
+        }
+
+        if let Some(format) = cli.log_console_format {
+            config.logging.console_format = Some(format);
+        }
+
+        if let Some(format) = cli.log_file_format {
+            config.logging.file_format = Some(format);
+        }
+
+        if let Some(path) = cli.log_file_path {
+            config.logging.file_path = Some(path);
+        }
+
+        if let Some(rotation) = cli.log_file_rotation {
+            config.logging.file_rotation = Some(rotation);
+        }
+
+        if let Some(max_files) = cli.log_file_max_files {
+            config.logging.file_max_files = Some(max_files);
+        }
+
+        config.self_test.exit_after = cli.self_test;
+
+        if let Some(on_startup) = cli.self_test_on_startup {
+            config.self_test.on_startup = on_startup;
+        }
+
+        if cli.strict {
+            config.strict_validation = true;
+        }
+
+        if let Some(log_sources) = cli.log_config_sources {
+            config.log_config_sources = log_sources;
+        }
+
+        if let Some(canary) = cli.self_test_canary {
+            config.self_test.canary = Some(canary);
+        }
+
+        if let Some(nameservers) = cli.dns_nameservers {
+            config.dns.nameservers = nameservers;
+        }
+
+        if let Some(timeout) = cli.dns_timeout {
+            config.dns.timeout_ms = timeout;
+        }
+
+        if let Some(host) = cli.proxy_host {
+            config.proxy.host = Some(host);
+        }
+
+        if let Some(port) = cli.proxy_port {
+            config.proxy.port = port;
+        }
+
+        if let Some(host) = cli.statsd_host {
+            config.statsd.host = Some(host);
+        }
+
+        if let Some(port) = cli.statsd_port {
+            config.statsd.port = port;
+        }
+
+        if let Some(format) = cli.statsd_format {
+            config.statsd.format = format;
+        }
+
+        if let Some(enabled) = cli.audit_log {
+            config.audit.enabled = enabled;
+        }
+
+        if let Some(level) = cli.audit_log_level {
+            config.audit.level = level;
+        }
+
+        if let Some(path) = cli.audit_log_file {
+            config.audit.file_path = Some(path);
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // T046: Unit test for config validation with valid config
+    #[test]
+    fn test_config_validation_valid() {
+        let config = AgentConfig {
+            server_port: 5555,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 1500,
+            metrics_port: 9090,
+            metrics_bind_address: "0.0.0.0".to_string(),
+            grpc_channel_cache_enabled: true,
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_valid_custom_ports() {
+        let config = AgentConfig {
+            server_port: 8080,
+            server_bind_address: "127.0.0.1".to_string(),
+            grpc_connect_timeout_ms: 500,
+            grpc_rpc_timeout_ms: 1000,
+            grpc_channel_cache_enabled: true,
+            metrics_port: 9091,
+            metrics_bind_address: "127.0.0.1".to_string(),
+            log_level: LogLevel::Debug,
+            log_format: LogFormat::Pretty,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_ok());
+    }
+
+    // T047: Unit test for config validation with port conflict
+    #[test]
+    fn test_config_validation_port_conflict() {
+        let config = AgentConfig {
+            server_port: 9090,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 1500,
+            grpc_channel_cache_enabled: true,
+            metrics_port: 9090, // Same as server_port!
+            metrics_bind_address: "0.0.0.0".to_string(),
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("cannot be the same"));
+    }
+
+    #[test]
+    fn test_config_validation_invalid_server_port_zero() {
+        let config = AgentConfig {
+            server_port: 0,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 1500,
+            grpc_channel_cache_enabled: true,
+            metrics_port: 9090,
+            metrics_bind_address: "0.0.0.0".to_string(),
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("server_port"));
+    }
+
+    #[test]
+    fn test_config_validation_unix_socket_ignores_server_port() {
+        let config = AgentConfig {
+            server_bind_address: "@haproxy-agent".to_string(),
+            server_port: 0,
+            ..AgentConfig::default()
+        };
+
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_invalid_metrics_port_zero() {
+        let config = AgentConfig {
+            server_port: 5555,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 1500,
+            grpc_channel_cache_enabled: true,
+            metrics_port: 0,
+            metrics_bind_address: "0.0.0.0".to_string(),
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("metrics_port"));
+    }
+
+    #[test]
+    fn test_config_validation_invalid_listen_backlog_zero() {
+        let config = AgentConfig {
+            listen_backlog: 0,
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("listen_backlog"));
+    }
+
+    // T048: Unit test for config validation with invalid timeout
+    #[test]
+    fn test_config_validation_invalid_connect_timeout_zero() {
+        let config = AgentConfig {
+            server_port: 5555,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 0, // Invalid!
+            grpc_rpc_timeout_ms: 1500,
+            grpc_channel_cache_enabled: true,
+            metrics_port: 9090,
+            metrics_bind_address: "0.0.0.0".to_string(),
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("grpc_connect_timeout_ms"));
+    }
+
+    #[test]
+    fn test_config_validation_invalid_rpc_timeout_zero() {
+        let config = AgentConfig {
+            server_port: 5555,
+            server_bind_address: "0.0.0.0".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 0, // Invalid!
+            grpc_channel_cache_enabled: true,
+            metrics_port: 9090,
+            metrics_bind_address: "0.0.0.0".to_string(),
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Json,
+            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
+        };
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("grpc_rpc_timeout_ms"));
+    }
+
+    #[test]
+    fn test_config_default_values() {
+        let config = AgentConfig::default();
+
+        assert_eq!(config.server_port, 5555);
+        assert_eq!(config.server_bind_address, "0.0.0.0");
+        assert_eq!(config.grpc_connect_timeout_ms, 1000);
+        assert_eq!(config.grpc_rpc_timeout_ms, 1500);
+        assert_eq!(config.metrics_port, 9090);
+        assert_eq!(config.metrics_bind_address, "0.0.0.0");
+        assert_eq!(config.listen_backlog, 1024);
+        assert_eq!(config.default_grpc_service, "");
+        assert_eq!(config.service_unknown_status, HealthStatus::Down);
+        assert_eq!(config.unknown_status, HealthStatus::Down);
+        assert!(!config.unknown_retry);
+        assert_eq!(config.unknown_retry_delay_ms, 100);
+    }
+
+    #[test]
+    fn test_config_default_grpc_channel_cache_enabled() {
+        let config = AgentConfig::default();
+        assert!(
+            config.grpc_channel_cache_enabled,
+            "grpc_channel_cache_enabled should default to true"
+        );
+        assert_eq!(config.channel_ready_timeout_ms, 10);
+    }
+
+    // T008: LoggingConfig default and validation tests
+    #[test]
+    fn test_logging_config_defaults() {
+        let logging = LoggingConfig::default();
+        assert!(matches!(logging.destination, LogDestination::Console));
+        assert!(logging.level.is_none());
+        assert!(logging.format.is_none());
+        assert!(logging.file_path.is_none());
+        assert!(logging.file_rotation.is_none());
+        assert!(logging.file_max_files.is_none());
+        assert!(logging.packages.is_empty());
+    }
+
+    #[test]
+    fn test_logging_config_in_agent_config_default() {
+        let config = AgentConfig::default();
+        assert!(matches!(
+            config.logging.destination,
+            LogDestination::Console
+        ));
+        assert!(config.logging.packages.is_empty());
+    }
+
+    #[test]
+    fn test_logging_validation_file_without_path() {
+        let mut config = AgentConfig::default();
+        config.logging.destination = LogDestination::File;
+        config.logging.file_path = None;
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("file_path"));
+    }
+
+    #[test]
+    fn test_logging_validation_file_with_empty_path() {
+        let mut config = AgentConfig::default();
+        config.logging.destination = LogDestination::File;
+        config.logging.file_path = Some("".to_string());
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("file_path"));
+    }
+
+    #[test]
+    fn test_logging_validation_file_with_valid_path() {
+        let mut config = AgentConfig::default();
+        config.logging.destination = LogDestination::File;
+        config.logging.file_path = Some("/tmp/test.log".to_string());
+
+        let result = config.validate();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_logging_validation_both_requires_path() {
+        let mut config = AgentConfig::default();
+        config.logging.destination = LogDestination::Both;
+
+        let error_msg = config.validate().unwrap_err().to_string();
+        assert!(error_msg.contains("file_path"));
+
+        config.logging.file_path = Some("/tmp/test.log".to_string());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_logging_validation_max_size_zero() {
+        let mut config = AgentConfig::default();
+        config.logging.file_max_files = Some(0);
+
+        let result = config.validate();
+        assert!(result.is_err());
+        let error_msg = result.unwrap_err().to_string();
+        assert!(error_msg.contains("file_max_files"));
+    }
+
+    #[test]
+    fn test_logging_validation_console_ignores_file_settings() {
+        let mut config = AgentConfig::default();
+        config.logging.destination = LogDestination::Console;
+        // These should be ignored for console destination
+        config.logging.file_rotation = Some(LogRotation::Daily);
+        config.logging.file_max_files = Some(5);
+
+        let result = config.validate();
+        assert!(result.is_ok());
+    }
+
+    // T012: Tests for resolved_level
+    #[test]
+    fn test_resolved_level_logging_level_set() {
+        let logging = LoggingConfig {
+            level: Some(LogLevel::Debug),
+            ..Default::default()
+        };
+        let top_level = LogLevel::Info;
+
+        assert!(matches!(
+            logging.resolved_level(&top_level),
+            LogLevel::Debug
+        ));
+    }
+
+    #[test]
+    fn test_resolved_level_logging_level_unset_falls_back() {
+        let logging = LoggingConfig::default();
+        let top_level = LogLevel::Warn;
+
+        assert!(matches!(logging.resolved_level(&top_level), LogLevel::Warn));
+    }
+
+    #[test]
+    fn test_resolved_level_both_set_logging_wins() {
+        let logging = LoggingConfig {
+            level: Some(LogLevel::Error),
+            ..Default::default()
+        };
+        let top_level = LogLevel::Trace;
+
+        assert!(matches!(
+            logging.resolved_level(&top_level),
+            LogLevel::Error
+        ));
+    }
+
+    #[test]
+    fn test_resolved_destination_formats_fall_back() {
+        let logging = LoggingConfig {
+            format: Some(LogFormat::Json),
+            console_format: Some(LogFormat::Pretty),
+            ..Default::default()
+        };
+        let top_level = LogFormat::Pretty;
+
+        assert!(matches!(
+            logging.resolved_console_format(&top_level),
+            LogFormat::Pretty
+        ));
+        assert!(matches!(
+            logging.resolved_file_format(&top_level),
+            LogFormat::Json
+        ));
+    }
+
+    // T013: Tests for build_env_filter_directive
+    #[test]
+    fn test_env_filter_directive_no_overrides() {
+        let logging = LoggingConfig::default();
+        let directive = logging.build_env_filter_directive(&LogLevel::Info);
+        assert_eq!(directive, "info");
+    }
+
+    #[test]
+    fn test_env_filter_directive_single_override() {
+        let mut logging = LoggingConfig::default();
+        logging
+            .packages
+            .insert("haproxy_grpc_agent::checker".to_string(), LogLevel::Debug);
+        let directive = logging.build_env_filter_directive(&LogLevel::Info);
+        assert!(directive.starts_with("info,"));
+        assert!(directive.contains("haproxy_grpc_agent::checker=debug"));
+    }
+
+    #[test]
+    fn test_env_filter_directive_multiple_overrides() {
+        let mut logging = LoggingConfig::default();
+        logging
+            .packages
+            .insert("haproxy_grpc_agent::checker".to_string(), LogLevel::Debug);
+        logging.packages.insert("tonic".to_string(), LogLevel::Warn);
+        let directive = logging.build_env_filter_directive(&LogLevel::Info);
+        assert!(directive.starts_with("info,"));
+        assert!(directive.contains("haproxy_grpc_agent::checker=debug"));
+        assert!(directive.contains("tonic=warn"));
+    }
+
+    #[test]
+    fn test_env_filter_directive_with_logging_level() {
+        let logging = LoggingConfig {
+            level: Some(LogLevel::Warn),
+            ..Default::default()
+        };
+        let directive = logging.build_env_filter_directive(&LogLevel::Info);
+        assert_eq!(directive, "warn");
+    }
 
-        if let Ok(dest) = std::env::var("HAPROXY_AGENT_LOG_DESTINATION") {
-            config.logging.destination = match dest.to_lowercase().as_str() {
-                "console" => LogDestination::Console,
-                "file" => LogDestination::File,
-                _ => anyhow::bail!(
-                    "Invalid HAPROXY_AGENT_LOG_DESTINATION: {} (expected 'console' or 'file')",
-                    dest
-                ),
-            };
-        }
+    #[test]
+    fn test_service_unknown_status_from_toml() {
+        let config: AgentConfig = toml::from_str("service_unknown_status = \"drain\"").unwrap();
+        assert_eq!(config.service_unknown_status, HealthStatus::Drain);
 
-        if let Ok(path) = std::env::var("HAPROXY_AGENT_LOG_FILE_PATH") {
-            config.logging.file_path = Some(path);
-        }
+        let result: Result<AgentConfig, _> = toml::from_str("service_unknown_status = \"bogus\"");
+        assert!(result.is_err());
+    }
 
-        if let Ok(rotation) = std::env::var("HAPROXY_AGENT_LOG_FILE_ROTATION") {
-            config.logging.file_rotation = Some(match rotation.to_lowercase().as_str() {
-                "never" => LogRotation::Never,
-                "daily" => LogRotation::Daily,
-                "hourly" => LogRotation::Hourly,
-                _ => anyhow::bail!(
-                    "Invalid HAPROXY_AGENT_LOG_FILE_ROTATION: {} (expected 'never', 'daily', or 'hourly')",
-                    rotation
-                ),
-            });
-        }
+    #[test]
+    fn test_unknown_status_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            unknown_status = "drain"
+            unknown_retry = true
+            unknown_retry_delay_ms = 250
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.unknown_status, HealthStatus::Drain);
+        assert!(config.unknown_retry);
+        assert_eq!(config.unknown_retry_delay_ms, 250);
+    }
 
-        if let Ok(max_files) = std::env::var("HAPROXY_AGENT_LOG_FILE_MAX_FILES") {
-            config.logging.file_max_files = Some(
-                max_files
-                    .parse()
-                    .context("Invalid HAPROXY_AGENT_LOG_FILE_MAX_FILES")?,
-            );
-        }
+    #[test]
+    fn test_resource_exhausted_response_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            resource_exhausted_status = "up"
+            resource_exhausted_weight = 25
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.resource_exhausted_status, Some(HealthStatus::Up));
+        assert_eq!(config.resource_exhausted_weight, Some(25));
+        assert!(config.validate().is_ok());
+
+        // A weight alone has no status to go with
+        let config = AgentConfig {
+            resource_exhausted_weight: Some(25),
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
 
-        Ok(config)
+    #[test]
+    fn test_listener_configs_default_single() {
+        let config = AgentConfig::default();
+        let listeners = config.listener_configs();
+
+        assert_eq!(listeners.len(), 1);
+        assert_eq!(listeners[0].port, 5555);
+        assert_eq!(listeners[0].bind_address.as_deref(), Some("0.0.0.0"));
     }
 
-    // T032: Load configuration from TOML file
-    fn load_from_file(path: &PathBuf) -> Result<Self> {
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+    #[test]
+    fn test_listeners_from_toml_with_overrides() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            verbose_response = false
+
+            [[listeners]]
+            port = 5555
+
+            [[listeners]]
+            bind_address = "127.0.0.1"
+            port = 5556
+            verbose_response = true
+            response_status_map = { drain = "up" }
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        let listeners = config.listener_configs();
+        assert_eq!(listeners.len(), 2);
+
+        let first = config.for_listener(&listeners[0]);
+        assert_eq!(first.server_port, 5555);
+        assert_eq!(first.server_bind_address, "0.0.0.0");
+        assert!(!first.verbose_response);
+        assert!(first.response_status_map.is_empty());
+
+        let second = config.for_listener(&listeners[1]);
+        assert_eq!(second.server_port, 5556);
+        assert_eq!(second.server_bind_address, "127.0.0.1");
+        assert!(second.verbose_response);
+        assert_eq!(
+            second.response_status_map.get(&HealthStatus::Drain),
+            Some(&HealthStatus::Up)
+        );
+    }
 
-        let config: AgentConfig = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+    #[test]
+    fn test_listeners_validation_duplicate_address() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [[listeners]]
+            port = 5555
+
+            [[listeners]]
+            port = 5555
+            "#,
+        )
+        .unwrap();
 
-        Ok(config)
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Duplicate listener")
+        );
     }
 
-    // T031: Apply CLI argument overrides
-    fn apply_cli_overrides(mut config: AgentConfig, cli: CliArgs) -> Self {
-        if let Some(port) = cli.server_port {
-            config.server_port = port;
-        }
+    #[test]
+    fn test_tls_connect_timeout_fallback() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.connect_timeout_ms(SslFlag::Ssl), 1000);
+        assert_eq!(config.connect_timeout_ms(SslFlag::NoSsl), 1000);
 
-        if let Some(bind) = cli.server_bind {
-            config.server_bind_address = bind;
-        }
+        config.grpc_tls_connect_timeout_ms = Some(1500);
+        assert_eq!(config.connect_timeout_ms(SslFlag::Ssl), 1500);
+        assert_eq!(config.connect_timeout_ms(SslFlag::SslInsecure), 1500);
+        assert_eq!(config.connect_timeout_ms(SslFlag::NoSsl), 1000);
+    }
 
-        if let Some(port) = cli.metrics_port {
-            config.metrics_port = port;
-        }
+    #[test]
+    fn test_check_timeout_budget() {
+        let mut config = AgentConfig::default();
+        assert_eq!(config.check_timeout_budget_ms(), 2500);
 
-        if let Some(bind) = cli.metrics_bind {
-            config.metrics_bind_address = bind;
-        }
+        config.grpc_tls_connect_timeout_ms = Some(1500);
+        assert_eq!(config.check_timeout_budget_ms(), 3000);
 
-        if let Some(timeout) = cli.grpc_connect_timeout {
-            config.grpc_connect_timeout_ms = timeout;
-        }
+        config.response_deadline_ms = 1800;
+        assert_eq!(config.check_timeout_budget_ms(), 1800);
+    }
 
-        if let Some(timeout) = cli.grpc_rpc_timeout {
-            config.grpc_rpc_timeout_ms = timeout;
-        }
+    #[test]
+    fn test_validation_tls_connect_timeout_zero() {
+        let config = AgentConfig {
+            grpc_tls_connect_timeout_ms: Some(0),
+            ..AgentConfig::default()
+        };
 
-        if let Some(level) = cli.log_level {
-            config.log_level = level;
-        }
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("grpc_tls_connect_timeout_ms")
+        );
+    }
 
-        if let Some(format) = cli.log_format {
-            config.log_format = format;
-        }
+    #[test]
+    fn test_validation_connect_backoff() {
+        let config = AgentConfig {
+            connect_backoff_ms: 500,
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_ok());
 
-        if let Some(cache) = cli.grpc_channel_cache {
-            config.grpc_channel_cache_enabled = cache;
-        }
+        let config = AgentConfig {
+            connect_backoff_ms: 500,
+            connect_backoff_max_ms: 100,
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("connect_backoff_max_ms")
+        );
+    }
 
-        if let Some(dest) = cli.log_destination {
-            config.logging.destination = dest;
-        }
+    #[test]
+    fn test_tls_alpn_protocols() {
+        assert_eq!(AgentConfig::default().tls_alpn_protocols, vec!["h2"]);
 
-        if let Some(path) = cli.log_file_path {
-            config.logging.file_path = Some(path);
-        }
+        let config: AgentConfig =
+            toml::from_str(r#"tls_alpn_protocols = ["h2", "http/1.1"]"#).unwrap();
+        assert_eq!(config.tls_alpn_protocols, vec!["h2", "http/1.1"]);
+        assert!(config.validate().is_ok());
 
-        if let Some(rotation) = cli.log_file_rotation {
-            config.logging.file_rotation = Some(rotation);
-        }
+        let config = AgentConfig {
+            tls_alpn_protocols: Vec::new(),
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_err());
 
-        if let Some(max_files) = cli.log_file_max_files {
-            config.logging.file_max_files = Some(max_files);
-        }
+        let config = AgentConfig {
+            tls_alpn_protocols: vec!["h2".to_string(), String::new()],
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("tls_alpn_protocols")
+        );
+    }
 
-        config
+    #[test]
+    fn test_first_failure_response_from_toml() {
+        let config: AgentConfig = toml::from_str(r#"first_failure_response = "noop""#).unwrap();
+        assert_eq!(config.first_failure_response, FirstFailureResponse::Noop);
+        assert_eq!(
+            AgentConfig::default().first_failure_response,
+            FirstFailureResponse::Down
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_over_capacity_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            max_concurrent_checks = 64
+            over_capacity_response = "noop"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.max_concurrent_checks, 64);
+        assert_eq!(config.over_capacity_response, OverCapacityResponse::Noop);
+        assert_eq!(
+            AgentConfig::default().over_capacity_response,
+            OverCapacityResponse::Down
+        );
+    }
 
-    // T046: Unit test for config validation with valid config
     #[test]
-    fn test_config_validation_valid() {
+    fn test_validation_grpc_health_check_path() {
+        assert_eq!(
+            AgentConfig::default().grpc_health_check_path,
+            "/grpc.health.v1.Health/Check"
+        );
+
         let config = AgentConfig {
-            server_port: 5555,
-            server_bind_address: "0.0.0.0".to_string(),
-            grpc_connect_timeout_ms: 1000,
-            grpc_rpc_timeout_ms: 1500,
-            metrics_port: 9090,
-            metrics_bind_address: "0.0.0.0".to_string(),
-            grpc_channel_cache_enabled: true,
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            grpc_health_check_path: "/legacy.Health/Check".to_string(),
+            ..AgentConfig::default()
         };
+        assert!(config.validate().is_ok());
 
+        let config = AgentConfig {
+            grpc_health_check_path: "legacy.Health/Check".to_string(),
+            ..AgentConfig::default()
+        };
         let result = config.validate();
-        assert!(result.is_ok());
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("grpc_health_check_path")
+        );
     }
 
     #[test]
-    fn test_config_validation_valid_custom_ports() {
+    fn test_validation_warnings_and_strict_mode() {
         let config = AgentConfig {
-            server_port: 8080,
-            server_bind_address: "127.0.0.1".to_string(),
+            grpc_connect_timeout_ms: 1000,
+            grpc_rpc_timeout_ms: 1500,
+            ..AgentConfig::default()
+        };
+        let warnings = config.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].severity, Severity::Warning);
+        assert_eq!(warnings[0].code, "timeout_budget_too_high");
+        assert!(config.validate().is_ok());
+
+        let strict = AgentConfig {
+            strict_validation: true,
+            ..config
+        };
+        let err = strict.validate().unwrap_err().to_string();
+        assert!(err.contains("timeout_budget_too_high"), "{}", err);
+
+        let within_budget = AgentConfig {
             grpc_connect_timeout_ms: 500,
             grpc_rpc_timeout_ms: 1000,
-            grpc_channel_cache_enabled: true,
-            metrics_port: 9091,
-            metrics_bind_address: "127.0.0.1".to_string(),
-            log_level: LogLevel::Debug,
-            log_format: LogFormat::Pretty,
-            logging: LoggingConfig::default(),
+            strict_validation: true,
+            ..AgentConfig::default()
         };
-
-        let result = config.validate();
-        assert!(result.is_ok());
+        assert!(within_budget.warnings().is_empty());
+        assert!(within_budget.validate().is_ok());
     }
 
-    // T047: Unit test for config validation with port conflict
     #[test]
-    fn test_config_validation_port_conflict() {
+    fn test_validation_warns_on_timeout_relationships() {
+        let codes = |config: &AgentConfig| -> Vec<&'static str> {
+            config.warnings().iter().map(|issue| issue.code).collect()
+        };
+
         let config = AgentConfig {
-            server_port: 9090,
-            server_bind_address: "0.0.0.0".to_string(),
             grpc_connect_timeout_ms: 1000,
             grpc_rpc_timeout_ms: 1500,
-            grpc_channel_cache_enabled: true,
-            metrics_port: 9090, // Same as server_port!
+            response_deadline_ms: 1000,
+            ..AgentConfig::default()
+        };
+        assert_eq!(
+            codes(&config),
+            [
+                "connect_timeout_exceeds_deadline",
+                "rpc_timeout_exceeds_deadline"
+            ]
+        );
+
+        let config = AgentConfig {
+            connect_within_deadline: true,
+            ..AgentConfig::default()
+        };
+        assert!(codes(&config).contains(&"connect_within_deadline_without_deadline"));
+
+        let config = AgentConfig {
+            persistent_connections: false,
+            pipeline_depth: 4,
+            ..AgentConfig::default()
+        };
+        assert!(codes(&config).contains(&"pipeline_depth_without_persistent_connections"));
+
+        let config = AgentConfig {
+            admin_overrides_enabled: true,
             metrics_bind_address: "0.0.0.0".to_string(),
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            ..AgentConfig::default()
         };
+        assert!(codes(&config).contains(&"admin_overrides_exposed"));
+        for loopback in ["127.0.0.1", "::1", "localhost"] {
+            let config = AgentConfig {
+                admin_overrides_enabled: true,
+                metrics_bind_address: loopback.to_string(),
+                ..AgentConfig::default()
+            };
+            assert!(!codes(&config).contains(&"admin_overrides_exposed"));
+        }
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("cannot be the same"));
+        // A short connect timeout with a longer RPC timeout is fine
+        let config = AgentConfig {
+            grpc_connect_timeout_ms: 200,
+            grpc_rpc_timeout_ms: 1200,
+            response_deadline_ms: 1800,
+            ..AgentConfig::default()
+        };
+        assert!(codes(&config).is_empty());
+
+        let config = AgentConfig {
+            grpc_connect_timeout_ms: 500,
+            grpc_tls_connect_timeout_ms: Some(300),
+            grpc_rpc_timeout_ms: 1000,
+            ..AgentConfig::default()
+        };
+        assert_eq!(
+            codes(&config),
+            ["tls_connect_timeout_below_connect_timeout"]
+        );
+    }
+
+    #[test]
+    fn test_validation_issues_include_error() {
+        let config = AgentConfig {
+            grpc_connect_timeout_ms: 0,
+            ..AgentConfig::default()
+        };
+        let issues = config.validation_issues();
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert!(issues[0].message.contains("grpc_connect_timeout_ms"));
+    }
+
+    #[test]
+    fn test_validation_host_header_name() {
+        for name in ["host", "X-Forwarded-Host", ""] {
+            let config = AgentConfig {
+                host_header_name: name.to_string(),
+                ..AgentConfig::default()
+            };
+            assert!(config.validate().is_ok(), "{}", name);
+        }
+
+        for name in [":authority", "bad header", "host-bin"] {
+            let config = AgentConfig {
+                host_header_name: name.to_string(),
+                ..AgentConfig::default()
+            };
+            assert!(
+                config
+                    .validate()
+                    .unwrap_err()
+                    .to_string()
+                    .contains("host_header_name"),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation_user_agent() {
+        assert!(
+            AgentConfig::default()
+                .user_agent
+                .starts_with("haproxy-grpc-agent/")
+        );
+
+        for user_agent in ["lb-probe/2.1 (ops@example.com)", ""] {
+            let config = AgentConfig {
+                user_agent: user_agent.to_string(),
+                ..AgentConfig::default()
+            };
+            assert!(config.validate().is_ok(), "{}", user_agent);
+        }
+
+        let config = AgentConfig {
+            user_agent: "agent\r\nx-injected: 1".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("user_agent")
+        );
     }
 
     #[test]
-    fn test_config_validation_invalid_server_port_zero() {
+    fn test_validation_auth_token() {
         let config = AgentConfig {
-            server_port: 0,
-            server_bind_address: "0.0.0.0".to_string(),
-            grpc_connect_timeout_ms: 1000,
-            grpc_rpc_timeout_ms: 1500,
-            grpc_channel_cache_enabled: true,
-            metrics_port: 9090,
-            metrics_bind_address: "0.0.0.0".to_string(),
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            auth_token: "s3cret".to_string(),
+            ..AgentConfig::default()
         };
+        assert!(config.validate().is_ok());
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("server_port"));
+        let config = AgentConfig {
+            auth_token: "two words".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("auth_token")
+        );
     }
 
     #[test]
-    fn test_config_validation_invalid_metrics_port_zero() {
+    fn test_validation_metrics_tls_pair() {
         let config = AgentConfig {
-            server_port: 5555,
-            server_bind_address: "0.0.0.0".to_string(),
-            grpc_connect_timeout_ms: 1000,
-            grpc_rpc_timeout_ms: 1500,
-            grpc_channel_cache_enabled: true,
-            metrics_port: 0,
-            metrics_bind_address: "0.0.0.0".to_string(),
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            metrics_tls_cert: Some("/etc/agent/metrics.pem".to_string()),
+            ..AgentConfig::default()
         };
-
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("metrics_port"));
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must be set together")
+        );
     }
 
-    // T048: Unit test for config validation with invalid timeout
     #[test]
-    fn test_config_validation_invalid_connect_timeout_zero() {
+    fn test_validation_load_weight() {
         let config = AgentConfig {
-            server_port: 5555,
-            server_bind_address: "0.0.0.0".to_string(),
-            grpc_connect_timeout_ms: 0, // Invalid!
-            grpc_rpc_timeout_ms: 1500,
-            grpc_channel_cache_enabled: true,
-            metrics_port: 9090,
-            metrics_bind_address: "0.0.0.0".to_string(),
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            load_metadata_key: "x-active-conns".to_string(),
+            ..AgentConfig::default()
         };
+        assert!(config.validate().is_ok());
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("grpc_connect_timeout_ms"));
+        let config = AgentConfig {
+            load_metadata_key: "x active conns".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("load_metadata_key")
+        );
+
+        let config = AgentConfig {
+            load_weight_capacity: 0,
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_err());
     }
 
     #[test]
-    fn test_config_validation_invalid_rpc_timeout_zero() {
+    fn test_validation_success_weight() {
         let config = AgentConfig {
-            server_port: 5555,
-            server_bind_address: "0.0.0.0".to_string(),
-            grpc_connect_timeout_ms: 1000,
-            grpc_rpc_timeout_ms: 0, // Invalid!
-            grpc_channel_cache_enabled: true,
-            metrics_port: 9090,
-            metrics_bind_address: "0.0.0.0".to_string(),
-            log_level: LogLevel::Info,
-            log_format: LogFormat::Json,
-            logging: LoggingConfig::default(),
+            success_weight_window: 20,
+            success_weight_min_percent: 25,
+            ..AgentConfig::default()
         };
+        assert!(config.validate().is_ok());
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("grpc_rpc_timeout_ms"));
+        let config = AgentConfig {
+            success_weight_window: 20,
+            success_weight_min_percent: 101,
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("success_weight_min_percent")
+        );
     }
 
     #[test]
-    fn test_config_default_values() {
-        let config = AgentConfig::default();
+    fn test_validation_version_gate() {
+        let config = AgentConfig {
+            version_metadata_key: "x-app-version".to_string(),
+            min_backend_version: "v2.4.1".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_ok());
 
-        assert_eq!(config.server_port, 5555);
-        assert_eq!(config.server_bind_address, "0.0.0.0");
-        assert_eq!(config.grpc_connect_timeout_ms, 1000);
-        assert_eq!(config.grpc_rpc_timeout_ms, 1500);
-        assert_eq!(config.metrics_port, 9090);
-        assert_eq!(config.metrics_bind_address, "0.0.0.0");
-    }
+        let config = AgentConfig {
+            version_metadata_key: "x-app-version".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("min_backend_version")
+        );
 
-    #[test]
-    fn test_config_default_grpc_channel_cache_enabled() {
-        let config = AgentConfig::default();
+        let config = AgentConfig {
+            version_metadata_key: "x app version".to_string(),
+            min_backend_version: "2.4".to_string(),
+            ..AgentConfig::default()
+        };
         assert!(
-            config.grpc_channel_cache_enabled,
-            "grpc_channel_cache_enabled should default to true"
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("version_metadata_key")
         );
     }
 
-    // T008: LoggingConfig default and validation tests
     #[test]
-    fn test_logging_config_defaults() {
-        let logging = LoggingConfig::default();
-        assert!(matches!(logging.destination, LogDestination::Console));
-        assert!(logging.level.is_none());
-        assert!(logging.format.is_none());
-        assert!(logging.file_path.is_none());
-        assert!(logging.file_rotation.is_none());
-        assert!(logging.file_max_files.is_none());
-        assert!(logging.packages.is_empty());
+    fn test_resolve_secret() {
+        assert_eq!(resolve_secret("plain").unwrap(), "plain");
+        assert_eq!(
+            resolve_secret("${PATH}").unwrap(),
+            std::env::var("PATH").unwrap()
+        );
+
+        let err = resolve_secret("${HAPROXY_AGENT_TEST_UNSET_SECRET}").unwrap_err();
+        assert!(err.to_string().contains("HAPROXY_AGENT_TEST_UNSET_SECRET"));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"s3cret\n").unwrap();
+        let reference = format!("file:{}", file.path().display());
+        assert_eq!(resolve_secret(&reference).unwrap(), "s3cret");
+
+        let err = resolve_secret("file:/nonexistent/secret").unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/secret"));
     }
 
     #[test]
-    fn test_logging_config_in_agent_config_default() {
-        let config = AgentConfig::default();
-        assert!(matches!(
-            config.logging.destination,
-            LogDestination::Console
-        ));
-        assert!(config.logging.packages.is_empty());
+    fn test_load_records_setting_sources() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            b"server_port = 6000\ngrpc_rpc_timeout_ms = 1200\n\n[proxy]\nhost = \"proxy.internal\"\nusername = \"agent\"\npassword = \"s3cret\"\n\n[logging]\nlevel = \"debug\"\n",
+        )
+        .unwrap();
+        let cli = CliArgs::parse_from([
+            "haproxy-grpc-agent",
+            "--config",
+            file.path().to_str().unwrap(),
+            "--grpc-rpc-timeout",
+            "900",
+        ]);
+
+        let config = AgentConfig::load_with_cli(cli).unwrap();
+        let sources = config.setting_sources();
+        let find = |setting: &str| {
+            sources
+                .iter()
+                .find(|(name, _, _)| name == setting)
+                .unwrap_or_else(|| panic!("{} not listed", setting))
+                .clone()
+        };
+
+        assert_eq!(
+            find("server_port"),
+            (
+                "server_port".to_string(),
+                "6000".to_string(),
+                ConfigSource::File
+            )
+        );
+        assert_eq!(find("grpc_rpc_timeout_ms").2, ConfigSource::Cli);
+        assert_eq!(find("logging.level").2, ConfigSource::File);
+        assert_eq!(find("grpc_connect_timeout_ms").2, ConfigSource::Default);
+        assert_eq!(find("proxy.password").1, "<redacted>");
+        assert_eq!(find("proxy.username").1, "\"agent\"");
+        assert_eq!(find("dns.nameservers").1, "[]");
     }
 
     #[test]
-    fn test_logging_validation_file_without_path() {
-        let mut config = AgentConfig::default();
-        config.logging.destination = LogDestination::File;
-        config.logging.file_path = None;
-
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("file_path"));
+    fn test_credential_path() {
+        let dir = std::path::Path::new("/run/credentials/haproxy-agent.service");
+        assert_eq!(
+            credential_path("proxy-password", Some(dir)),
+            dir.join("proxy-password")
+        );
+        assert_eq!(
+            credential_path("/etc/agent/password", Some(dir)),
+            PathBuf::from("/etc/agent/password")
+        );
+        assert_eq!(
+            credential_path("proxy-password", None),
+            PathBuf::from("proxy-password")
+        );
     }
 
     #[test]
-    fn test_logging_validation_file_with_empty_path() {
+    fn test_resolve_secrets_proxy_credentials() {
         let mut config = AgentConfig::default();
-        config.logging.destination = LogDestination::File;
-        config.logging.file_path = Some("".to_string());
+        config.proxy.username = Some("agent".to_string());
+        config.proxy.password = Some("${HAPROXY_AGENT_TEST_UNSET_SECRET}".to_string());
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("file_path"));
+        let err = config.resolve_secrets().unwrap_err();
+        assert!(format!("{:#}", err).contains("proxy.password"));
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"s3cret\n").unwrap();
+        let mut config = AgentConfig {
+            auth_token: format!("file:{}", file.path().display()),
+            ..AgentConfig::default()
+        };
+        config.resolve_secrets().unwrap();
+        assert_eq!(config.auth_token, "s3cret");
     }
 
     #[test]
-    fn test_logging_validation_file_with_valid_path() {
-        let mut config = AgentConfig::default();
-        config.logging.destination = LogDestination::File;
-        config.logging.file_path = Some("/tmp/test.log".to_string());
+    fn test_resolve_credential_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = AgentConfig {
+            tls_ca_dir: Some("ca.d".to_string()),
+            metrics_tls_cert: Some("metrics.pem".to_string()),
+            metrics_tls_key: Some("file:metrics.key".to_string()),
+            ..AgentConfig::default()
+        };
+        config.resolve_credential_paths(Some(dir.path())).unwrap();
+        assert_eq!(
+            config.tls_ca_dir.as_deref().map(PathBuf::from),
+            Some(dir.path().join("ca.d"))
+        );
+        assert_eq!(
+            config.metrics_tls_cert.as_deref().map(PathBuf::from),
+            Some(dir.path().join("metrics.pem"))
+        );
+        assert_eq!(
+            config.metrics_tls_key.as_deref().map(PathBuf::from),
+            Some(dir.path().join("metrics.key"))
+        );
 
-        let result = config.validate();
-        assert!(result.is_ok());
+        // Absolute paths are left alone
+        let mut config = AgentConfig {
+            metrics_tls_cert: Some("/etc/agent/metrics.pem".to_string()),
+            ..AgentConfig::default()
+        };
+        config.resolve_credential_paths(Some(dir.path())).unwrap();
+        assert_eq!(
+            config.metrics_tls_cert.as_deref(),
+            Some("/etc/agent/metrics.pem")
+        );
+
+        let mut config = AgentConfig {
+            metrics_tls_key: Some("${HAPROXY_AGENT_TEST_UNSET_SECRET}".to_string()),
+            ..AgentConfig::default()
+        };
+        let err = config.resolve_credential_paths(None).unwrap_err();
+        assert!(format!("{:#}", err).contains("metrics_tls_key"));
     }
 
     #[test]
-    fn test_logging_validation_max_size_zero() {
-        let mut config = AgentConfig::default();
-        config.logging.file_max_files = Some(0);
+    fn test_self_test_config() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [self_test]
+            on_startup = true
+            canary = "127.0.0.1 50051 no-ssl localhost"
+            "#,
+        )
+        .unwrap();
+        assert!(config.self_test.on_startup);
+        assert!(!config.self_test.exit_after);
+        assert!(config.validate().is_ok());
 
-        let result = config.validate();
-        assert!(result.is_err());
-        let error_msg = result.unwrap_err().to_string();
-        assert!(error_msg.contains("file_max_files"));
+        let config = AgentConfig {
+            self_test: SelfTestConfig {
+                canary: Some("127.0.0.1 50051".to_string()),
+                ..SelfTestConfig::default()
+            },
+            ..AgentConfig::default()
+        };
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("self_test.canary"));
     }
 
     #[test]
-    fn test_logging_validation_console_ignores_file_settings() {
+    fn test_chaos_config_validation() {
         let mut config = AgentConfig::default();
-        config.logging.destination = LogDestination::Console;
-        // These should be ignored for console destination
-        config.logging.file_rotation = Some(LogRotation::Daily);
-        config.logging.file_max_files = Some(5);
+        config.chaos.down_probability = 1.5;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("chaos.down_probability"));
 
-        let result = config.validate();
-        assert!(result.is_ok());
+        config.chaos.down_probability = 0.5;
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "chaos"));
     }
 
-    // T012: Tests for resolved_level
     #[test]
-    fn test_resolved_level_logging_level_set() {
-        let logging = LoggingConfig {
-            level: Some(LogLevel::Debug),
-            ..Default::default()
-        };
-        let top_level = LogLevel::Info;
-
-        assert!(matches!(
-            logging.resolved_level(&top_level),
-            LogLevel::Debug
-        ));
+    fn test_proxy_config_validation() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [proxy]
+            host = "egress.internal"
+            username = "agent"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.proxy.port, 3128);
+        assert!(!format!("{:?}", config.proxy).contains("secret"));
+
+        let mut config = config;
+        config.proxy.password = None;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("proxy.username and proxy.password"));
     }
 
     #[test]
-    fn test_resolved_level_logging_level_unset_falls_back() {
-        let logging = LoggingConfig::default();
-        let top_level = LogLevel::Warn;
+    fn test_audit_config_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [audit]
+            enabled = true
+            level = "warn"
+            file_path = "/var/log/haproxy-agent/audit.log"
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.audit.enabled);
+        assert!(matches!(config.audit.level, LogLevel::Warn));
+        assert_eq!(
+            config.audit.file_path.as_deref(),
+            Some("/var/log/haproxy-agent/audit.log")
+        );
+        assert!(!AgentConfig::default().audit.enabled);
+    }
 
-        assert!(matches!(logging.resolved_level(&top_level), LogLevel::Warn));
+    #[test]
+    fn test_dns_config_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [dns]
+            nameservers = ["10.0.0.2", "10.0.0.3:5353"]
+            timeout_ms = 500
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.dns.timeout_ms, 500);
+        assert_eq!(
+            config.dns.nameserver_addrs().unwrap(),
+            vec![
+                "10.0.0.2:53".parse::<SocketAddr>().unwrap(),
+                "10.0.0.3:5353".parse::<SocketAddr>().unwrap(),
+            ]
+        );
+        assert!(AgentConfig::default().dns.nameservers.is_empty());
     }
 
     #[test]
-    fn test_resolved_level_both_set_logging_wins() {
-        let logging = LoggingConfig {
-            level: Some(LogLevel::Error),
-            ..Default::default()
-        };
-        let top_level = LogLevel::Trace;
+    fn test_validation_invalid_dns_nameserver() {
+        let mut config = AgentConfig::default();
+        config.dns.nameservers = vec!["dns.internal".to_string()];
 
-        assert!(matches!(
-            logging.resolved_level(&top_level),
-            LogLevel::Error
-        ));
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid DNS nameserver")
+        );
     }
 
-    // T013: Tests for build_env_filter_directive
     #[test]
-    fn test_env_filter_directive_no_overrides() {
-        let logging = LoggingConfig::default();
-        let directive = logging.build_env_filter_directive(&LogLevel::Info);
-        assert_eq!(directive, "info");
+    fn test_request_delimiter_from_toml() {
+        assert_eq!(
+            AgentConfig::default().request_delimiter,
+            RequestDelimiter::Newline
+        );
+
+        let config: AgentConfig = toml::from_str(r#"request_delimiter = "null""#).unwrap();
+        assert_eq!(config.request_delimiter, RequestDelimiter::Null);
+        assert_eq!(config.request_delimiter.as_byte(), 0);
     }
 
     #[test]
-    fn test_env_filter_directive_single_override() {
-        let mut logging = LoggingConfig::default();
-        logging
-            .packages
-            .insert("haproxy_grpc_agent::checker".to_string(), LogLevel::Debug);
-        let directive = logging.build_env_filter_directive(&LogLevel::Info);
-        assert!(directive.starts_with("info,"));
-        assert!(directive.contains("haproxy_grpc_agent::checker=debug"));
+    fn test_request_framing_per_listener() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [[listeners]]
+            port = 5555
+
+            [[listeners]]
+            port = 5556
+            request_framing = "length-prefixed"
+            "#,
+        )
+        .unwrap();
+
+        let framings: Vec<RequestFraming> = config
+            .listener_configs()
+            .iter()
+            .map(|listener| config.for_listener(listener).request_framing)
+            .collect();
+        assert_eq!(
+            framings,
+            [RequestFraming::Line, RequestFraming::LengthPrefixed]
+        );
     }
 
     #[test]
-    fn test_env_filter_directive_multiple_overrides() {
-        let mut logging = LoggingConfig::default();
-        logging
-            .packages
-            .insert("haproxy_grpc_agent::checker".to_string(), LogLevel::Debug);
-        logging.packages.insert("tonic".to_string(), LogLevel::Warn);
-        let directive = logging.build_env_filter_directive(&LogLevel::Info);
-        assert!(directive.starts_with("info,"));
-        assert!(directive.contains("haproxy_grpc_agent::checker=debug"));
-        assert!(directive.contains("tonic=warn"));
+    fn test_check_protocol_per_listener() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            http_check_path = "/ready"
+
+            [[listeners]]
+            port = 5555
+
+            [[listeners]]
+            port = 5556
+            check_protocol = "http"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let protocols: Vec<CheckProtocol> = config
+            .listener_configs()
+            .iter()
+            .map(|listener| config.for_listener(listener).check_protocol)
+            .collect();
+        assert_eq!(protocols, [CheckProtocol::Grpc, CheckProtocol::Http]);
+
+        let config = AgentConfig {
+            http_check_path: "healthz".to_string(),
+            ..AgentConfig::default()
+        };
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("http_check_path")
+        );
     }
 
     #[test]
-    fn test_env_filter_directive_with_logging_level() {
-        let logging = LoggingConfig {
-            level: Some(LogLevel::Warn),
-            ..Default::default()
-        };
-        let directive = logging.build_env_filter_directive(&LogLevel::Info);
-        assert_eq!(directive, "warn");
+    fn test_grpc_channel_config_from_toml() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+            [grpc_channel]
+            concurrency_limit = 256
+            buffer_size = 2048
+            initial_stream_window_size = 1048576
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.grpc_channel.concurrency_limit, Some(256));
+        assert_eq!(config.grpc_channel.buffer_size, Some(2048));
+        assert_eq!(
+            config.grpc_channel.initial_stream_window_size,
+            Some(1048576)
+        );
+        assert_eq!(config.grpc_channel.initial_connection_window_size, None);
+
+        let mut config = AgentConfig::default();
+        config.grpc_channel.buffer_size = Some(0);
+        assert!(config.validate().is_err());
     }
 }
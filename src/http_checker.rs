@@ -0,0 +1,286 @@
+// HTTP health checker
+// For backends that expose an HTTP endpoint such as /healthz instead of gRPC health
+
+use crate::checker::{CheckError, HealthChecker};
+use crate::config::AgentConfig;
+use crate::protocol::{HealthCheckRequest, HealthCheckResponse, HealthStatus, SslFlag};
+use crate::proxy;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Upper bound on the backend's status line
+const MAX_STATUS_LINE: u64 = 8 * 1024;
+
+/// Checks a backend with an HTTP/1.1 GET, one connection per check. Any 2xx status means up.
+///
+/// GrpcHealthChecker runs this probe for `check=http` requests, so they share its result cache,
+/// coalescing, DNS settings and overrides. Use it on its own via `AgentServer::with_checker`
+/// for an agent that only ever checks HTTP.
+pub struct HttpHealthChecker {
+    /// Trust anchors from tls_ca_dir for verified ssl checks
+    #[cfg(feature = "tls")]
+    tls_ca_bundle: Option<tonic::transport::Certificate>,
+    config: AgentConfig,
+}
+
+impl HttpHealthChecker {
+    pub fn new(config: AgentConfig) -> Self {
+        HttpHealthChecker {
+            #[cfg(feature = "tls")]
+            tls_ca_bundle: config.tls_ca_dir.as_deref().and_then(|dir| {
+                crate::tls::load_ca_dir(std::path::Path::new(dir))
+                    .inspect_err(|e| {
+                        tracing::error!(error = %format!("{:#}", e), "Failed to load tls_ca_dir")
+                    })
+                    .ok()
+            }),
+            config,
+        }
+    }
+
+    /// Path requested for a check: the request's service field, or http_check_path
+    pub fn resolve_path<'a>(&'a self, request: &'a HealthCheckRequest) -> &'a str {
+        request
+            .service
+            .as_deref()
+            .unwrap_or(&self.config.http_check_path)
+    }
+
+    #[tracing::instrument(
+        name = "http_check",
+        skip_all,
+        fields(
+            backend_server = %request.backend_server,
+            backend_port = request.backend_port,
+            ssl = ?request.ssl_flag,
+            path = %self.resolve_path(request),
+        )
+    )]
+    pub async fn check_backend(&self, request: &HealthCheckRequest) -> HealthCheckResponse {
+        match self.probe(&request.backend_server, request).await {
+            Ok(code) if is_success(code) => HealthCheckResponse::new(HealthStatus::Up),
+            Ok(_) => HealthCheckResponse::new(HealthStatus::Down).with_reason("not serving"),
+            Err(e) => {
+                tracing::error!(
+                    backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                    error = %e,
+                    error_type = %e.error_type(),
+                    "Health check failed"
+                );
+                HealthCheckResponse::new(HealthStatus::Down).with_reason(e.reason())
+            }
+        }
+    }
+
+    /// GET the check path from `host` (the backend server, possibly already resolved) and
+    /// return the response's status code. Connecting is bounded by the connect timeout for the
+    /// request's SSL mode, the exchange by grpc_rpc_timeout_ms.
+    pub(crate) async fn probe(
+        &self,
+        host: &str,
+        request: &HealthCheckRequest,
+    ) -> Result<u16, CheckError> {
+        let path = self.resolve_path(request);
+        let endpoint = format!(
+            "{}://{}:{}{}",
+            if request.ssl_flag.is_tls() {
+                "https"
+            } else {
+                "http"
+            },
+            host,
+            request.backend_port,
+            path
+        );
+        if !path.starts_with('/') || path.contains(char::is_whitespace) {
+            return Err(CheckError::InvalidEndpoint {
+                endpoint,
+                message: "HTTP check path must start with '/'".to_string(),
+            });
+        }
+
+        let mut head = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: */*\r\nConnection: close\r\n",
+            path, request.proxy_host_name
+        );
+        if !self.config.user_agent.is_empty() {
+            head.push_str(&format!("User-Agent: {}\r\n", self.config.user_agent));
+        }
+        head.push_str("\r\n");
+
+        let connect_timeout =
+            Duration::from_millis(self.config.connect_timeout_ms(request.ssl_flag));
+        let rpc_timeout = Duration::from_millis(self.config.grpc_rpc_timeout_ms);
+        let connect_error = |e: io::Error| {
+            #[cfg(feature = "tls")]
+            if let Some(failure) = crate::tls::handshake_failure(&e) {
+                return CheckError::TlsHandshake {
+                    endpoint: endpoint.clone(),
+                    message: failure.reason,
+                };
+            }
+            CheckError::ConnectFailed {
+                endpoint: endpoint.clone(),
+                message: e.to_string(),
+            }
+        };
+        let http_error = |e: io::Error| CheckError::HttpFailed {
+            endpoint: endpoint.clone(),
+            message: e.to_string(),
+        };
+
+        let tcp = tokio::time::timeout(
+            connect_timeout,
+            proxy::dial(&self.config.proxy, host, request.backend_port),
+        )
+        .await
+        .map_err(|_| CheckError::ConnectTimeout(endpoint.clone()))?
+        .map_err(connect_error)?;
+
+        let code = match request.ssl_flag {
+            SslFlag::NoSsl => tokio::time::timeout(rpc_timeout, exchange(tcp, &head)).await,
+            #[cfg(feature = "tls")]
+            SslFlag::Ssl | SslFlag::SslInsecure => {
+                let connector =
+                    crate::tls::http_tls_connector(request.ssl_flag, self.tls_ca_bundle.as_ref())?;
+                let server_name =
+                    rustls::pki_types::ServerName::try_from(request.proxy_host_name.clone())
+                        .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+                let tls =
+                    tokio::time::timeout(connect_timeout, connector.connect(server_name, tcp))
+                        .await
+                        .map_err(|_| CheckError::ConnectTimeout(endpoint.clone()))?
+                        .map_err(connect_error)?;
+                tokio::time::timeout(rpc_timeout, exchange(tls, &head)).await
+            }
+            #[cfg(not(feature = "tls"))]
+            SslFlag::Ssl | SslFlag::SslInsecure => {
+                return Err(CheckError::TlsConfig(
+                    "TLS support is not compiled in (build with the `tls` feature)".to_string(),
+                ));
+            }
+        };
+        let code = code
+            .map_err(|_| CheckError::RpcTimeout(rpc_timeout))?
+            .map_err(http_error)?;
+
+        tracing::debug!(status_code = code, "HTTP health check completed");
+        Ok(code)
+    }
+}
+
+impl HealthChecker for HttpHealthChecker {
+    fn check(
+        &self,
+        request: &HealthCheckRequest,
+    ) -> impl Future<Output = HealthCheckResponse> + Send {
+        self.check_backend(request)
+    }
+}
+
+/// True for the 2xx statuses that mean the backend is healthy
+pub(crate) fn is_success(code: u16) -> bool {
+    (200..300).contains(&code)
+}
+
+/// Send the request head and read back the status code; the body is never read
+async fn exchange<S>(mut stream: S, head: &str) -> io::Result<u16>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(head.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut status_line = Vec::new();
+    BufReader::new(&mut stream)
+        .take(MAX_STATUS_LINE)
+        .read_until(b'\n', &mut status_line)
+        .await?;
+    parse_status_line(&status_line).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "not an HTTP response: {:?}",
+                String::from_utf8_lossy(&status_line).trim_end()
+            ),
+        )
+    })
+}
+
+/// Status code of an `HTTP/1.x NNN reason` line
+fn parse_status_line(line: &[u8]) -> Option<u16> {
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let code = parts.next()?;
+    (code.len() == 3).then(|| code.parse().ok()).flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_http_peer;
+
+    fn request(port: u16, extra: &str) -> HealthCheckRequest {
+        crate::protocol::parse_request(&format!(
+            "127.0.0.1 {} no-ssl app.internal{} check=http",
+            port, extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_status_line() {
+        assert_eq!(parse_status_line(b"HTTP/1.1 200 OK\r\n"), Some(200));
+        assert_eq!(parse_status_line(b"HTTP/1.0 503\r\n"), Some(503));
+        assert_eq!(parse_status_line(b"SSH-2.0-OpenSSH\r\n"), None);
+        assert_eq!(parse_status_line(b"HTTP/1.1 20 OK\r\n"), None);
+        assert_eq!(parse_status_line(b""), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_up_on_2xx() {
+        let (port, backend) = mock_http_peer("HTTP/1.1 204 No Content\r\n\r\n").await;
+        let checker = HttpHealthChecker::new(AgentConfig::default());
+
+        let response = checker.check_backend(&request(port, "")).await;
+        assert_eq!(response.status, HealthStatus::Up);
+
+        let head = backend.await.unwrap();
+        assert!(head.starts_with("GET /healthz HTTP/1.1\r\n"), "{}", head);
+        assert!(head.contains("Host: app.internal\r\n"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn test_check_down_on_error_status() {
+        let (port, backend) =
+            mock_http_peer("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n").await;
+        let checker = HttpHealthChecker::new(AgentConfig::default());
+
+        let response = checker.check_backend(&request(port, " /ready")).await;
+        assert_eq!(response.status, HealthStatus::Down);
+        assert_eq!(response.reason, Some("not serving"));
+        assert!(
+            backend
+                .await
+                .unwrap()
+                .starts_with("GET /ready HTTP/1.1\r\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_down_on_non_http_reply() {
+        let (port, _backend) = mock_http_peer("SSH-2.0-OpenSSH_9.6\r\n").await;
+        let checker = HttpHealthChecker::new(AgentConfig::default());
+
+        match checker.probe("127.0.0.1", &request(port, "")).await {
+            Err(CheckError::HttpFailed { message, .. }) => {
+                assert!(message.contains("not an HTTP response"), "{}", message)
+            }
+            other => panic!("expected an HTTP failure, got {:?}", other),
+        }
+    }
+}
@@ -2,8 +2,18 @@
 // Makes modules accessible to tests and external usage
 
 pub mod checker;
+pub mod clock;
 pub mod config;
+pub mod http_checker;
+pub mod listener;
 pub mod logger;
+#[cfg(feature = "metrics")]
 pub mod metrics;
 pub mod protocol;
+mod proxy;
+pub mod selftest;
 pub mod server;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "tls")]
+mod tls;
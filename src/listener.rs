@@ -0,0 +1,375 @@
+// Listener module for the Agent Text Protocol server
+// Binds TCP or Unix domain sockets from the configured bind address
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpSocket};
+
+#[cfg(all(unix, feature = "unix-socket"))]
+use tokio::net::UnixListener;
+
+/// Byte stream for a single accepted agent connection
+pub trait AgentIo: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> AgentIo for T {}
+
+/// Parsed listen address.
+/// `unix:/path` or `/path` selects a filesystem Unix socket, `@name` an abstract one (Linux only);
+/// anything else is a TCP host combined with the configured port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(String),
+    Unix(String),
+    Abstract(String),
+}
+
+impl ListenAddr {
+    pub fn parse(bind_address: &str, port: u16) -> Self {
+        if let Some(name) = bind_address.strip_prefix('@') {
+            ListenAddr::Abstract(name.to_string())
+        } else if let Some(path) = bind_address.strip_prefix("unix:") {
+            ListenAddr::Unix(path.to_string())
+        } else if bind_address.starts_with('/') {
+            ListenAddr::Unix(bind_address.to_string())
+        } else {
+            ListenAddr::Tcp(format!("{}:{}", bind_address, port))
+        }
+    }
+
+    pub fn is_unix(&self) -> bool {
+        !matches!(self, ListenAddr::Tcp(_))
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path),
+            ListenAddr::Abstract(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+/// Remote end of an accepted agent connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerAddr {
+    Tcp(SocketAddr),
+    Unix,
+}
+
+impl PeerAddr {
+    /// Peer IP for TCP connections, with IPv4-mapped IPv6 addresses unwrapped to IPv4
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            PeerAddr::Tcp(addr) => Some(addr.ip().to_canonical()),
+            PeerAddr::Unix => None,
+        }
+    }
+}
+
+impl fmt::Display for PeerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerAddr::Tcp(addr) => write!(f, "{}", addr),
+            PeerAddr::Unix => f.write_str("unix"),
+        }
+    }
+}
+
+/// IPv4 or IPv6 network in CIDR notation; a bare address is a single-host network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Parses "10.0.0.0/8", "2001:db8::/32" or a bare address. Host bits may be set.
+    pub fn parse(cidr: &str) -> Option<Self> {
+        let (addr, prefix_len) = match cidr.trim().split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len.parse::<u8>().ok()?)),
+            None => (cidr.trim(), None),
+        };
+        let network: IpAddr = addr.parse().ok()?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        (prefix_len <= max_len).then_some(IpCidr {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Bound listening socket for the agent protocol
+pub enum AgentListener {
+    Tcp(TcpListener),
+    #[cfg(all(unix, feature = "unix-socket"))]
+    Unix(UnixListener),
+}
+
+impl AgentListener {
+    /// Bind the given address. The backlog only applies to TCP listeners.
+    pub async fn bind(addr: &ListenAddr, backlog: u32) -> Result<Self> {
+        match addr {
+            ListenAddr::Tcp(bind_addr) => Ok(AgentListener::Tcp(
+                bind_tcp_listener(bind_addr, backlog).await?,
+            )),
+            #[cfg(not(feature = "unix-socket"))]
+            ListenAddr::Unix(_) | ListenAddr::Abstract(_) => {
+                anyhow::bail!(
+                    "Unix socket support is not compiled in (build with the `unix-socket` feature)"
+                )
+            }
+            #[cfg(all(unix, feature = "unix-socket"))]
+            ListenAddr::Unix(path) => Ok(AgentListener::Unix(bind_unix_listener(path)?)),
+            #[cfg(all(target_os = "linux", feature = "unix-socket"))]
+            ListenAddr::Abstract(name) => Ok(AgentListener::Unix(bind_abstract_listener(name)?)),
+            #[cfg(all(not(target_os = "linux"), feature = "unix-socket"))]
+            ListenAddr::Abstract(_) => {
+                anyhow::bail!("Abstract Unix sockets are only supported on Linux")
+            }
+            #[cfg(all(not(unix), feature = "unix-socket"))]
+            ListenAddr::Unix(_) => anyhow::bail!("Unix sockets are not supported on this platform"),
+        }
+    }
+
+    /// Enable TCP Fast Open with the given pending queue length.
+    /// Ok(false) when it doesn't apply: a Unix socket, or a platform without TFO support.
+    pub fn set_fast_open(&self, queue: u32) -> std::io::Result<bool> {
+        match self {
+            AgentListener::Tcp(listener) => set_tcp_fast_open(listener, queue),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            AgentListener::Unix(_) => Ok(false),
+        }
+    }
+
+    /// TCP address the listener is bound to; None for Unix sockets
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            AgentListener::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            AgentListener::Unix(_) => None,
+        }
+    }
+
+    /// Accept the next connection, returning the stream and its peer
+    pub async fn accept(&self) -> std::io::Result<(Box<dyn AgentIo>, PeerAddr)> {
+        match self {
+            AgentListener::Tcp(listener) => {
+                let (stream, peer_addr) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Tcp(peer_addr)))
+            }
+            #[cfg(all(unix, feature = "unix-socket"))]
+            AgentListener::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok((Box::new(stream), PeerAddr::Unix))
+            }
+        }
+    }
+}
+
+impl From<TcpListener> for AgentListener {
+    fn from(listener: TcpListener) -> Self {
+        AgentListener::Tcp(listener)
+    }
+}
+
+/// Bind a TCP listener with an explicit accept backlog.
+/// A deep backlog absorbs reconnect bursts from a large HAProxy fleet after an agent restart.
+async fn bind_tcp_listener(bind_addr: &str, backlog: u32) -> Result<TcpListener> {
+    let addr = tokio::net::lookup_host(bind_addr)
+        .await?
+        .next()
+        .with_context(|| format!("No addresses resolved for {}", bind_addr))?;
+
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+
+    // Match TcpListener::bind defaults so restarts don't trip over TIME_WAIT sockets
+    #[cfg(unix)]
+    socket.set_reuseaddr(true)?;
+
+    socket.bind(addr)?;
+    Ok(socket.listen(backlog)?)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_tcp_fast_open(listener: &TcpListener, queue: u32) -> std::io::Result<bool> {
+    use std::os::fd::AsRawFd;
+
+    let queue = libc::c_int::try_from(queue).unwrap_or(libc::c_int::MAX);
+    // SAFETY: the fd is a live socket owned by `listener`, and the option value is a c_int
+    // that outlives the call, as TCP_FASTOPEN expects
+    let result = unsafe {
+        libc::setsockopt(
+            listener.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_FASTOPEN,
+            (&raw const queue).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(true)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_tcp_fast_open(_listener: &TcpListener, _queue: u32) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// Whether the kernel accepts Fast Open connections on listeners (net.ipv4.tcp_fastopen bit 2).
+/// None when that can't be read.
+pub fn kernel_fast_open_server_enabled() -> Option<bool> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        std::fs::read_to_string("/proc/sys/net/ipv4/tcp_fastopen")
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|flags| flags & 0x2 != 0)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    None
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+fn bind_unix_listener(path: &str) -> Result<UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // Remove a stale socket left behind by a previous run, but never a regular file
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove stale socket {}", path))?;
+    }
+
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Abstract-namespace sockets live outside the filesystem, so there is nothing to clean up
+#[cfg(all(target_os = "linux", feature = "unix-socket"))]
+fn bind_abstract_listener(name: &str) -> Result<UnixListener> {
+    use std::os::linux::net::SocketAddrExt;
+
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+    let listener = std::os::unix::net::UnixListener::bind_addr(&addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(UnixListener::from_std(listener)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen_addr_parse_tcp() {
+        assert_eq!(
+            ListenAddr::parse("0.0.0.0", 5555),
+            ListenAddr::Tcp("0.0.0.0:5555".to_string())
+        );
+    }
+
+    #[test]
+    fn test_listen_addr_parse_unix() {
+        assert_eq!(
+            ListenAddr::parse("/run/agent.sock", 5555),
+            ListenAddr::Unix("/run/agent.sock".to_string())
+        );
+        assert_eq!(
+            ListenAddr::parse("unix:/run/agent.sock", 5555),
+            ListenAddr::Unix("/run/agent.sock".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ip_cidr_contains() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        let v4 = IpCidr::parse("10.1.0.0/16").unwrap();
+        assert!(v4.contains(ip("10.1.255.7")));
+        assert!(!v4.contains(ip("10.2.0.1")));
+        assert!(!v4.contains(ip("::ffff:10.1.0.1")));
+
+        let host = IpCidr::parse("192.0.2.10").unwrap();
+        assert!(host.contains(ip("192.0.2.10")));
+        assert!(!host.contains(ip("192.0.2.11")));
+
+        let v6 = IpCidr::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains(ip("2001:db8:1::1")));
+        assert!(!v6.contains(ip("2001:db9::1")));
+
+        assert!(
+            IpCidr::parse("0.0.0.0/0")
+                .unwrap()
+                .contains(ip("203.0.113.1"))
+        );
+        assert!(IpCidr::parse("::/0").unwrap().contains(ip("::1")));
+    }
+
+    #[test]
+    fn test_ip_cidr_parse_invalid() {
+        for cidr in [
+            "",
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0/8",
+            "10.0.0.0/x",
+            "host/8",
+        ] {
+            assert_eq!(IpCidr::parse(cidr), None, "{}", cidr);
+        }
+    }
+
+    #[test]
+    fn test_peer_addr_ip_unwraps_mapped_ipv4() {
+        let peer = PeerAddr::Tcp("[::ffff:10.0.0.1]:40000".parse().unwrap());
+        assert_eq!(peer.ip(), Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(PeerAddr::Unix.ip(), None);
+        assert_eq!(PeerAddr::Unix.to_string(), "unix");
+    }
+
+    #[test]
+    fn test_listen_addr_parse_abstract() {
+        let addr = ListenAddr::parse("@haproxy-agent", 5555);
+        assert_eq!(addr, ListenAddr::Abstract("haproxy-agent".to_string()));
+        assert_eq!(addr.to_string(), "@haproxy-agent");
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[tokio::test]
+    async fn test_set_fast_open_on_tcp_listener() {
+        let listener = AgentListener::bind(&ListenAddr::Tcp("127.0.0.1:0".to_string()), 16)
+            .await
+            .unwrap();
+        assert!(listener.set_fast_open(16).unwrap());
+    }
+}
@@ -1,69 +1,171 @@
 // T018: logger.rs module file
 // Structured logging module using tracing
-// Configures JSON logging with trace IDs and configurable output destination
+// Configures JSON logging with trace IDs and configurable output destination,
+// plus an optional audit stream with its own destination and level
 
-use crate::config::{AgentConfig, LogDestination, LogFormat, LogRotation};
+use crate::config::{AgentConfig, AuditConfig, LogFormat, LogRotation};
 use anyhow::Result;
+use std::io::{self, Write};
+use tracing_appender::non_blocking::NonBlocking;
 use tracing_appender::rolling;
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Target of audit records. The operational log always filters it out.
+pub const AUDIT_TARGET: &str = "audit";
+
+/// Version of the JSON log line layout, sent as the first key of every JSON line.
+/// Bumped whenever the layout changes in a way parsers would notice.
+pub const LOG_SCHEMA_VERSION: u32 = 1;
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
 /// Initialize the logging system based on configuration.
-/// Supports console (stderr) and file output destinations with optional rotation.
+/// Supports console (stderr) and file output destinations with optional rotation, or both at
+/// once with a format of their own.
 pub fn init(config: &AgentConfig) -> Result<()> {
     let directive = config.logging.build_env_filter_directive(&config.log_level);
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&directive));
-
-    let format = config.logging.resolved_format(&config.log_format);
-
-    match config.logging.destination {
-        LogDestination::Console => init_console(env_filter, format),
-        LogDestination::File => {
-            let file_path = config
-                .logging
-                .file_path
-                .as_deref()
-                .expect("file_path validated in config");
-            init_file(
-                env_filter,
-                format,
-                file_path,
-                config.logging.file_rotation,
-                config.logging.file_max_files,
-            )?;
-        }
+    // EnvFilter isn't Clone, so each operational layer builds its own
+    let env_filter = || -> Result<EnvFilter> {
+        Ok(EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(&directive))
+            .add_directive(format!("{}=off", AUDIT_TARGET).parse()?))
+    };
+
+    let mut layers = Vec::new();
+    if config.logging.destination.writes_console() {
+        layers.push(operational_layer(
+            config.logging.resolved_console_format(&config.log_format),
+            BoxMakeWriter::new(std::io::stderr),
+            env_filter()?,
+        ));
+    }
+    if config.logging.destination.writes_file() {
+        let file_path = config
+            .logging
+            .file_path
+            .as_deref()
+            .expect("file_path validated in config");
+        let writer = BoxMakeWriter::new(file_writer(
+            file_path,
+            config.logging.file_rotation,
+            config.logging.file_max_files,
+        )?);
+        layers.push(operational_layer(
+            config.logging.resolved_file_format(&config.log_format),
+            writer,
+            env_filter()?,
+        ));
+    }
+    if config.audit.enabled {
+        layers.push(audit_layer(&config.audit)?);
     }
 
+    tracing_subscriber::registry().with(layers).init();
+
     Ok(())
 }
 
-fn init_console(env_filter: EnvFilter, format: LogFormat) {
+fn operational_layer(
+    format: LogFormat,
+    writer: BoxMakeWriter,
+    env_filter: EnvFilter,
+) -> BoxedLayer {
     match format {
-        LogFormat::Json => {
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .json()
-                .with_current_span(false)
-                .with_span_list(true)
-                .with_writer(std::io::stderr)
-                .init();
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(SchemaVersioned(writer))
+            .with_filter(env_filter)
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_filter(env_filter)
+            .boxed(),
+    }
+}
+
+/// One flat JSON line per audit record, without span context, written to stdout or a file
+fn audit_layer(audit: &AuditConfig) -> Result<BoxedLayer> {
+    let writer = match audit.file_path.as_deref() {
+        Some(file_path) => BoxMakeWriter::new(file_writer(file_path, None, None)?),
+        None => BoxMakeWriter::new(std::io::stdout),
+    };
+    let level: LevelFilter = audit.level.as_str().parse()?;
+
+    Ok(tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_target(false)
+        .with_writer(SchemaVersioned(writer))
+        .with_filter(Targets::new().with_target(AUDIT_TARGET, level))
+        .boxed())
+}
+
+/// Writer wrapper that adds `"schema_version"` to each JSON line.
+/// The fmt layer writes every event with one write_all on a fresh writer, so the first write
+/// starts the line.
+struct SchemaVersioned<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for SchemaVersioned<M> {
+    type Writer = SchemaVersionWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SchemaVersionWriter {
+            inner: self.0.make_writer(),
+            started: false,
         }
-        LogFormat::Pretty => {
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .with_writer(std::io::stderr)
-                .init();
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        SchemaVersionWriter {
+            inner: self.0.make_writer_for(meta),
+            started: false,
         }
     }
 }
 
-fn init_file(
-    env_filter: EnvFilter,
-    format: LogFormat,
+struct SchemaVersionWriter<W> {
+    inner: W,
+    started: bool,
+}
+
+impl<W: Write> Write for SchemaVersionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.started {
+            self.started = true;
+            if let Some(rest) = buf.strip_prefix(b"{") {
+                // One write, so lines from other threads can't land in between
+                let mut line = Vec::with_capacity(buf.len() + 24);
+                write!(line, "{{\"schema_version\":{}", LOG_SCHEMA_VERSION)?;
+                if !rest.starts_with(b"}") {
+                    line.push(b',');
+                }
+                line.extend_from_slice(rest);
+                self.inner.write_all(&line)?;
+                return Ok(buf.len());
+            }
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn file_writer(
     file_path: &str,
     rotation: Option<LogRotation>,
     max_files: Option<usize>,
-) -> Result<()> {
+) -> Result<NonBlocking> {
     let path = std::path::Path::new(file_path);
 
     // Ensure parent directory exists
@@ -109,23 +211,27 @@ fn init_file(
     // This is intentional — the guard must not be dropped or logs will stop being written.
     std::mem::forget(_guard);
 
-    match format {
-        LogFormat::Json => {
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .json()
-                .with_current_span(false)
-                .with_span_list(true)
-                .with_writer(non_blocking)
-                .init();
-        }
-        LogFormat::Pretty => {
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .with_writer(non_blocking)
-                .init();
-        }
-    }
+    Ok(non_blocking)
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_version_prefixes_json_lines() {
+        let mut line = Vec::new();
+        let mut writer = SchemaVersionWriter {
+            inner: &mut line,
+            started: false,
+        };
+        writer
+            .write_all(b"{\"timestamp\":\"t\",\"level\":\"INFO\"}\n")
+            .unwrap();
+
+        let log: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(log["schema_version"], LOG_SCHEMA_VERSION);
+        assert_eq!(log["level"], "INFO");
+        assert!(line.starts_with(b"{\"schema_version\":1,\"timestamp\""));
+    }
 }
@@ -1,14 +1,11 @@
 // T012: Basic tokio main function skeleton
 // T077: Wire together config, logger, and server
 
-mod checker;
-mod config;
-mod logger;
-mod metrics;
-mod protocol;
-mod server;
-
 use anyhow::{Context, Result};
+#[cfg(feature = "metrics")]
+use haproxy_grpc_agent::metrics;
+use haproxy_grpc_agent::{checker, config, logger, selftest, server};
+use std::sync::Arc;
 use tokio::signal;
 
 // T138: Graceful shutdown signal handler
@@ -65,11 +62,52 @@ async fn main() -> Result<()> {
         "HAProxy gRPC Agent starting"
     );
 
+    if config.report_only {
+        tracing::warn!(
+            "Report-only mode is active: checks run and are logged as usual, but HAProxy is always answered up"
+        );
+    }
+
+    if config.log_config_sources {
+        for (setting, value, source) in config.setting_sources() {
+            tracing::info!(
+                setting = %setting,
+                value = %value,
+                source = source.as_str(),
+                "Configuration value"
+            );
+        }
+    }
+
+    // Shared by every listener and by the metrics server's admin endpoint
+    let health_checker = Arc::new(checker::GrpcHealthChecker::new(config.clone()));
+
+    // Runs before anything binds, so the bind checks don't trip over our own listeners
+    if config.self_test.exit_after || config.self_test.on_startup {
+        let report = selftest::run(&config, &health_checker).await;
+        if config.self_test.exit_after {
+            println!("{}", report);
+            std::process::exit(if report.passed() { 0 } else { 1 });
+        }
+        if !report.passed() {
+            anyhow::bail!("Startup self-test failed:\n{}", report);
+        }
+    }
+
+    health_checker.spawn_channel_sweep();
+
     // T128: Create metrics server
     // T129: Graceful degradation - metrics failure doesn't stop health checks
+    #[cfg(feature = "metrics")]
     let metrics_server_result = metrics::MetricsServer::new(&config);
+    #[cfg(feature = "metrics")]
     let metrics_handle = match metrics_server_result {
+        Ok(_) if !config.metrics_endpoint_enabled => {
+            tracing::debug!("Metrics endpoint disabled");
+            None
+        }
         Ok(metrics_server) => {
+            let metrics_server = metrics_server.with_health_checker(Arc::clone(&health_checker));
             tracing::debug!("Metrics server configured successfully");
             Some(tokio::spawn(async move {
                 if let Err(e) = metrics_server.run().await {
@@ -87,30 +125,80 @@ async fn main() -> Result<()> {
         }
     };
 
-    // T077: Create and run TCP server
-    let server = server::AgentServer::new(config);
+    // Pushing to StatsD is independent of the scrape endpoint, and degrades the same way
+    #[cfg(feature = "metrics")]
+    let statsd_handle = match metrics::StatsdExporter::new(&config) {
+        Ok(Some(exporter)) => {
+            tracing::info!(
+                statsd = %format!("{}:{}", config.statsd.host.as_deref().unwrap_or_default(), config.statsd.port),
+                "Pushing metrics to StatsD"
+            );
+            Some(tokio::spawn(exporter.run()))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!(
+                error = %e,
+                "Failed to initialize StatsD exporter - continuing without it"
+            );
+            None
+        }
+    };
 
-    tracing::info!("Initialization complete, starting server");
+    // T077: Create and run TCP servers, one per listener, sharing a single health checker
+    let mut server_handles = Vec::new();
+    // Shared so idle_shutdown_ms only fires once every listener is idle
+    let activity = Arc::new(server::ActivityTracker::new());
+
+    for listener in config.listener_configs() {
+        let server = server::AgentServer::with_checker(
+            config.for_listener(&listener),
+            Arc::clone(&health_checker),
+        )
+        .with_activity_tracker(Arc::clone(&activity));
+        // T138: Run server with graceful shutdown
+        let handle = server.start().await.context("Failed to start server")?;
+        server_handles.push(handle);
+    }
 
-    // T138: Run server with graceful shutdown
-    let server_handle = tokio::spawn(async move { server.run().await });
+    tracing::info!(
+        listeners = server_handles.len(),
+        "Initialization complete, servers started"
+    );
 
-    // T138: Wait for either server to exit or shutdown signal
-    tokio::select! {
-        result = server_handle => {
-            if let Ok(Err(e)) = result {
-                tracing::error!(error = %e, "Server error");
+    match config.idle_shutdown_ms {
+        Some(idle_ms) => tokio::select! {
+            _ = shutdown_signal() => {
+                tracing::info!("Shutdown signal received, stopping server...");
             }
-        }
-        _ = shutdown_signal() => {
+            _ = activity.wait_idle(std::time::Duration::from_millis(idle_ms)) => {
+                tracing::info!(idle_shutdown_ms = idle_ms, "Agent idle, stopping server...");
+            }
+        },
+        None => {
+            shutdown_signal().await;
             tracing::info!("Shutdown signal received, stopping server...");
         }
     }
 
+    // Listeners drain their connections concurrently
+    let results =
+        futures_util::future::join_all(server_handles.into_iter().map(|h| h.shutdown())).await;
+    for result in results {
+        if let Err(e) = result {
+            tracing::error!(error = %e, "Server error");
+        }
+    }
+
     // Clean up metrics server
+    #[cfg(feature = "metrics")]
     if let Some(handle) = metrics_handle {
         handle.abort();
     }
+    #[cfg(feature = "metrics")]
+    if let Some(handle) = statsd_handle {
+        handle.abort();
+    }
 
     tracing::info!("Shutdown complete");
     Ok(())
@@ -1,6 +1,9 @@
 // Agent Text Protocol parser and formatter
 // T035-T041: Complete protocol implementation
 
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
 // T036: SslFlag enum
@@ -8,6 +11,63 @@ use thiserror::Error;
 pub enum SslFlag {
     Ssl,
     NoSsl,
+    /// TLS without verifying the backend certificate (`ssl-insecure`)
+    SslInsecure,
+}
+
+impl SslFlag {
+    /// True for both verified and unverified TLS
+    pub fn is_tls(self) -> bool {
+        matches!(self, SslFlag::Ssl | SslFlag::SslInsecure)
+    }
+
+    /// Returns the wire token ("ssl" / "no-ssl" / "ssl-insecure")
+    pub fn as_token(self) -> &'static str {
+        match self {
+            SslFlag::Ssl => "ssl",
+            SslFlag::NoSsl => "no-ssl",
+            SslFlag::SslInsecure => "ssl-insecure",
+        }
+    }
+
+    /// Parses a wire token (case-sensitive, as HAProxy sends it)
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "ssl" => Some(SslFlag::Ssl),
+            "no-ssl" => Some(SslFlag::NoSsl),
+            "ssl-insecure" => Some(SslFlag::SslInsecure),
+            _ => None,
+        }
+    }
+}
+
+/// How the backend's health is checked: the gRPC health protocol or an HTTP GET
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckProtocol {
+    #[default]
+    Grpc,
+    Http,
+}
+
+impl CheckProtocol {
+    /// Returns the name used in the `check=` request token and in config
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckProtocol::Grpc => "grpc",
+            CheckProtocol::Http => "http",
+        }
+    }
+
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "grpc" => Some(CheckProtocol::Grpc),
+            "http" => Some(CheckProtocol::Http),
+            _ => None,
+        }
+    }
 }
 
 // T035: HealthCheckRequest struct
@@ -17,64 +77,259 @@ pub struct HealthCheckRequest {
     pub backend_port: u16,
     pub ssl_flag: SslFlag,
     pub proxy_host_name: String,
+    /// Optional gRPC health service name, or the path for HTTP checks; None falls back to the
+    /// configured default
+    pub service: Option<String>,
+    /// Optional HAProxy check interval (`interval=<ms>` token), used to size result cache freshness
+    pub interval_hint: Option<Duration>,
+    /// Optional weight (`<N>%` token) echoed back when the backend is up
+    pub weight: Option<u8>,
+    /// Optional check protocol (`check=grpc` / `check=http` token); None uses the listener's
+    pub check_protocol: Option<CheckProtocol>,
+}
+
+/// Formats the request line the agent expects, including the trailing newline.
+/// Inverse of `parse_request` for requests whose fields contain no whitespace.
+impl fmt::Display for HealthCheckRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.backend_server,
+            self.backend_port,
+            self.ssl_flag.as_token(),
+            self.proxy_host_name
+        )?;
+        if let Some(service) = &self.service {
+            write!(f, " {}", service)?;
+        }
+        if let Some(interval) = self.interval_hint {
+            write!(f, " interval={}", interval.as_millis())?;
+        }
+        if let Some(weight) = self.weight {
+            write!(f, " {}%", weight)?;
+        }
+        if let Some(protocol) = self.check_protocol {
+            write!(f, " check={}", protocol.as_str())?;
+        }
+        writeln!(f)
+    }
 }
 
 // T038: HealthStatus enum
-#[derive(Debug, Clone, Copy, PartialEq)]
+// Drain and Maint are HAProxy agent states used by configurable status mappings; Ready is
+// their counterpart that takes a server out of drain or maint.
+// Fail and Stopped are HAProxy's alternative down tokens ("fail" marks the check failed,
+// "stopped" marks the server stopped). HAProxy has no "success" token; "up" is its counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     Up,
     Down,
+    Drain,
+    Maint,
+    Ready,
+    Fail,
+    Stopped,
 }
 
 impl HealthStatus {
-    pub fn to_protocol_string(self) -> String {
+    /// Returns the bare agent protocol token ("up" / "down") without a line terminator
+    pub fn as_token(self) -> &'static str {
         match self {
-            HealthStatus::Up => "up\n".to_string(),
-            HealthStatus::Down => "down\n".to_string(),
+            HealthStatus::Up => "up",
+            HealthStatus::Down => "down",
+            HealthStatus::Drain => "drain",
+            HealthStatus::Maint => "maint",
+            HealthStatus::Ready => "ready",
+            HealthStatus::Fail => "fail",
+            HealthStatus::Stopped => "stopped",
         }
     }
+
+    /// Parses a bare agent protocol token (case-insensitive)
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token.to_lowercase().as_str() {
+            "up" => Some(HealthStatus::Up),
+            "down" => Some(HealthStatus::Down),
+            "drain" => Some(HealthStatus::Drain),
+            "maint" => Some(HealthStatus::Maint),
+            "ready" => Some(HealthStatus::Ready),
+            "fail" => Some(HealthStatus::Fail),
+            "stopped" => Some(HealthStatus::Stopped),
+            _ => None,
+        }
+    }
+
+    /// Returns the complete response line ("up\n" / "down\n") as a static byte slice
+    pub fn as_line_bytes(self) -> &'static [u8] {
+        match self {
+            HealthStatus::Up => b"up\n",
+            HealthStatus::Down => b"down\n",
+            HealthStatus::Drain => b"drain\n",
+            HealthStatus::Maint => b"maint\n",
+            HealthStatus::Ready => b"ready\n",
+            HealthStatus::Fail => b"fail\n",
+            HealthStatus::Stopped => b"stopped\n",
+        }
+    }
+
+    pub fn to_protocol_string(self) -> String {
+        format!("{}\n", self.as_token())
+    }
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_token())
+    }
 }
 
 // T037: HealthCheckResponse struct
 #[derive(Debug, Clone)]
 pub struct HealthCheckResponse {
     pub status: HealthStatus,
+    /// Optional short failure reason, appended as a `# reason` comment.
+    /// HAProxy ignores everything after the `#`, so this is for humans only.
+    pub reason: Option<&'static str>,
+    /// Optional weight as a percentage of the server's configured weight, sent as `N%`
+    pub weight: Option<u8>,
+    /// Send nothing and close the connection, so HAProxy keeps its current agent state
+    pub noop: bool,
+    /// gRPC ServingStatus name the backend answered with ("SERVING", "NOT_SERVING", ...),
+    /// when the check got an answer
+    pub serving_status: Option<&'static str>,
 }
 
 impl HealthCheckResponse {
     pub fn new(status: HealthStatus) -> Self {
-        HealthCheckResponse { status }
+        HealthCheckResponse {
+            status,
+            reason: None,
+            weight: None,
+            noop: false,
+            serving_status: None,
+        }
     }
 
-    #[allow(clippy::inherent_to_string)]
-    pub fn to_string(&self) -> String {
-        self.status.to_protocol_string()
+    pub fn with_reason(mut self, reason: &'static str) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+
+    pub fn with_weight(mut self, weight_percent: u8) -> Self {
+        self.weight = Some(weight_percent);
+        self
+    }
+
+    pub fn with_serving_status(mut self, serving_status: &'static str) -> Self {
+        self.serving_status = Some(serving_status);
+        self
+    }
+
+    /// Answer with nothing; the status stays for logs, metrics and the audit record
+    pub fn into_noop(mut self) -> Self {
+        self.noop = true;
+        self
+    }
+
+    /// True when the response is a bare status token with nothing appended
+    pub fn is_bare(&self) -> bool {
+        self.reason.is_none() && self.weight.is_none()
     }
 }
 
+/// Formats the full response line, including the trailing newline
+impl fmt::Display for HealthCheckResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.status)?;
+        if let Some(weight) = self.weight {
+            write!(f, " {}%", weight)?;
+        }
+        if let Some(reason) = self.reason {
+            write!(f, " # {}", reason)?;
+        }
+        writeln!(f)
+    }
+}
+
+/// A response line as read back by a client of the agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgentResponse {
+    pub status: HealthStatus,
+    /// Weight percentage from an `N%` token
+    pub weight: Option<u8>,
+    /// Text after `#`, if any
+    pub comment: Option<String>,
+}
+
 // T041: ParseError enum
 #[derive(Debug, Error)]
 pub enum ParseError {
-    #[error("Invalid field count: expected 4, got {0}")]
+    #[error(
+        "Invalid field count: expected 4 or 5 plus an optional interval hint, weight and check protocol, got {0}"
+    )]
     InvalidFieldCount(usize),
 
+    #[error("Invalid check protocol: {0} (expected 'check=grpc' or 'check=http')")]
+    InvalidCheckProtocol(String),
+
+    #[error("Invalid interval hint: {0} (expected 'interval=<ms>')")]
+    InvalidIntervalHint(String),
+
+    #[error("Invalid weight: {0} (expected '<percent>%')")]
+    InvalidWeight(String),
+
     #[error("Invalid port: {0}")]
     InvalidPort(String),
 
-    #[error("Invalid SSL flag: {0} (expected 'ssl' or 'no-ssl')")]
+    #[error("Invalid SSL flag: {0} (expected 'ssl', 'ssl-insecure' or 'no-ssl')")]
     InvalidSslFlag(String),
 
     #[error("Empty field: {0}")]
     EmptyField(String),
+
+    #[error("Invalid status: {0}")]
+    InvalidStatus(String),
+}
+
+impl ParseError {
+    /// Label used for the PARSE_ERRORS_TOTAL variant dimension
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            ParseError::InvalidFieldCount(_) => "invalid_field_count",
+            ParseError::InvalidIntervalHint(_) => "invalid_interval_hint",
+            ParseError::InvalidCheckProtocol(_) => "invalid_check_protocol",
+            ParseError::InvalidPort(_) => "invalid_port",
+            ParseError::InvalidSslFlag(_) => "invalid_ssl_flag",
+            ParseError::EmptyField(_) => "empty_field",
+            ParseError::InvalidStatus(_) => "invalid_status",
+            ParseError::InvalidWeight(_) => "invalid_weight",
+        }
+    }
+}
+
+/// Parses a weight token such as `75%`; None when the token isn't one
+fn parse_weight(token: &str) -> Option<u8> {
+    token.strip_suffix('%')?.parse().ok()
 }
 
+/// Maximum number of whitespace-separated tokens scanned before a line is rejected.
+/// Bounds the work done on adversarial input; the reported field count saturates here.
+pub const MAX_SCANNED_FIELDS: usize = 9;
+
 // T039: parse_request function
 pub fn parse_request(line: &str) -> Result<HealthCheckRequest, ParseError> {
-    let trimmed = line.trim();
-    let parts: Vec<&str> = trimmed.split_whitespace().collect();
-
-    // T040: Validate field count
-    if parts.len() != 4 {
+    // Strips the request delimiter, whether newline or null byte
+    let trimmed = line.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    let parts: Vec<&str> = trimmed
+        .split_whitespace()
+        .take(MAX_SCANNED_FIELDS)
+        .collect();
+
+    // T040: Validate field count (optional service name, interval hint, weight and check protocol
+    // follow the 4 required fields)
+    if !(4..=8).contains(&parts.len()) {
         return Err(ParseError::InvalidFieldCount(parts.len()));
     }
 
@@ -83,6 +338,37 @@ pub fn parse_request(line: &str) -> Result<HealthCheckRequest, ParseError> {
     let ssl_flag_str = parts[2];
     let proxy_host_name = parts[3];
 
+    let mut service = None;
+    let mut interval_hint = None;
+    let mut weight = None;
+    let mut check_protocol = None;
+    for extra in &parts[4..] {
+        if extra.ends_with('%') {
+            let percent =
+                parse_weight(extra).ok_or_else(|| ParseError::InvalidWeight(extra.to_string()))?;
+            if weight.replace(percent).is_some() {
+                return Err(ParseError::InvalidFieldCount(parts.len()));
+            }
+        } else if let Some(ms) = extra.strip_prefix("interval=") {
+            let ms: u64 = ms
+                .parse()
+                .ok()
+                .filter(|ms| *ms > 0)
+                .ok_or_else(|| ParseError::InvalidIntervalHint(extra.to_string()))?;
+            if interval_hint.replace(Duration::from_millis(ms)).is_some() {
+                return Err(ParseError::InvalidFieldCount(parts.len()));
+            }
+        } else if let Some(protocol) = extra.strip_prefix("check=") {
+            let protocol = CheckProtocol::from_token(protocol)
+                .ok_or_else(|| ParseError::InvalidCheckProtocol(extra.to_string()))?;
+            if check_protocol.replace(protocol).is_some() {
+                return Err(ParseError::InvalidFieldCount(parts.len()));
+            }
+        } else if service.replace(extra.to_string()).is_some() {
+            return Err(ParseError::InvalidFieldCount(parts.len()));
+        }
+    }
+
     // Validate backend_server not empty
     if backend_server.is_empty() {
         return Err(ParseError::EmptyField("backend_server".to_string()));
@@ -100,11 +386,8 @@ pub fn parse_request(line: &str) -> Result<HealthCheckRequest, ParseError> {
     }
 
     // T040: Validate and parse ssl_flag
-    let ssl_flag = match ssl_flag_str {
-        "ssl" => SslFlag::Ssl,
-        "no-ssl" => SslFlag::NoSsl,
-        _ => return Err(ParseError::InvalidSslFlag(ssl_flag_str.to_string())),
-    };
+    let ssl_flag = SslFlag::from_token(ssl_flag_str)
+        .ok_or_else(|| ParseError::InvalidSslFlag(ssl_flag_str.to_string()))?;
 
     // Validate proxy_host_name not empty
     if proxy_host_name.is_empty() {
@@ -116,6 +399,44 @@ pub fn parse_request(line: &str) -> Result<HealthCheckRequest, ParseError> {
         backend_port,
         ssl_flag,
         proxy_host_name: proxy_host_name.to_string(),
+        service,
+        interval_hint,
+        weight,
+        check_protocol,
+    })
+}
+
+/// Parse an agent response line (`status[ N%][ # comment]`), the inverse of
+/// `HealthCheckResponse`'s Display. For tools that talk to the agent directly.
+pub fn parse_response(line: &str) -> Result<AgentResponse, ParseError> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let (words, comment) = match line.split_once('#') {
+        Some((words, comment)) => (words, Some(comment.trim().to_string())),
+        None => (line, None),
+    };
+
+    let mut tokens = words.split_whitespace();
+    let status_token = tokens.next().unwrap_or_default();
+    let status = HealthStatus::from_token(status_token)
+        .ok_or_else(|| ParseError::InvalidStatus(status_token.to_string()))?;
+
+    let weight = match tokens.next() {
+        Some(token) => {
+            Some(parse_weight(token).ok_or_else(|| ParseError::InvalidWeight(token.to_string()))?)
+        }
+        None => None,
+    };
+
+    if tokens.next().is_some() {
+        return Err(ParseError::InvalidFieldCount(
+            words.split_whitespace().count(),
+        ));
+    }
+
+    Ok(AgentResponse {
+        status,
+        weight,
+        comment,
     })
 }
 
@@ -127,6 +448,46 @@ mod tests {
     fn test_health_status_to_string() {
         assert_eq!(HealthStatus::Up.to_protocol_string(), "up\n");
         assert_eq!(HealthStatus::Down.to_protocol_string(), "down\n");
+        assert_eq!(HealthStatus::Drain.to_protocol_string(), "drain\n");
+        assert_eq!(HealthStatus::Maint.to_protocol_string(), "maint\n");
+    }
+
+    #[test]
+    fn test_health_status_from_token() {
+        assert_eq!(HealthStatus::from_token("up"), Some(HealthStatus::Up));
+        assert_eq!(HealthStatus::from_token("DRAIN"), Some(HealthStatus::Drain));
+        assert_eq!(HealthStatus::from_token("fail"), Some(HealthStatus::Fail));
+        assert_eq!(
+            HealthStatus::from_token("stopped"),
+            Some(HealthStatus::Stopped)
+        );
+        assert_eq!(HealthStatus::from_token("ready"), Some(HealthStatus::Ready));
+        // Not part of HAProxy's agent-check vocabulary
+        assert_eq!(HealthStatus::from_token("success"), None);
+    }
+
+    #[test]
+    fn test_health_status_line_bytes() {
+        for status in [
+            HealthStatus::Up,
+            HealthStatus::Down,
+            HealthStatus::Drain,
+            HealthStatus::Maint,
+            HealthStatus::Ready,
+            HealthStatus::Fail,
+            HealthStatus::Stopped,
+        ] {
+            assert_eq!(
+                status.as_line_bytes(),
+                status.to_protocol_string().as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_health_status_display() {
+        assert_eq!(HealthStatus::Up.to_string(), "up");
+        assert_eq!(format!("{}", HealthStatus::Maint), "maint");
     }
 
     #[test]
@@ -135,6 +496,28 @@ mod tests {
         assert_eq!(response.to_string(), "up\n");
     }
 
+    #[test]
+    fn test_health_check_response_fail_with_reason() {
+        let response = HealthCheckResponse::new(HealthStatus::Fail).with_reason("not serving");
+        assert_eq!(response.to_string(), "fail # not serving\n");
+    }
+
+    #[test]
+    fn test_health_check_response_with_weight() {
+        let response = HealthCheckResponse::new(HealthStatus::Up).with_weight(40);
+        assert_eq!(response.to_string(), "up 40%\n");
+        assert!(!response.is_bare());
+
+        let response = response.with_reason("slow start");
+        assert_eq!(response.to_string(), "up 40% # slow start\n");
+    }
+
+    #[test]
+    fn test_health_check_response_with_reason() {
+        let response = HealthCheckResponse::new(HealthStatus::Down).with_reason("connect timeout");
+        assert_eq!(response.to_string(), "down # connect timeout\n");
+    }
+
     // T042: Unit test for parse_request with valid input
     #[test]
     fn test_parse_request_valid() {
@@ -147,6 +530,18 @@ mod tests {
         assert_eq!(request.backend_port, 50051);
         assert_eq!(request.ssl_flag, SslFlag::NoSsl);
         assert_eq!(request.proxy_host_name, "backend.example.com");
+        assert_eq!(request.service, None);
+    }
+
+    #[test]
+    fn test_parse_request_valid_with_service() {
+        let input = "backend.example.com 50051 no-ssl backend.example.com my.package.Service";
+        let result = parse_request(input);
+
+        assert!(result.is_ok());
+        let request = result.unwrap();
+        assert_eq!(request.proxy_host_name, "backend.example.com");
+        assert_eq!(request.service.as_deref(), Some("my.package.Service"));
     }
 
     #[test]
@@ -162,6 +557,16 @@ mod tests {
         assert_eq!(request.proxy_host_name, "secure.example.com");
     }
 
+    #[test]
+    fn test_parse_request_valid_ssl_insecure() {
+        let request = parse_request("secure.example.com 443 ssl-insecure secure.example.com")
+            .expect("ssl-insecure should parse");
+
+        assert_eq!(request.ssl_flag, SslFlag::SslInsecure);
+        assert!(request.ssl_flag.is_tls());
+        assert!(!SslFlag::NoSsl.is_tls());
+    }
+
     #[test]
     fn test_parse_request_valid_ip_address() {
         let input = "192.168.1.100 9090 no-ssl api.internal";
@@ -188,6 +593,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_request_with_interval_hint() {
+        let request =
+            parse_request("backend.example.com 50051 no-ssl proxy.host interval=2000\n").unwrap();
+        assert_eq!(request.service, None);
+        assert_eq!(request.interval_hint, Some(Duration::from_millis(2000)));
+
+        let request =
+            parse_request("backend.example.com 50051 no-ssl proxy.host interval=500 my.Service\n")
+                .unwrap();
+        assert_eq!(request.service.as_deref(), Some("my.Service"));
+        assert_eq!(request.interval_hint, Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_parse_request_invalid_interval_hint() {
+        for input in [
+            "backend.example.com 50051 no-ssl proxy.host interval=abc",
+            "backend.example.com 50051 no-ssl proxy.host interval=0",
+        ] {
+            match parse_request(input).unwrap_err() {
+                ParseError::InvalidIntervalHint(token) => assert!(token.starts_with("interval=")),
+                e => panic!("Expected InvalidIntervalHint error, got {:?}", e),
+            }
+        }
+
+        let result =
+            parse_request("backend.example.com 50051 no-ssl proxy.host interval=1 interval=2");
+        assert!(matches!(result, Err(ParseError::InvalidFieldCount(6))));
+    }
+
     #[test]
     fn test_parse_request_invalid_field_count_too_many() {
         let input = "backend.example.com 50051 no-ssl proxy.host extra field";
@@ -200,6 +636,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_request_pathological_field_count() {
+        let input = "x ".repeat(1_000_000);
+        let result = parse_request(&input);
+
+        match result.unwrap_err() {
+            ParseError::InvalidFieldCount(count) => assert_eq!(count, MAX_SCANNED_FIELDS),
+            _ => panic!("Expected InvalidFieldCount error"),
+        }
+    }
+
     #[test]
     fn test_parse_request_empty_input() {
         let input = "";
@@ -268,12 +715,163 @@ mod tests {
         let result = parse_request(input);
 
         assert!(result.is_err());
-        match result.unwrap_err() {
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid SSL flag: no_ssl (expected 'ssl', 'ssl-insecure' or 'no-ssl')"
+        );
+        match err {
             ParseError::InvalidSslFlag(_) => {}
             _ => panic!("Expected InvalidSslFlag error"),
         }
     }
 
+    #[test]
+    fn test_parse_error_variant_name() {
+        let cases = [
+            ("backend 50051 no-ssl", "invalid_field_count"),
+            ("backend 0 no-ssl proxy", "invalid_port"),
+            ("backend 50051 tls proxy", "invalid_ssl_flag"),
+            (
+                "backend 50051 no-ssl proxy interval=x",
+                "invalid_interval_hint",
+            ),
+            (
+                "backend 50051 no-ssl proxy check=tcp",
+                "invalid_check_protocol",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_request(input).unwrap_err().variant_name(), expected);
+        }
+        assert_eq!(
+            ParseError::EmptyField("backend_server".to_string()).variant_name(),
+            "empty_field"
+        );
+    }
+
+    #[test]
+    fn test_request_display_round_trip() {
+        for line in [
+            "backend.example.com 50051 no-ssl proxy.host\n",
+            "10.0.0.1 443 ssl-insecure proxy.host my.Service interval=2000\n",
+            "backend 50051 ssl proxy interval=500\n",
+            "backend 50051 no-ssl proxy my.Service interval=500 75%\n",
+            "backend 8080 no-ssl proxy /healthz interval=500 75% check=http\n",
+        ] {
+            let request = parse_request(line).unwrap();
+            assert_eq!(request.to_string(), line);
+            assert_eq!(parse_request(&request.to_string()).unwrap(), request);
+        }
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response = parse_response("up\n").unwrap();
+        assert_eq!(response.status, HealthStatus::Up);
+        assert_eq!(response.weight, None);
+        assert_eq!(response.comment, None);
+
+        let response = parse_response("drain 40% # slow start").unwrap();
+        assert_eq!(response.status, HealthStatus::Drain);
+        assert_eq!(response.weight, Some(40));
+        assert_eq!(response.comment.as_deref(), Some("slow start"));
+    }
+
+    #[test]
+    fn test_parse_response_round_trip() {
+        let sent = HealthCheckResponse::new(HealthStatus::Down)
+            .with_weight(10)
+            .with_reason("connect timeout");
+        let parsed = parse_response(&sent.to_string()).unwrap();
+        assert_eq!(parsed.status, sent.status);
+        assert_eq!(parsed.weight, sent.weight);
+        assert_eq!(parsed.comment.as_deref(), sent.reason);
+    }
+
+    #[test]
+    fn test_parse_response_invalid() {
+        assert!(matches!(
+            parse_response("healthy\n"),
+            Err(ParseError::InvalidStatus(_))
+        ));
+        assert!(matches!(
+            parse_response("\n"),
+            Err(ParseError::InvalidStatus(_))
+        ));
+        assert!(matches!(
+            parse_response("up 40\n"),
+            Err(ParseError::InvalidWeight(_))
+        ));
+        assert!(matches!(
+            parse_response("up 40% extra\n"),
+            Err(ParseError::InvalidFieldCount(3))
+        ));
+    }
+
+    #[test]
+    fn test_parse_request_with_weight() {
+        let request = parse_request("backend 50051 no-ssl proxy 75%\n").unwrap();
+        assert_eq!(request.weight, Some(75));
+        assert_eq!(request.service, None);
+
+        let request = parse_request("backend 50051 no-ssl proxy 0% svc interval=1000\n").unwrap();
+        assert_eq!(request.weight, Some(0));
+        assert_eq!(request.service.as_deref(), Some("svc"));
+
+        for input in [
+            "backend 50051 no-ssl proxy 300%",
+            "backend 50051 no-ssl proxy x%",
+        ] {
+            assert!(
+                matches!(parse_request(input), Err(ParseError::InvalidWeight(_))),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_request("backend 50051 no-ssl proxy 50% 60%"),
+            Err(ParseError::InvalidFieldCount(6))
+        ));
+    }
+
+    #[test]
+    fn test_parse_request_with_check_protocol() {
+        let request = parse_request("backend 50051 no-ssl proxy\n").unwrap();
+        assert_eq!(request.check_protocol, None);
+
+        let request = parse_request("backend 8080 no-ssl proxy check=http /ready\n").unwrap();
+        assert_eq!(request.check_protocol, Some(CheckProtocol::Http));
+        assert_eq!(request.service.as_deref(), Some("/ready"));
+
+        let request = parse_request("backend 50051 ssl proxy check=grpc").unwrap();
+        assert_eq!(request.check_protocol, Some(CheckProtocol::Grpc));
+
+        for input in [
+            "backend 50051 no-ssl proxy check=",
+            "backend 50051 no-ssl proxy check=HTTP",
+        ] {
+            assert!(
+                matches!(
+                    parse_request(input),
+                    Err(ParseError::InvalidCheckProtocol(_))
+                ),
+                "{}",
+                input
+            );
+        }
+        assert!(matches!(
+            parse_request("backend 50051 no-ssl proxy check=http check=grpc"),
+            Err(ParseError::InvalidFieldCount(6))
+        ));
+    }
+
+    #[test]
+    fn test_parse_request_null_delimited() {
+        let request = parse_request("backend.example.com 50051 no-ssl proxy.host\0").unwrap();
+        assert_eq!(request.proxy_host_name, "proxy.host");
+    }
+
     #[test]
     fn test_parse_request_with_whitespace() {
         let input = "  backend.example.com   50051   no-ssl   proxy.host  ";
@@ -0,0 +1,231 @@
+// Startup self-test
+// Checks listeners can bind, TLS support initializes and an optional canary backend answers up,
+// so misconfiguration fails the deploy instead of the first real health check
+
+use crate::checker::{FIRST_FAILURE_REASON, GrpcHealthChecker};
+use crate::config::AgentConfig;
+use crate::listener::{AgentListener, ListenAddr};
+use crate::protocol::{self, HealthStatus};
+use std::fmt;
+
+/// Outcome of one self-test step: a short detail on success, a diagnostic on failure
+#[derive(Debug)]
+pub struct SelfTestStep {
+    pub name: String,
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.result.is_ok())
+    }
+
+    fn push(&mut self, name: impl Into<String>, result: Result<String, String>) {
+        let name = name.into();
+        match &result {
+            Ok(detail) => tracing::info!(step = %name, detail = %detail, "Self-test step passed"),
+            Err(error) => tracing::error!(step = %name, error = %error, "Self-test step failed"),
+        }
+        self.steps.push(SelfTestStep { name, result });
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            match &step.result {
+                Ok(detail) => writeln!(f, "ok    {}: {}", step.name, detail)?,
+                Err(error) => writeln!(f, "FAIL  {}: {}", step.name, error)?,
+            }
+        }
+        write!(
+            f,
+            "self-test {}",
+            if self.passed() { "passed" } else { "failed" }
+        )
+    }
+}
+
+/// Run every self-test step. Nothing is left bound afterwards.
+pub async fn run(config: &AgentConfig, checker: &GrpcHealthChecker) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+
+    for listener in config.listener_configs() {
+        let listener_config = config.for_listener(&listener);
+        let addr = ListenAddr::parse(
+            &listener_config.server_bind_address,
+            listener_config.server_port,
+        );
+        report.push(
+            format!("bind {}", addr),
+            check_bind(&addr, config.listen_backlog).await,
+        );
+    }
+
+    #[cfg(feature = "metrics")]
+    if config.metrics_endpoint_enabled {
+        let addr = ListenAddr::parse(&config.metrics_bind_address, config.metrics_port);
+        report.push(format!("bind metrics {}", addr), check_bind(&addr, 1).await);
+    }
+
+    #[cfg(feature = "tls")]
+    report.push(
+        "tls",
+        crate::tls::self_test(&config.tls_alpn_protocols)
+            .map(|()| "client configurations build".to_string())
+            .map_err(|e| e.to_string()),
+    );
+    #[cfg(not(feature = "tls"))]
+    report.push(
+        "tls",
+        Ok("not compiled in; ssl backends will answer down".to_string()),
+    );
+
+    if let Some(canary) = &config.self_test.canary {
+        report.push(
+            format!("canary {}", canary),
+            check_canary(canary, checker).await,
+        );
+    }
+
+    report
+}
+
+/// Bind and immediately release the address. A filesystem socket is not bound, since that
+/// would replace the socket of an agent already running there; its directory is checked instead.
+async fn check_bind(addr: &ListenAddr, backlog: u32) -> Result<String, String> {
+    if let ListenAddr::Unix(path) = addr {
+        let dir = std::path::Path::new(path)
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        return match std::fs::metadata(dir) {
+            Ok(metadata) if metadata.is_dir() => Ok(format!("directory {} exists", dir.display())),
+            Ok(_) => Err(format!("{} is not a directory", dir.display())),
+            Err(e) => Err(format!("{}: {}", dir.display(), e)),
+        };
+    }
+
+    AgentListener::bind(addr, backlog)
+        .await
+        .map(|_| "address available".to_string())
+        .map_err(|e| format!("{:#}", e))
+}
+
+async fn check_canary(canary: &str, checker: &GrpcHealthChecker) -> Result<String, String> {
+    let request = protocol::parse_request(canary).map_err(|e| e.to_string())?;
+    let response = checker.check_backend(&request).await;
+    match response.status {
+        // first_failure_response must not let a dead canary pass
+        HealthStatus::Up if response.reason == Some(FIRST_FAILURE_REASON) => {
+            Err("failed; answered up only because first_failure_response held it back".to_string())
+        }
+        HealthStatus::Up => Ok("answered up".to_string()),
+        status => Err(match response.reason {
+            Some(reason) => format!("answered {} ({})", status.as_token(), reason),
+            None => format!("answered {}", status.as_token()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SelfTestConfig;
+
+    #[tokio::test]
+    async fn test_self_test_reports_port_in_use() {
+        let occupied = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = AgentConfig {
+            server_bind_address: "127.0.0.1".to_string(),
+            server_port: occupied.local_addr().unwrap().port(),
+            metrics_bind_address: "127.0.0.1".to_string(),
+            metrics_port: 0,
+            ..AgentConfig::default()
+        };
+        let checker = GrpcHealthChecker::new(config.clone());
+
+        let report = run(&config, &checker).await;
+
+        assert!(!report.passed());
+        assert!(report.steps[0].result.is_err());
+        assert!(report.steps[1..].iter().all(|step| step.result.is_ok()));
+        assert!(report.to_string().ends_with("self-test failed"));
+    }
+
+    #[tokio::test]
+    async fn test_self_test_skips_disabled_metrics_endpoint() {
+        let occupied = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = AgentConfig {
+            server_bind_address: "127.0.0.1".to_string(),
+            server_port: 0,
+            metrics_bind_address: "127.0.0.1".to_string(),
+            metrics_port: occupied.local_addr().unwrap().port(),
+            metrics_endpoint_enabled: false,
+            ..AgentConfig::default()
+        };
+        let checker = GrpcHealthChecker::new(config.clone());
+
+        let report = run(&config, &checker).await;
+
+        assert!(report.passed());
+        assert!(
+            !report
+                .steps
+                .iter()
+                .any(|step| step.name.contains("metrics"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_self_test_canary_must_be_up() {
+        // Nothing listens on the discard port
+        let config = AgentConfig {
+            server_bind_address: "127.0.0.1".to_string(),
+            server_port: 0,
+            metrics_bind_address: "127.0.0.1".to_string(),
+            metrics_port: 0,
+            grpc_connect_timeout_ms: 200,
+            self_test: SelfTestConfig {
+                canary: Some("127.0.0.1 9 no-ssl localhost".to_string()),
+                ..SelfTestConfig::default()
+            },
+            ..AgentConfig::default()
+        };
+        let checker = GrpcHealthChecker::new(config.clone());
+
+        let report = run(&config, &checker).await;
+
+        let canary = report.steps.last().unwrap();
+        assert!(canary.name.starts_with("canary"));
+        assert!(
+            canary
+                .result
+                .as_ref()
+                .unwrap_err()
+                .starts_with("answered down")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_self_test_canary_first_failure_not_held_back() {
+        let config = AgentConfig {
+            grpc_connect_timeout_ms: 200,
+            first_failure_response: crate::config::FirstFailureResponse::Up,
+            self_test: SelfTestConfig {
+                canary: Some("127.0.0.1 9 no-ssl localhost".to_string()),
+                ..SelfTestConfig::default()
+            },
+            ..AgentConfig::default()
+        };
+        let checker = GrpcHealthChecker::new(config.clone());
+
+        let result = check_canary(config.self_test.canary.as_deref().unwrap(), &checker).await;
+        assert!(result.unwrap_err().contains("first_failure_response"));
+    }
+}
@@ -1,87 +1,453 @@
 // TCP server module for Agent Text Protocol
 // T067-T076: Complete TCP server implementation
 
-use crate::checker::GrpcHealthChecker;
-use crate::config::AgentConfig;
+use crate::checker::{GrpcHealthChecker, HealthChecker};
+use crate::config::{
+    AgentConfig, BlankLineResponse, OverCapacityResponse, OverloadResponse, RequestFraming,
+};
+use crate::listener::{AgentListener, IpCidr, ListenAddr, PeerAddr};
+use crate::logger::AUDIT_TARGET;
+#[cfg(feature = "metrics")]
 use crate::metrics;
-use crate::protocol;
+use crate::protocol::{self, HealthCheckRequest, HealthCheckResponse, HealthStatus};
 use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesOrdered;
+use std::io::Write as _;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{Semaphore, oneshot, watch};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::Instrument;
 use uuid::Uuid;
 
-pub struct AgentServer {
-    config: AgentConfig,
-    health_checker: Arc<GrpcHealthChecker>,
+/// Agent Text Protocol server, generic over the checker that answers each request
+pub struct AgentServer<C: HealthChecker = GrpcHealthChecker> {
+    config: Arc<AgentConfig>,
+    health_checker: Arc<C>,
+    stats: Arc<ServerStats>,
+    /// Parsed client_allowlist; empty allows every peer
+    client_allowlist: Vec<IpCidr>,
+    /// Limits open connections; None when max_connections is 0
+    connection_permits: Option<Arc<Semaphore>>,
+    /// Limits connections being answered with overload_response at once
+    overload_permits: Arc<Semaphore>,
+}
+
+/// Most connections answered with overload_response at once; beyond that they are closed
+/// straight away, so a flood can't pile up answering tasks past max_connections
+const MAX_OVERLOAD_ANSWERS: usize = 64;
+
+/// How long an overloaded connection may take to send its request before it is answered anyway
+const OVERLOAD_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a connection gets to send the auth_token; HAProxy sends it with its request
+const AUTH_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Extra time open connections get on shutdown beyond the check timeout budget, to write their
+/// last answer
+const SHUTDOWN_DRAIN_MARGIN: Duration = Duration::from_secs(1);
+
+/// When the agent last accepted a connection or ran a check, for idle_shutdown_ms.
+/// Share one tracker between listeners so the agent only counts as idle when all of them are.
+pub struct ActivityTracker {
+    started_at: Instant,
+    /// Milliseconds since started_at
+    last_active_ms: AtomicU64,
+}
+
+impl ActivityTracker {
+    pub fn new() -> Self {
+        ActivityTracker {
+            started_at: Instant::now(),
+            last_active_ms: AtomicU64::new(0),
+        }
+    }
+
+    pub fn touch(&self) {
+        self.last_active_ms.fetch_max(
+            self.started_at.elapsed().as_millis() as u64,
+            Ordering::Relaxed,
+        );
+    }
+
+    fn idle_for(&self) -> Duration {
+        let last_active = Duration::from_millis(self.last_active_ms.load(Ordering::Relaxed));
+        self.started_at.elapsed().saturating_sub(last_active)
+    }
+
+    /// Completes once nothing has touched the tracker for `idle`
+    pub async fn wait_idle(&self, idle: Duration) {
+        loop {
+            let idle_for = self.idle_for();
+            if idle_for >= idle {
+                return;
+            }
+            tokio::time::sleep(idle - idle_for).await;
+        }
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counters reported by the stats command, covering checks answered by one server,
+/// and the activity tracker idle_shutdown_ms watches
+struct ServerStats {
+    started_at: Instant,
+    up: AtomicU64,
+    down: AtomicU64,
+    other: AtomicU64,
+    activity: Arc<ActivityTracker>,
+}
+
+impl ServerStats {
+    fn new(activity: Arc<ActivityTracker>) -> Self {
+        ServerStats {
+            started_at: Instant::now(),
+            up: AtomicU64::new(0),
+            down: AtomicU64::new(0),
+            other: AtomicU64::new(0),
+            activity,
+        }
+    }
+
+    fn record(&self, status: HealthStatus) {
+        let counter = match status {
+            HealthStatus::Up => &self.up,
+            HealthStatus::Down => &self.down,
+            _ => &self.other,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// e.g. `uptime=3600s checks=120 up=110 down=8 other=2 channels=4`
+    fn summary_line(&self, active_channels: Option<usize>) -> String {
+        let up = self.up.load(Ordering::Relaxed);
+        let down = self.down.load(Ordering::Relaxed);
+        let other = self.other.load(Ordering::Relaxed);
+        let mut line = format!(
+            "uptime={}s checks={} up={} down={} other={}",
+            self.started_at.elapsed().as_secs(),
+            up + down + other,
+            up,
+            down,
+            other
+        );
+        if let Some(channels) = active_channels {
+            line.push_str(&format!(" channels={}", channels));
+        }
+        line.push('\n');
+        line
+    }
 }
 
 impl AgentServer {
     pub fn new(config: AgentConfig) -> Self {
         let health_checker = Arc::new(GrpcHealthChecker::new(config.clone()));
+        Self::with_checker(config, health_checker)
+    }
+}
 
+impl<C: HealthChecker> AgentServer<C> {
+    /// Create a server that uses the given checker, possibly shared with other servers.
+    /// Used to run several listeners, each with its own effective config.
+    pub fn with_checker(config: AgentConfig, health_checker: Arc<C>) -> Self {
         AgentServer {
-            config,
+            client_allowlist: config
+                .client_allowlist
+                .iter()
+                .filter_map(|cidr| IpCidr::parse(cidr))
+                .collect(),
+            connection_permits: (config.max_connections > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_connections))),
+            overload_permits: Arc::new(Semaphore::new(MAX_OVERLOAD_ANSWERS)),
+            config: Arc::new(config),
             health_checker,
+            stats: Arc::new(ServerStats::new(Arc::new(ActivityTracker::new()))),
         }
     }
 
-    // T067-T069: TCP server with connection accept loop
-    pub async fn run(&self) -> Result<()> {
+    /// Record activity in a tracker shared with other listeners, for idle_shutdown_ms
+    pub fn with_activity_tracker(mut self, activity: Arc<ActivityTracker>) -> Self {
+        self.stats = Arc::new(ServerStats::new(activity));
+        self
+    }
+
+    /// Unix socket peers are always allowed; file permissions guard those
+    fn is_client_allowed(&self, peer: &PeerAddr) -> bool {
+        match peer.ip() {
+            Some(ip) if !self.client_allowlist.is_empty() => {
+                self.client_allowlist.iter().any(|cidr| cidr.contains(ip))
+            }
+            _ => true,
+        }
+    }
+
+    /// Bind the agent listener to the configured address and port (or Unix socket path).
+    pub async fn bind(&self) -> Result<AgentListener> {
         // T068: Bind to configured address and port
-        let bind_addr = format!(
-            "{}:{}",
-            self.config.server_bind_address, self.config.server_port
-        );
+        let bind_addr =
+            ListenAddr::parse(&self.config.server_bind_address, self.config.server_port);
 
-        let listener = TcpListener::bind(&bind_addr)
+        let listener = self
+            .bind_with_retry(&bind_addr)
             .await
             .with_context(|| format!("Failed to bind to {}", bind_addr))?;
 
+        let address = match listener.local_addr() {
+            Some(local_addr) => local_addr.to_string(),
+            None => bind_addr.to_string(),
+        };
         tracing::info!(
-            address = %bind_addr,
+            address = %address,
+            backlog = self.config.listen_backlog,
             "Agent Text Protocol server listening"
         );
 
-        self.run_with_listener(listener).await
+        // Fast Open is an optimization, so failing to enable it never stops the listener
+        if self.config.tcp_fast_open {
+            match listener.set_fast_open(self.config.listen_backlog) {
+                Ok(true) if crate::listener::kernel_fast_open_server_enabled() == Some(false) => {
+                    tracing::warn!(
+                        address = %address,
+                        "TCP Fast Open set on the listener, but the kernel has server support off \
+                         (net.ipv4.tcp_fastopen lacks 0x2); connections fall back to a normal handshake"
+                    )
+                }
+                Ok(true) => tracing::info!(address = %address, "TCP Fast Open enabled"),
+                Ok(false) => tracing::info!(
+                    address = %address,
+                    "TCP Fast Open is not supported for this listener or platform"
+                ),
+                Err(e) => tracing::warn!(
+                    address = %address,
+                    error = %e,
+                    "Failed to enable TCP Fast Open; continuing without it"
+                ),
+            }
+        }
+
+        Ok(listener)
+    }
+
+    /// Bind, retrying with backoff for up to bind_retry_ms while the address is still held by
+    /// the process being replaced or not yet assigned to this host
+    async fn bind_with_retry(&self, bind_addr: &ListenAddr) -> Result<AgentListener> {
+        let retry_for = Duration::from_millis(self.config.bind_retry_ms);
+        let started = Instant::now();
+        let mut delay = BIND_RETRY_INITIAL_DELAY;
+
+        loop {
+            match AgentListener::bind(bind_addr, self.config.listen_backlog).await {
+                Ok(listener) => return Ok(listener),
+                Err(e) if is_retryable_bind_error(&e) && started.elapsed() < retry_for => {
+                    let wait = delay.min(retry_for - started.elapsed());
+                    tracing::warn!(
+                        address = %bind_addr,
+                        error = %format!("{:#}", e),
+                        retry_in_ms = wait.as_millis() as u64,
+                        "Listener address not available yet, retrying bind"
+                    );
+                    tokio::time::sleep(wait).await;
+                    delay = (delay * 2).min(BIND_RETRY_MAX_DELAY);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // T067-T069: TCP server with connection accept loop
+    pub async fn run(&self) -> Result<()> {
+        let listener = self.bind().await?;
+        self.serve_with_shutdown(listener, std::future::pending())
+            .await
+    }
+
+    /// Bind the listener and run the accept loop on a background task.
+    /// Returns a handle exposing the bound address (useful with port 0) and a shutdown trigger.
+    pub async fn start(self) -> Result<ServerHandle> {
+        let listener = self.bind().await?;
+        let local_addr = listener.local_addr();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        let task = tokio::spawn(async move {
+            self.serve_with_shutdown(listener, async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+        });
+
+        Ok(ServerHandle {
+            local_addr,
+            shutdown_tx,
+            task,
+        })
     }
 
     /// Run the agent server using a pre-bound TcpListener.
     /// Useful for tests that need to bind to port 0 and discover the actual port.
     pub async fn run_with_listener(&self, listener: TcpListener) -> Result<()> {
+        self.serve_with_shutdown(listener.into(), std::future::pending())
+            .await
+    }
+
+    /// Run the accept loop until `shutdown` completes, or until the agent has been idle for
+    /// idle_shutdown_ms when that is set.
+    /// Open connections then finish their in-flight check and close instead of waiting for
+    /// the next request. This returns once they have, or once SHUTDOWN_DRAIN_MARGIN past the
+    /// check timeout budget has passed, aborting any still running, so nothing is left running
+    /// if the runtime is torn down afterwards.
+    pub async fn serve_with_shutdown(
+        &self,
+        listener: AgentListener,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        tokio::pin!(shutdown);
+        // Dropping the sender signals every connection to close
+        let (connections_shutdown_tx, connections_shutdown_rx) = watch::channel(());
+        let mut connections = JoinSet::new();
+        let idle_shutdown = self.config.idle_shutdown_ms.map(Duration::from_millis);
+        let idle = async {
+            match idle_shutdown {
+                Some(idle) => self.stats.activity.wait_idle(idle).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(idle);
+
         // T069: Connection accept loop spawning tasks per connection
         loop {
-            match listener.accept().await {
-                Ok((stream, peer_addr)) => {
-                    tracing::debug!(
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                // Reap finished connections, so the set only holds open ones
+                Some(_) = connections.join_next() => continue,
+                _ = &mut shutdown => {
+                    tracing::debug!("Agent server accept loop shutting down");
+                    break;
+                }
+                _ = &mut idle => {
+                    tracing::info!(
+                        idle_shutdown_ms = self.config.idle_shutdown_ms,
+                        "No connections or checks within idle_shutdown_ms, shutting down"
+                    );
+                    break;
+                }
+            };
+
+            match accepted {
+                Ok((stream, peer)) => {
+                    self.stats.activity.touch();
+                    if !self.is_client_allowed(&peer) {
+                        // Dropping the stream closes the connection without a response
+                        tracing::warn!(peer = %peer, "Rejected connection from peer outside client_allowlist");
+                        #[cfg(feature = "metrics")]
+                        metrics::HAPROXY_CONNECTIONS_REJECTED_TOTAL.inc();
+                        continue;
+                    }
+                    let peer_addr = peer.to_string();
+
+                    let connection_permit = match &self.connection_permits {
+                        Some(permits) => match Arc::clone(permits).try_acquire_owned() {
+                            Ok(permit) => Some(permit),
+                            Err(_) => {
+                                // Once per connection turned away, so debug; the overloaded
+                                // connections counter shows the storm
+                                tracing::debug!(
+                                    peer = %peer_addr,
+                                    max_connections = self.config.max_connections,
+                                    response = ?self.config.overload_response,
+                                    "Connection limit reached"
+                                );
+                                #[cfg(feature = "metrics")]
+                                metrics::HAPROXY_CONNECTIONS_OVERLOADED_TOTAL.inc();
+                                // Dropping the stream closes the connection without a response
+                                if self.config.overload_response != OverloadResponse::Close
+                                    && let Ok(permit) =
+                                        Arc::clone(&self.overload_permits).try_acquire_owned()
+                                {
+                                    let config = Arc::clone(&self.config);
+                                    tokio::spawn(async move {
+                                        let _permit = permit;
+                                        answer_overloaded(stream, config).await
+                                    });
+                                }
+                                continue;
+                            }
+                        },
+                        None => None,
+                    };
+
+                    let trace_id = Uuid::new_v4();
+                    // Every event while serving the connection, down to the checks it runs,
+                    // carries these fields without repeating them
+                    let span = tracing::info_span!(
+                        "handle_connection",
+                        trace_id = %trace_id,
                         peer = %peer_addr,
-                        "HAProxy connection established"
+                    );
+                    span.in_scope(
+                        || tracing::debug!(peer = %peer_addr, "HAProxy connection established"),
                     );
 
                     let health_checker = Arc::clone(&self.health_checker);
+                    let config = Arc::clone(&self.config);
+                    let stats = Arc::clone(&self.stats);
+                    let shutdown_rx = connections_shutdown_rx.clone();
 
                     // T126: Increment HAPROXY_CONNECTIONS_ACTIVE on connect
-                    metrics::HAPROXY_CONNECTIONS_ACTIVE.inc();
+                    #[cfg(feature = "metrics")]
+                    let connected_at = {
+                        metrics::HAPROXY_CONNECTIONS_ACTIVE.inc();
+                        metrics::HAPROXY_CONNECTIONS_TOTAL.inc();
+                        Instant::now()
+                    };
 
                     // Spawn a task to handle this connection
-                    tokio::spawn(async move {
-                        if let Err(e) = handle_connection(stream, health_checker).await {
-                            tracing::warn!(
+                    connections.spawn(
+                        async move {
+                            // Held until the connection closes
+                            let _connection_permit = connection_permit;
+                            if let Err(e) = handle_connection(
+                                trace_id,
+                                stream,
+                                &peer_addr,
+                                health_checker,
+                                config,
+                                stats,
+                                shutdown_rx,
+                            )
+                            .await
+                            {
+                                tracing::warn!(
+                                    peer = %peer_addr,
+                                    error = %e,
+                                    "Connection handling error"
+                                );
+                            }
+
+                            tracing::debug!(
                                 peer = %peer_addr,
-                                error = %e,
-                                "Connection handling error"
+                                "HAProxy connection closed"
                             );
-                        }
 
-                        tracing::debug!(
-                            peer = %peer_addr,
-                            "HAProxy connection closed"
-                        );
-
-                        // T126: Decrement HAPROXY_CONNECTIONS_ACTIVE on disconnect
-                        metrics::HAPROXY_CONNECTIONS_ACTIVE.dec();
-                    });
+                            // T126: Decrement HAPROXY_CONNECTIONS_ACTIVE on disconnect
+                            #[cfg(feature = "metrics")]
+                            {
+                                metrics::HAPROXY_CONNECTIONS_ACTIVE.dec();
+                                metrics::HAPROXY_CONNECTION_DURATION_SECONDS
+                                    .observe(connected_at.elapsed().as_secs_f64());
+                            }
+                        }
+                        .instrument(span),
+                    );
                 }
                 Err(e) => {
                     tracing::error!(error = %e, "Failed to accept connection");
@@ -89,111 +455,769 @@ impl AgentServer {
                 }
             }
         }
+
+        drop(listener);
+        drop(connections_shutdown_tx);
+        let drain =
+            Duration::from_millis(self.config.check_timeout_budget_ms()) + SHUTDOWN_DRAIN_MARGIN;
+        let drained = tokio::time::timeout(drain, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                connections = connections.len(),
+                drain_ms = drain.as_millis() as u64,
+                "Connections still open after the shutdown drain, aborting them"
+            );
+            connections.shutdown().await;
+        }
+        Ok(())
+    }
+}
+
+/// Handle to an agent server started with [`AgentServer::start`].
+/// Dropping the handle also stops the accept loop.
+pub struct ServerHandle {
+    local_addr: Option<SocketAddr>,
+    shutdown_tx: oneshot::Sender<()>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl ServerHandle {
+    /// TCP address the listener is actually bound to; None for Unix sockets
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Stop accepting connections and wait for open connections to finish their in-flight check
+    pub async fn shutdown(self) -> Result<()> {
+        let _ = self.shutdown_tx.send(());
+        self.task.await.context("Agent server task failed")?
     }
 }
 
 // T070-T076: handle_connection function
-#[tracing::instrument(skip(stream, health_checker), fields(trace_id = %Uuid::new_v4()))]
-async fn handle_connection(
-    stream: TcpStream,
-    health_checker: Arc<GrpcHealthChecker>,
-) -> Result<()> {
-    let peer_addr = stream.peer_addr().ok();
+// Runs inside the connection's span, which carries trace_id and peer
+async fn handle_connection<S, C>(
+    trace_id: Uuid,
+    stream: S,
+    peer_addr: &str,
+    health_checker: Arc<C>,
+    config: Arc<AgentConfig>,
+    stats: Arc<ServerStats>,
+    mut shutdown: watch::Receiver<()>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    C: HealthChecker,
+{
     let mut reader = BufReader::new(stream);
+    if !config.auth_token.is_empty()
+        && !authenticate(&mut reader, &config, peer_addr, &mut shutdown).await?
+    {
+        return Ok(());
+    }
 
-    // T071: Persistent connection handling (loop over requests)
-    loop {
-        let mut line = String::new();
+    let connection = Connection {
+        trace_id,
+        peer_addr,
+        health_checker: &*health_checker,
+        config: &config,
+        stats: &stats,
+        #[cfg(feature = "metrics")]
+        exemplar_trace_id: config.metrics_exemplars.then(|| trace_id.to_string()),
+    };
 
-        // Read line from TCP stream
-        let bytes_read = reader
-            .read_line(&mut line)
-            .await
-            .context("Failed to read from TCP stream")?;
+    // A single request per connection leaves nothing to pipeline
+    if config.pipeline_depth > 1 && config.persistent_connections {
+        connection.serve_pipelined(reader, shutdown).await
+    } else {
+        connection.serve(reader, shutdown).await
+    }
+}
 
-        // T072: Graceful connection close detection (EOF)
-        if bytes_read == 0 {
-            tracing::debug!("Connection closed by peer (EOF)");
-            break;
-        }
+/// Read the connection's first request and check it is the auth_token.
+/// Returns false when the connection should be closed without an answer.
+async fn authenticate<R>(
+    reader: &mut R,
+    config: &AgentConfig,
+    peer_addr: &str,
+    shutdown: &mut watch::Receiver<()>,
+) -> Result<bool>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut token = Vec::new();
+    let read = read_request(
+        reader,
+        config.request_framing,
+        config.request_delimiter.as_byte(),
+        &mut token,
+    );
+    let bytes_read = tokio::select! {
+        read = tokio::time::timeout(AUTH_READ_TIMEOUT, read) => match read {
+            Ok(read) => read.context("Failed to read from TCP stream")?,
+            Err(_) => {
+                tracing::warn!(peer = %peer_addr, "Closing connection that sent no auth token in time");
+                #[cfg(feature = "metrics")]
+                metrics::HAPROXY_AUTH_FAILURES_TOTAL.inc();
+                return Ok(false);
+            }
+        },
+        _ = shutdown.changed() => return Ok(false),
+    };
+    if bytes_read == 0 {
+        tracing::debug!("Connection closed by peer before authenticating");
+        return Ok(false);
+    }
 
-        // T074: Integrate protocol::parse_request
-        match protocol::parse_request(&line) {
-            Ok(request) => {
+    let token = token.trim_ascii();
+    let token = token.strip_suffix(b"\0").unwrap_or(token);
+    if tokens_match(token, config.auth_token.as_bytes()) {
+        tracing::debug!("Connection authenticated");
+        return Ok(true);
+    }
+
+    tracing::warn!(peer = %peer_addr, "Closing connection that did not open with the auth token");
+    #[cfg(feature = "metrics")]
+    metrics::HAPROXY_AUTH_FAILURES_TOTAL.inc();
+    Ok(false)
+}
+
+/// Compare in time independent of where the first difference is, so the token can't be
+/// guessed a byte at a time
+fn tokens_match(received: &[u8], expected: &[u8]) -> bool {
+    received.len() == expected.len()
+        && received
+            .iter()
+            .zip(expected)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// First and longest wait between bind attempts under bind_retry_ms
+const BIND_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(50);
+const BIND_RETRY_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// True when a bind failed because the address is taken or not (yet) local, which can clear up
+fn is_retryable_bind_error(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::AddrInUse | std::io::ErrorKind::AddrNotAvailable
+            )
+        })
+}
+
+/// What to send back for one request
+enum Reply {
+    /// Nothing, e.g. for an ignored blank line
+    Skip,
+    Status(HealthCheckResponse),
+    /// Raw text, such as the stats summary
+    Text(String),
+    /// Close the connection without answering
+    Close,
+}
+
+/// State shared by every request answered on one connection
+struct Connection<'a, C> {
+    trace_id: Uuid,
+    peer_addr: &'a str,
+    health_checker: &'a C,
+    config: &'a AgentConfig,
+    stats: &'a ServerStats,
+    #[cfg(feature = "metrics")]
+    exemplar_trace_id: Option<String>,
+}
+
+impl<C: HealthChecker> Connection<'_, C> {
+    /// Answer one request at a time, in the order they arrive
+    async fn serve<S>(
+        &self,
+        mut reader: BufReader<S>,
+        mut shutdown: watch::Receiver<()>,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        // Reused for every response on this connection to avoid a String per check
+        let mut response_buf = Vec::with_capacity(64);
+        let delimiter = self.config.request_delimiter.as_byte();
+        let mut request_buf = Vec::new();
+        let eof_grace = Duration::from_millis(self.config.eof_grace_ms);
+        let mut in_eof_grace = false;
+
+        // T071: Persistent connection handling (loop over requests)
+        loop {
+            request_buf.clear();
+
+            // Read one framed request, or stop waiting once the server shuts down
+            let bytes_read = tokio::select! {
+                read = read_request(&mut reader, self.config.request_framing, delimiter, &mut request_buf) => {
+                    read.context("Failed to read from TCP stream")?
+                }
+                _ = shutdown.changed() => {
+                    tracing::debug!("Server shutting down, closing connection");
+                    break;
+                }
+            };
+
+            // T072: Graceful connection close detection (EOF)
+            if bytes_read == 0 {
+                if !eof_grace.is_zero() && !in_eof_grace {
+                    tracing::debug!(
+                        grace_ms = self.config.eof_grace_ms,
+                        "Peer sent EOF, holding connection before closing"
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(eof_grace) => {}
+                        _ = shutdown.changed() => break,
+                    }
+                    in_eof_grace = true;
+                    continue;
+                }
+                tracing::debug!("Connection closed by peer (EOF)");
+                break;
+            }
+            in_eof_grace = false;
+
+            // Race the answer against HAProxy hanging up so an abandoned check is cancelled
+            let answer = self.answer(&request_buf, Instant::now());
+            tokio::pin!(answer);
+            let reply = tokio::select! {
+                // Answers that need no check win over an EOF that is already waiting
+                biased;
+                reply = &mut answer => reply,
+                closed = wait_for_peer_close(&mut reader) => {
+                    if closed && eof_grace.is_zero() {
+                        tracing::debug!("Connection closed mid-check, cancelling check");
+                        break;
+                    }
+                    if closed {
+                        // A half-closed peer may still read the answer, so it gets the grace too
+                        tracing::debug!(
+                            grace_ms = self.config.eof_grace_ms,
+                            "Peer sent EOF mid-check, finishing the check within the grace period"
+                        );
+                        let Ok(reply) = tokio::time::timeout(eof_grace, &mut answer).await else {
+                            tracing::debug!("Check outlasted the EOF grace period, cancelling check");
+                            break;
+                        };
+                        // The grace is spent, so the EOF read next closes the connection
+                        in_eof_grace = true;
+                        reply
+                    } else {
+                        // The next request arrived early; it stays buffered until this one is answered
+                        answer.await
+                    }
+                }
+            };
+
+            // A skipped keepalive line isn't the connection's one request
+            let answered = !matches!(reply, Reply::Skip);
+            if !send_reply(reader.get_mut(), &mut response_buf, reply).await? {
+                break;
+            }
+            if answered && !self.config.persistent_connections {
                 tracing::debug!(
-                    backend = %format!("{}:{}", request.backend_server, request.backend_port),
-                    ssl = ?request.ssl_flag,
-                    "Processing health check request"
+                    "Closing connection after its only request (persistent_connections = false)"
                 );
+                break;
+            }
+        }
 
-                // T124: Start timing health check
-                let start = Instant::now();
-
-                // T075: Integrate checker::check_backend
-                let response = health_checker.check_backend(&request).await;
+        Ok(())
+    }
 
-                // T124: Observe check duration
-                let duration = start.elapsed();
-                metrics::CHECK_DURATION_SECONDS.observe(duration.as_secs_f64());
+    /// Read ahead up to pipeline_depth requests and answer them concurrently, writing the
+    /// answers back in request order. Checks already started are answered even after the
+    /// peer's EOF, since a pipelining client may half-close once its last request is sent.
+    async fn serve_pipelined<S>(
+        &self,
+        mut reader: BufReader<S>,
+        mut shutdown: watch::Receiver<()>,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut response_buf = Vec::with_capacity(64);
+        let delimiter = self.config.request_delimiter.as_byte();
+        // Only cleared once a request is complete, so a read cut short by select! resumes
+        let mut request_buf = Vec::new();
+        let eof_grace = Duration::from_millis(self.config.eof_grace_ms);
+        let mut in_eof_grace = false;
+        let mut reading = true;
+        let mut shutting_down = false;
+        let mut answers = FuturesOrdered::new();
 
+        loop {
+            if !reading && answers.is_empty() {
+                if shutting_down || eof_grace.is_zero() || in_eof_grace {
+                    break;
+                }
                 tracing::debug!(
-                    backend = %format!("{}:{}", request.backend_server, request.backend_port),
-                    status = ?response.status,
-                    "Health check completed"
+                    grace_ms = self.config.eof_grace_ms,
+                    "Peer sent EOF, holding connection before closing"
                 );
+                tokio::select! {
+                    _ = tokio::time::sleep(eof_grace) => {}
+                    _ = shutdown.changed() => break,
+                }
+                in_eof_grace = true;
+                reading = true;
+            }
+
+            tokio::select! {
+                read = read_request(&mut reader, self.config.request_framing, delimiter, &mut request_buf),
+                    if reading && answers.len() < self.config.pipeline_depth =>
+                {
+                    if read.context("Failed to read from TCP stream")? == 0 {
+                        tracing::debug!(pending = answers.len(), "Connection closed by peer (EOF)");
+                        reading = false;
+                        continue;
+                    }
+                    in_eof_grace = false;
+                    answers.push_back(self.answer(&request_buf, Instant::now()));
+                    request_buf.clear();
+                }
+                Some(reply) = answers.next(), if !answers.is_empty() => {
+                    if !send_reply(reader.get_mut(), &mut response_buf, reply).await? {
+                        break;
+                    }
+                }
+                _ = shutdown.changed(), if reading => {
+                    tracing::debug!(pending = answers.len(), "Server shutting down, closing connection");
+                    reading = false;
+                    shutting_down = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Answer one request: a blank line, the stats command or a check.
+    /// The request is copied up front so the read buffer can take the next one.
+    fn answer(&self, request: &[u8], received_at: Instant) -> impl Future<Output = Reply> + '_ {
+        // Invalid UTF-8 is left for the parser to reject as a protocol violation
+        let line = String::from_utf8_lossy(request).into_owned();
+        async move {
+            let reply = match self.answer_line(&line, received_at).await {
+                Reply::Status(response) if self.config.report_only => {
+                    Reply::Status(report_only(response, &line))
+                }
+                reply => reply,
+            };
+            // response_min_latency_ms: a deterministic delay for timing experiments
+            let floor = Duration::from_millis(self.config.response_min_latency_ms);
+            if matches!(reply, Reply::Status(_)) && !floor.is_zero() {
+                tokio::time::sleep_until((received_at + floor).into()).await;
+            }
+            reply
+        }
+    }
+
+    async fn answer_line(&self, line: &str, received_at: Instant) -> Reply {
+        let config = self.config;
+        let stats = self.stats;
 
-                // T123: Increment CHECK_REQUESTS_TOTAL with result label
-                let result_label = match response.status {
-                    crate::protocol::HealthStatus::Up => "up",
-                    crate::protocol::HealthStatus::Down => "down",
-                };
-                metrics::CHECK_REQUESTS_TOTAL
-                    .with_label_values(&[result_label])
-                    .inc();
-
-                // T076: Write response to TCP stream
-                let response_str = response.to_string();
-                reader
-                    .get_mut()
-                    .write_all(response_str.as_bytes())
-                    .await
-                    .context("Failed to write response")?;
-
-                reader
-                    .get_mut()
-                    .flush()
-                    .await
-                    .context("Failed to flush response")?;
+        let trimmed = line.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        if trimmed.is_empty() {
+            match config.blank_line_response {
+                BlankLineResponse::Ignore => {
+                    tracing::trace!("Ignoring blank request line");
+                    return Reply::Skip;
+                }
+                BlankLineResponse::Up => {
+                    tracing::trace!("Answering blank request line as keepalive");
+                    return Reply::Status(shape_response(
+                        HealthCheckResponse::new(HealthStatus::Up),
+                        config,
+                    ));
+                }
+                // Left for the parser to reject
+                BlankLineResponse::Down => {}
             }
+        }
+
+        // A single-word line can never be a check request, which has at least four fields
+        if !config.stats_command.is_empty() && trimmed == config.stats_command {
+            return Reply::Text(stats.summary_line(self.health_checker.active_channels()));
+        }
+
+        // T074: Integrate protocol::parse_request
+        let mut request = match protocol::parse_request(line) {
+            Ok(request) => request,
             Err(e) => {
                 // Protocol violation - log warning and return down
                 tracing::warn!(
-                    peer = ?peer_addr,
+                    peer = %self.peer_addr,
                     error = %e,
                     input = %line.trim(),
                     "Protocol violation"
                 );
 
                 // Track protocol error
-                metrics::CHECK_ERRORS_TOTAL
-                    .with_label_values(&["protocol_error"])
-                    .inc();
+                #[cfg(feature = "metrics")]
+                {
+                    metrics::CHECK_ERRORS_TOTAL
+                        .with_label_values(&["protocol_error"])
+                        .inc();
+                    metrics::PARSE_ERRORS_TOTAL
+                        .with_label_values(&[e.variant_name()])
+                        .inc();
+                    metrics::CHECK_OUTCOMES_TOTAL
+                        .with_label_values(&["parse_error"])
+                        .inc();
+                }
 
                 // Return down for protocol violations
-                let response = "down\n";
-                if let Err(write_err) = reader.get_mut().write_all(response.as_bytes()).await {
-                    tracing::error!(error = %write_err, "Failed to write error response");
-                    // T073: Abrupt disconnect - break on write failure
-                    break;
+                let response = shape_response(
+                    HealthCheckResponse::new(HealthStatus::Down).with_reason("protocol error"),
+                    config,
+                );
+                if config.audit.enabled {
+                    audit_response(
+                        self.trace_id,
+                        self.peer_addr,
+                        None,
+                        &response,
+                        Some("protocol error"),
+                        received_at.elapsed(),
+                    );
                 }
+                return Reply::Status(response);
+            }
+        };
 
-                if let Err(flush_err) = reader.get_mut().flush().await {
-                    tracing::error!(error = %flush_err, "Failed to flush error response");
-                    break;
-                }
+        // The checker is shared by every listener, so the listener's default travels with the request
+        let check_protocol = *request.check_protocol.get_or_insert(config.check_protocol);
+
+        // Every parsed field, to check HAProxy's agent-send template field by field
+        tracing::debug!(
+            backend_server = %request.backend_server,
+            backend_port = request.backend_port,
+            ssl = request.ssl_flag.as_token(),
+            proxy_host = %request.proxy_host_name,
+            service = request.service.as_deref(),
+            interval_ms = request.interval_hint.map(|interval| interval.as_millis() as u64),
+            weight = request.weight,
+            check_protocol = check_protocol.as_str(),
+            "Processing health check request"
+        );
+
+        // Reject without a gRPC call when max_concurrent_checks is reached
+        let Ok(_permit) = self.health_checker.try_acquire_check_permit() else {
+            #[cfg(feature = "metrics")]
+            metrics::CHECKS_OVER_CAPACITY_TOTAL.inc();
+            // Once per rejected check, so debug; checks_over_capacity_total shows the overload
+            tracing::debug!(
+                backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                response = ?config.over_capacity_response,
+                "Concurrent check limit reached"
+            );
+
+            let status = match config.over_capacity_response {
+                OverCapacityResponse::Down => HealthStatus::Down,
+                OverCapacityResponse::Drain => HealthStatus::Drain,
+                OverCapacityResponse::Noop => return Reply::Close,
+            };
+            let response = shape_response(
+                HealthCheckResponse::new(status).with_reason("over capacity"),
+                config,
+            );
+            stats.record(response.status);
+            if config.audit.enabled {
+                audit_response(
+                    self.trace_id,
+                    self.peer_addr,
+                    Some(&request),
+                    &response,
+                    Some("over capacity"),
+                    received_at.elapsed(),
+                );
             }
+            return Reply::Status(response);
+        };
+
+        // T124: Start timing health check
+        let start = Instant::now();
+        stats.activity.touch();
+
+        // T075: Integrate checker::check_backend
+        let response = self.health_checker.check(&request).await;
+        stats.activity.touch();
+        if response.noop {
+            tracing::debug!(
+                backend = %format!("{}:{}", request.backend_server, request.backend_port),
+                status = ?response.status,
+                "Closing without a status"
+            );
+            return Reply::Close;
         }
+        // Verbose gating must not hide the failure reason from the audit log
+        let reason = response.reason;
+        let response = shape_response(response, config);
+
+        // T124: Observe check duration
+        let duration = start.elapsed();
+        #[cfg(feature = "metrics")]
+        metrics::observe_check_duration(duration.as_secs_f64(), self.exemplar_trace_id.as_deref());
+
+        tracing::debug!(
+            backend = %format!("{}:{}", request.backend_server, request.backend_port),
+            status = ?response.status,
+            duration_ms = duration.as_millis() as u64,
+            "Health check completed"
+        );
+
+        // T123: Increment CHECK_REQUESTS_TOTAL with result label
+        #[cfg(feature = "metrics")]
+        metrics::CHECK_REQUESTS_TOTAL
+            .with_label_values(&[response.status.as_token()])
+            .inc();
+
+        stats.record(response.status);
+        if config.audit.enabled {
+            audit_response(
+                self.trace_id,
+                self.peer_addr,
+                Some(&request),
+                &response,
+                reason,
+                received_at.elapsed(),
+            );
+        }
+
+        Reply::Status(response)
+    }
+}
+
+/// T076: Write a reply to the stream. Returns false when the connection should close instead.
+/// HAProxy resetting the connection mid-write is routine (it gave up on the check, or reloaded),
+/// so that closes the connection quietly rather than failing it.
+async fn send_reply<W>(writer: &mut W, buf: &mut Vec<u8>, reply: Reply) -> Result<bool>
+where
+    W: AsyncWrite + Unpin,
+{
+    let written = match reply {
+        Reply::Skip => return Ok(true),
+        Reply::Status(response) => write_response(writer, buf, &response)
+            .await
+            .map_err(|e| (e, "Failed to write response")),
+        Reply::Text(text) => writer
+            .write_all(text.as_bytes())
+            .await
+            .map_err(|e| (e, "Failed to write stats")),
+        Reply::Close => return Ok(false),
+    };
+    match written {
+        Ok(()) => Ok(true),
+        Err((e, _)) if is_peer_reset(&e) => {
+            tracing::debug!(error = %e, "Connection reset by peer during write");
+            #[cfg(feature = "metrics")]
+            metrics::HAPROXY_WRITE_RESETS_TOTAL.inc();
+            Ok(false)
+        }
+        Err((e, context)) => Err(anyhow::Error::new(e).context(context)),
+    }
+}
+
+/// Write errors meaning the peer already tore the connection down
+fn is_peer_reset(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// Record one answered request on the audit stream.
+/// Only `up` answers are INFO, so an audit level of WARN keeps just the failures.
+/// Unparseable requests have no backend fields. `error` is recorded even when the response
+/// sent to HAProxy omits it.
+fn audit_response(
+    trace_id: Uuid,
+    peer_addr: &str,
+    request: Option<&HealthCheckRequest>,
+    response: &HealthCheckResponse,
+    error: Option<&str>,
+    latency: Duration,
+) {
+    macro_rules! audit_event {
+        ($level:ident) => {
+            tracing::$level!(
+                target: AUDIT_TARGET,
+                trace_id = %trace_id,
+                peer = %peer_addr,
+                backend_server = request.map(|r| r.backend_server.as_str()),
+                backend_port = request.map(|r| r.backend_port),
+                proxy_host = request.map(|r| r.proxy_host_name.as_str()),
+                ssl = request.map(|r| r.ssl_flag.is_tls()),
+                status = response.status.as_token(),
+                latency_ms = latency.as_secs_f64() * 1000.0,
+                error,
+                "Agent request answered"
+            )
+        };
     }
 
-    Ok(())
+    if response.status == HealthStatus::Up {
+        audit_event!(info);
+    } else {
+        audit_event!(warn);
+    }
+}
+
+/// Answer a connection beyond max_connections with the overload_response status, then close.
+/// The request is read first (briefly) so closing doesn't reset the connection under the answer.
+async fn answer_overloaded<S>(stream: S, config: Arc<AgentConfig>)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Some(status) = config.overload_response.status() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut request_buf = Vec::new();
+    let _ = tokio::time::timeout(
+        OVERLOAD_READ_TIMEOUT,
+        read_request(
+            &mut reader,
+            config.request_framing,
+            config.request_delimiter.as_byte(),
+            &mut request_buf,
+        ),
+    )
+    .await;
+
+    let mut response = shape_response(
+        HealthCheckResponse::new(status).with_reason("overloaded"),
+        &config,
+    );
+    if config.report_only {
+        response = report_only(response, &String::from_utf8_lossy(&request_buf));
+    }
+    let mut response_buf = Vec::with_capacity(64);
+    let stream = reader.get_mut();
+    if write_response(stream, &mut response_buf, &response)
+        .await
+        .is_ok()
+    {
+        let _ = stream.shutdown().await;
+    }
+}
+
+/// Read one request into `buf`, returning the bytes consumed from the stream (0 at EOF).
+/// A length-prefixed frame yields only its payload, which carries no delimiter.
+/// Cancel safe as long as `buf` is kept until the read completes.
+async fn read_request<R>(
+    reader: &mut R,
+    framing: RequestFraming,
+    delimiter: u8,
+    buf: &mut Vec<u8>,
+) -> std::io::Result<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
+    match framing {
+        RequestFraming::Line => reader.read_until(delimiter, buf).await,
+        // Accumulates the prefix and payload in `buf`, consuming only what it copies, so a read
+        // cancelled partway resumes where it left off when called again with the same buffer
+        RequestFraming::LengthPrefixed => loop {
+            let frame_len = match buf.get(..2) {
+                Some(prefix) => 2 + u16::from_be_bytes([prefix[0], prefix[1]]) as usize,
+                None => 2,
+            };
+            if buf.len() >= 2 && buf.len() == frame_len {
+                buf.drain(..2);
+                return Ok(frame_len);
+            }
+
+            let available = reader.fill_buf().await?;
+            if available.is_empty() {
+                // EOF before a new frame is a clean close; anywhere later it is a truncated frame
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            let take = available.len().min(frame_len - buf.len());
+            buf.extend_from_slice(&available[..take]);
+            reader.consume(take);
+        },
+    }
+}
+
+/// Wait until the peer sends more data (false) or closes its side of the connection (true).
+/// Data is only buffered, not consumed, so a pipelined request is still read afterwards.
+async fn wait_for_peer_close<R>(reader: &mut R) -> bool
+where
+    R: AsyncBufRead + Unpin,
+{
+    match reader.fill_buf().await {
+        Ok(buf) => buf.is_empty(),
+        Err(_) => true,
+    }
+}
+
+/// Write the response to the stream.
+/// Bare status lines are static slices; weights and reasons go through the connection's buffer.
+async fn write_response<W>(
+    writer: &mut W,
+    buf: &mut Vec<u8>,
+    response: &HealthCheckResponse,
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    if response.is_bare() {
+        writer.write_all(response.status.as_line_bytes()).await?;
+    } else {
+        buf.clear();
+        write!(buf, "{}", response)?;
+        writer.write_all(buf).await?;
+    }
+    writer.flush().await
+}
+
+/// The plain `up` report_only sends in place of an answer that has been logged and counted.
+/// `line` is the request answered, parsed again only to name the backend in the log.
+fn report_only(response: HealthCheckResponse, line: &str) -> HealthCheckResponse {
+    if response.status != HealthStatus::Up {
+        // Once per check, so debug like every other per-check event
+        tracing::debug!(
+            backend = protocol::parse_request(line)
+                .ok()
+                .map(|request| format!("{}:{}", request.backend_server, request.backend_port)),
+            would_report = response.status.as_token(),
+            reason = response.reason,
+            weight = response.weight,
+            "Report-only mode: answering up"
+        );
+        #[cfg(feature = "metrics")]
+        metrics::REPORT_ONLY_OVERRIDES_TOTAL
+            .with_label_values(&[response.status.as_token()])
+            .inc();
+    }
+    HealthCheckResponse::new(HealthStatus::Up)
+}
+
+/// Apply listener-level response settings: status remapping and reason comments
+fn shape_response(mut response: HealthCheckResponse, config: &AgentConfig) -> HealthCheckResponse {
+    if let Some(status) = config.response_status_map.get(&response.status) {
+        response.status = *status;
+    }
+    if config.serving_status_comment && response.serving_status.is_some() {
+        response.reason = response.serving_status;
+    } else if !config.verbose_response {
+        response.reason = None;
+    }
+    response
 }
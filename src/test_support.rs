@@ -0,0 +1,23 @@
+// Unit test helpers
+// Fixtures shared by the tests of several modules, compiled only for cargo test
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// HTTP/1.1 peer that answers one request with `reply` and returns the request head it received
+pub(crate) async fn mock_http_peer(reply: &'static str) -> (u16, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = String::new();
+        while !head.ends_with("\r\n\r\n") {
+            reader.read_line(&mut head).await.unwrap();
+        }
+        reader.get_mut().write_all(reply.as_bytes()).await.unwrap();
+        head
+    });
+    (port, task)
+}
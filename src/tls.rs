@@ -0,0 +1,393 @@
+// TLS support for ssl-insecure backends
+// tonic's ClientTlsConfig always verifies certificates, so skip-verify needs its own connector
+
+use crate::checker::CheckError;
+use crate::config::ProxyConfig;
+use crate::protocol::SslFlag;
+use crate::proxy;
+use anyhow::Context;
+use hyper_util::rt::TokioIo;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tonic::codegen::http::Uri;
+
+/// Connector for `ssl-insecure` backends: TLS over TCP to host:port with certificate verification
+/// disabled. Handshake signatures are still checked, so only the certificate chain is trusted blindly.
+pub(crate) fn insecure_tls_connector(
+    host: String,
+    port: u16,
+    proxy_host: &str,
+    alpn_protocols: &[String],
+    proxy: ProxyConfig,
+) -> Result<
+    impl tower::Service<
+        Uri,
+        Response = TokioIo<TlsStream<TcpStream>>,
+        Error = std::io::Error,
+        Future: Send,
+    > + Send
+    + 'static,
+    CheckError,
+> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut tls_config = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| CheckError::TlsConfig(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+        .with_no_client_auth();
+    tls_config.alpn_protocols = alpn_protocols
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+
+    let server_name = ServerName::try_from(proxy_host.to_string())
+        .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+    let connector = TlsConnector::from(Arc::new(tls_config));
+
+    Ok(tower::service_fn(move |_: Uri| {
+        let connector = connector.clone();
+        let server_name = server_name.clone();
+        let host = host.clone();
+        let proxy = proxy.clone();
+        async move {
+            let tcp = proxy::dial(&proxy, &host, port).await?;
+            let tls = connector.connect(server_name, tcp).await?;
+            Ok::<_, std::io::Error>(TokioIo::new(tls))
+        }
+    }))
+}
+
+/// TLS for HTTP checks, offering only http/1.1: `ssl-insecure` skips certificate verification
+/// like its gRPC counterpart, and `ssl` trusts the tls_ca_dir bundle, as tonic's channels do
+pub(crate) fn http_tls_connector(
+    ssl_flag: SslFlag,
+    ca_bundle: Option<&tonic::transport::Certificate>,
+) -> Result<TlsConnector, CheckError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+    let mut tls_config = if ssl_flag == SslFlag::SslInsecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        let mut roots = rustls::RootCertStore::empty();
+        for certificate in ca_bundle
+            .map(|bundle| CertificateDer::pem_slice_iter(bundle.get_ref()))
+            .into_iter()
+            .flatten()
+        {
+            let certificate = certificate.map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+            roots
+                .add(certificate)
+                .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    tls_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsConnector::from(Arc::new(tls_config)))
+}
+
+/// Accepts any server certificate; used only for `ssl-insecure` requests
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Why a TLS handshake failed, plus what the backend's certificate presented when rustls reports it
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct HandshakeFailure {
+    pub reason: String,
+    pub expected_name: Option<String>,
+    /// Subject alternative names of the leaf certificate, on a name mismatch
+    pub presented_names: Vec<String>,
+    /// Certificate validity bounds as Unix seconds, on an expired or not-yet-valid certificate
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+}
+
+impl HandshakeFailure {
+    fn from_rustls(error: &rustls::Error) -> Self {
+        let mut failure = HandshakeFailure {
+            reason: error.to_string(),
+            ..HandshakeFailure::default()
+        };
+        if let rustls::Error::InvalidCertificate(cert_error) = error {
+            match cert_error {
+                CertificateError::NotValidForNameContext {
+                    expected,
+                    presented,
+                } => {
+                    failure.expected_name = Some(expected.to_str().into_owned());
+                    failure.presented_names = presented.clone();
+                }
+                CertificateError::ExpiredContext { not_after, .. } => {
+                    failure.not_after = Some(not_after.as_secs());
+                }
+                CertificateError::NotValidYetContext { not_before, .. } => {
+                    failure.not_before = Some(not_before.as_secs());
+                }
+                _ => {}
+            }
+        }
+        failure
+    }
+}
+
+/// Find a rustls error in a connect error's source chain, if the connect failed in the handshake
+pub(crate) fn handshake_failure(
+    error: &(dyn std::error::Error + 'static),
+) -> Option<HandshakeFailure> {
+    let mut current = Some(error);
+    while let Some(err) = current {
+        // io::Error::source() skips the error it wraps, so look inside it explicitly
+        let rustls_error = err.downcast_ref::<rustls::Error>().or_else(|| {
+            err.downcast_ref::<std::io::Error>()
+                .and_then(|io_err| io_err.get_ref())
+                .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        });
+        if let Some(rustls_error) = rustls_error {
+            return Some(HandshakeFailure::from_rustls(rustls_error));
+        }
+        current = err.source();
+    }
+    None
+}
+
+/// Concatenate the PEM certificates of every file in `dir` into one bundle of trust anchors for
+/// verified `ssl` channels. Dot-entries and subdirectories are skipped, which also skips the
+/// `..data` links of a Kubernetes ConfigMap or Secret mount. A file that can't be read or holds
+/// no certificate fails the whole load, naming the file.
+pub(crate) fn load_ca_dir(dir: &Path) -> anyhow::Result<tonic::transport::Certificate> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read CA directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to read CA directory {}", dir.display()))?;
+    paths.sort();
+
+    let mut bundle = Vec::new();
+    for path in paths {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        // Follows symlinks, so certificates linked into the directory are loaded
+        if hidden || !path.is_file() {
+            continue;
+        }
+
+        let pem = std::fs::read(&path)
+            .with_context(|| format!("Failed to read CA file {}", path.display()))?;
+        let mut certificates = 0;
+        for certificate in CertificateDer::pem_slice_iter(&pem) {
+            certificate.with_context(|| format!("Invalid PEM in CA file {}", path.display()))?;
+            certificates += 1;
+        }
+        if certificates == 0 {
+            anyhow::bail!("CA file {} contains no PEM certificates", path.display());
+        }
+
+        bundle.extend_from_slice(&pem);
+        if !pem.ends_with(b"\n") {
+            bundle.push(b'\n');
+        }
+    }
+
+    if bundle.is_empty() {
+        anyhow::bail!("CA directory {} contains no certificates", dir.display());
+    }
+    Ok(tonic::transport::Certificate::from_pem(bundle))
+}
+
+/// Server TLS for the metrics endpoint from a PEM certificate chain and private key, offering
+/// only http/1.1 since that is all the metrics server speaks
+pub(crate) fn load_server_config(
+    cert_path: &Path,
+    key_path: &Path,
+) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+    let chain = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Invalid PEM in certificate file {}", cert_path.display()))?;
+    if chain.is_empty() {
+        anyhow::bail!(
+            "Certificate file {} contains no PEM certificates",
+            cert_path.display()
+        );
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .with_context(|| format!("Invalid PEM private key in {}", key_path.display()))?;
+
+    let mut tls_config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(chain, key)
+    .with_context(|| {
+        format!(
+            "Failed to use private key {} with certificate {}",
+            key_path.display(),
+            cert_path.display()
+        )
+    })?;
+    tls_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(tls_config))
+}
+
+/// Build both TLS client configurations without connecting anywhere, so a broken crypto
+/// provider or unusable ALPN list shows up in the self-test rather than on the first check
+pub(crate) fn self_test(alpn_protocols: &[String]) -> Result<(), CheckError> {
+    tonic::transport::Channel::from_static("https://localhost")
+        .tls_config(tonic::transport::ClientTlsConfig::new().domain_name("localhost"))
+        .map_err(|e| CheckError::TlsConfig(e.to_string()))?;
+    insecure_tls_connector(
+        "localhost".to_string(),
+        443,
+        "localhost",
+        alpn_protocols,
+        ProxyConfig::default(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Stand-in for a transport error that wraps the I/O error of a failed handshake
+    #[derive(Debug)]
+    struct Wrapped(std::io::Error);
+
+    impl std::fmt::Display for Wrapped {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("transport error")
+        }
+    }
+
+    impl std::error::Error for Wrapped {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    fn wrap(error: CertificateError) -> Wrapped {
+        Wrapped(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            rustls::Error::InvalidCertificate(error),
+        ))
+    }
+
+    #[test]
+    fn test_handshake_failure_name_mismatch() {
+        let error = wrap(CertificateError::NotValidForNameContext {
+            expected: ServerName::try_from("api.internal").unwrap(),
+            presented: vec!["old.internal".to_string()],
+        });
+
+        let failure = handshake_failure(&error).unwrap();
+        assert_eq!(failure.expected_name.as_deref(), Some("api.internal"));
+        assert_eq!(failure.presented_names, ["old.internal"]);
+        assert!(
+            failure.reason.contains("old.internal"),
+            "{}",
+            failure.reason
+        );
+    }
+
+    #[test]
+    fn test_handshake_failure_expired() {
+        let error = wrap(CertificateError::ExpiredContext {
+            time: UnixTime::since_unix_epoch(Duration::from_secs(2_000)),
+            not_after: UnixTime::since_unix_epoch(Duration::from_secs(1_000)),
+        });
+
+        let failure = handshake_failure(&error).unwrap();
+        assert_eq!(failure.not_after, Some(1_000));
+        assert!(failure.reason.starts_with("invalid peer certificate"));
+    }
+
+    #[test]
+    fn test_handshake_failure_absent() {
+        let error = Wrapped(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        assert_eq!(handshake_failure(&error), None);
+    }
+
+    #[test]
+    fn test_load_ca_dir() {
+        // Only the PEM framing is checked when loading; the DER inside is parsed on connect
+        const PEM: &str = "-----BEGIN CERTIFICATE-----\nMIIBAA==\n-----END CERTIFICATE-----\n";
+        let dir = tempfile::tempdir().unwrap();
+        assert!(
+            load_ca_dir(dir.path())
+                .unwrap_err()
+                .to_string()
+                .contains("contains no certificates")
+        );
+
+        std::fs::write(dir.path().join("root.pem"), PEM).unwrap();
+        std::fs::write(dir.path().join("intermediate.crt"), PEM.trim_end()).unwrap();
+        std::fs::write(dir.path().join(".hidden"), "not a certificate").unwrap();
+        std::fs::create_dir(dir.path().join("..data")).unwrap();
+        let bundle = load_ca_dir(dir.path()).unwrap();
+        assert_eq!(bundle.get_ref(), format!("{0}{0}", PEM).as_bytes());
+
+        std::fs::write(dir.path().join("notes.txt"), "not a certificate").unwrap();
+        let err = load_ca_dir(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("notes.txt"), "{}", err);
+    }
+}
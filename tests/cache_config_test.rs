@@ -3,8 +3,13 @@
 
 mod common;
 
-use common::{cleanup_agent, send_check, start_agent, start_agent_with_config, start_mock_backend};
+use common::{
+    cleanup_agent, send_check, silent_backend, start_agent, start_agent_with_config,
+    start_mock_backend,
+};
+use haproxy_grpc_agent::checker::{GrpcHealthChecker, HealthChecker};
 use haproxy_grpc_agent::config::AgentConfig;
+use haproxy_grpc_agent::protocol;
 
 // T009: Test that health checks work with caching disabled
 #[tokio::test]
@@ -35,7 +40,7 @@ async fn test_health_check_cache_disabled() {
         "Second check with cache disabled should return 'up'"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // T010: Test that default config (cache enabled) preserves existing behavior
@@ -58,7 +63,7 @@ async fn test_cache_enabled_default_behavior() {
         "Second check with cache enabled should return 'up' (cached channel)"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // T011: Test that unreachable backend returns 'down' with caching disabled
@@ -81,5 +86,23 @@ async fn test_cache_disabled_unreachable_backend() {
         "Unreachable backend with cache disabled should return 'down'"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
+}
+
+// Test that with caching disabled no channel outlives its check
+#[tokio::test]
+async fn test_cache_disabled_keeps_no_channels() {
+    let (_backend, port) = silent_backend().await;
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        grpc_channel_cache_enabled: false,
+        grpc_rpc_timeout_ms: 50,
+        ..AgentConfig::default()
+    });
+
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", port)).unwrap();
+    checker.check_backend(&request).await;
+
+    assert_eq!(checker.cached_channel_count(), 0);
+    assert_eq!(checker.active_channels(), Some(0));
 }
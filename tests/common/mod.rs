@@ -1,6 +1,6 @@
 // Shared test utilities for integration and resilience tests
 use haproxy_grpc_agent::config::AgentConfig;
-use haproxy_grpc_agent::server::AgentServer;
+use haproxy_grpc_agent::server::{AgentServer, ServerHandle};
 use std::net::SocketAddr;
 use std::sync::Once;
 use std::time::Duration;
@@ -54,9 +54,24 @@ pub async fn start_mock_backend(health_status: &str) -> (ContainerAsync<GenericI
     (container, port)
 }
 
+/// Binds a backend that accepts TCP but never speaks HTTP/2, so channels connect and every
+/// check against it stays in flight until a timeout. Returns the listener, which must be kept
+/// alive for the backend to stay up, and its port.
+#[allow(dead_code)] // Each test binary compiles this module; not all of them use it
+pub async fn silent_backend() -> (TcpListener, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind silent backend");
+    let port = listener
+        .local_addr()
+        .expect("Failed to get silent backend port")
+        .port();
+    (listener, port)
+}
+
 /// Starts the agent server in-process on a dynamic port with default config.
-/// Returns the tokio JoinHandle and the bound SocketAddr.
-pub async fn start_agent() -> (tokio::task::JoinHandle<anyhow::Result<()>>, SocketAddr) {
+/// Returns the server handle and the bound SocketAddr.
+pub async fn start_agent() -> (ServerHandle, SocketAddr) {
     let config = AgentConfig {
         server_port: 0,
         server_bind_address: "127.0.0.1".to_string(),
@@ -104,27 +119,30 @@ pub async fn send_raw_request(agent_addr: SocketAddr, request: &str) -> String {
 }
 
 /// Starts the agent server in-process with a custom config on a dynamic port.
-/// Returns the tokio JoinHandle and the bound SocketAddr.
-pub async fn start_agent_with_config(
-    config: AgentConfig,
-) -> (tokio::task::JoinHandle<anyhow::Result<()>>, SocketAddr) {
-    let listener = TcpListener::bind("127.0.0.1:0")
+/// The configured server bind address and port are overridden with 127.0.0.1:0.
+/// Returns the server handle and the bound SocketAddr.
+pub async fn start_agent_with_config(config: AgentConfig) -> (ServerHandle, SocketAddr) {
+    let config = AgentConfig {
+        server_port: 0,
+        server_bind_address: "127.0.0.1".to_string(),
+        ..config
+    };
+
+    let handle = AgentServer::new(config)
+        .start()
         .await
-        .expect("Failed to bind agent listener");
-    let addr = listener
+        .expect("Failed to start agent server");
+    let addr = handle
         .local_addr()
-        .expect("Failed to get agent bound address");
-
-    let server = AgentServer::new(config);
-    let handle = tokio::spawn(async move { server.run_with_listener(listener).await });
-
-    // Brief pause to let the accept loop start
-    tokio::time::sleep(Duration::from_millis(50)).await;
+        .expect("Agent server should be bound to a TCP address");
 
     (handle, addr)
 }
 
-/// Aborts the agent server task.
-pub fn cleanup_agent(handle: tokio::task::JoinHandle<anyhow::Result<()>>) {
-    handle.abort();
+/// Stops the agent server and waits for its accept loop to exit.
+pub async fn cleanup_agent(handle: ServerHandle) {
+    handle
+        .shutdown()
+        .await
+        .expect("Agent server did not shut down cleanly");
 }
@@ -402,3 +402,62 @@ level = "verbose"
         stderr
     );
 }
+
+// Audit records go to their own file as flat JSON lines and stay out of the operational log
+#[test]
+#[ignore] // Requires cargo build
+fn test_audit_log_records_request_and_response() {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpStream;
+
+    ensure_binary_built();
+
+    let audit_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let audit_path = audit_dir.path().join("audit.log");
+
+    let config_content = format!(
+        r#"
+server_port = 15558
+metrics_port = 19098
+
+[audit]
+enabled = true
+file_path = "{}"
+"#,
+        audit_path.to_string_lossy()
+    );
+
+    let (_config_file, config_path) = write_temp_config(&config_content);
+    let mut child = start_agent_with_config(&config_path);
+    std::thread::sleep(Duration::from_secs(1));
+
+    let mut stream = TcpStream::connect("127.0.0.1:15558").expect("Failed to connect to agent");
+    stream.write_all(b"invalid request\n").unwrap();
+    let mut response = String::new();
+    BufReader::new(&stream).read_line(&mut response).unwrap();
+    assert_eq!(response, "down\n");
+    drop(stream);
+
+    // The audit writer is non-blocking; give it a moment to flush
+    std::thread::sleep(Duration::from_millis(500));
+    let _ = child.kill();
+    let output = child.wait_with_output().expect("Failed to wait for agent");
+
+    let audit_content = std::fs::read_to_string(&audit_path).expect("Failed to read audit log");
+    let record: serde_json::Value = audit_content
+        .lines()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .expect("Audit log should contain a JSON record");
+
+    assert_eq!(record["level"], "WARN");
+    assert_eq!(record["status"], "down");
+    assert_eq!(record["error"], "protocol error");
+    assert!(record.get("trace_id").is_some());
+    assert!(record.get("latency_ms").is_some());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("Agent request answered"),
+        "Audit records should not appear in the operational log"
+    );
+}
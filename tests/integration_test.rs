@@ -3,7 +3,19 @@
 
 mod common;
 
-use common::{cleanup_agent, send_check, send_raw_request, start_agent, start_mock_backend};
+use common::{
+    cleanup_agent, send_check, send_raw_request, silent_backend, start_agent,
+    start_agent_with_config, start_mock_backend,
+};
+use haproxy_grpc_agent::checker::{GrpcHealthChecker, HealthChecker};
+use haproxy_grpc_agent::config::{
+    AgentConfig, BlankLineResponse, ChannelKeyMode, FirstFailureResponse,
+};
+use haproxy_grpc_agent::protocol::{
+    self, CheckProtocol, HealthCheckRequest, HealthCheckResponse, HealthStatus,
+};
+use haproxy_grpc_agent::server::AgentServer;
+use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpStream;
 
@@ -16,7 +28,7 @@ async fn test_agent_connectivity() {
     let result = TcpStream::connect(agent_addr).await;
     assert!(result.is_ok(), "Should connect to agent TCP server");
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test health check with healthy backend
@@ -28,7 +40,24 @@ async fn test_health_check_healthy_backend() {
     let response = send_check(agent_addr, "127.0.0.1", backend_port).await;
     assert_eq!(response, "up", "Healthy backend should return 'up'");
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
+}
+
+// Test UNKNOWN answers are reported as the configured unknown_status, even after a retry
+#[tokio::test]
+async fn test_health_check_unknown_status() {
+    let (_container, backend_port) = start_mock_backend("UNKNOWN").await;
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        unknown_status: HealthStatus::Drain,
+        unknown_retry: true,
+        ..AgentConfig::default()
+    })
+    .await;
+
+    let response = send_check(agent_addr, "127.0.0.1", backend_port).await;
+    assert_eq!(response, "drain");
+
+    cleanup_agent(handle).await;
 }
 
 // Test health check with SSL flag (non-existent SSL backend)
@@ -42,7 +71,7 @@ async fn test_health_check_with_ssl() {
         "Non-existent SSL backend should return 'down'"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test protocol violation handling
@@ -53,7 +82,7 @@ async fn test_protocol_violation() {
     let response = send_raw_request(agent_addr, "invalid request\n").await;
     assert_eq!(response, "down", "Protocol violation should return 'down'");
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test persistent connection (multiple requests on same TCP stream)
@@ -92,7 +121,113 @@ async fn test_persistent_connection() {
         response.clear();
     }
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
+}
+
+// Test the agent closes the connection after one answer when persistent_connections is off
+#[tokio::test]
+async fn test_non_persistent_connection() {
+    let config = AgentConfig {
+        persistent_connections: false,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let stream = TcpStream::connect(agent_addr)
+        .await
+        .expect("Should connect to agent");
+    let mut reader = BufReader::new(stream);
+    // A blank keepalive line doesn't use up the connection's one request
+    reader
+        .get_mut()
+        .write_all(b"\nstats\nstats\n")
+        .await
+        .expect("Should write requests");
+
+    let mut response = String::new();
+    reader
+        .read_line(&mut response)
+        .await
+        .expect("Should read response");
+    assert!(response.starts_with("uptime="), "got '{}'", response);
+
+    response.clear();
+    let read = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        reader.read_line(&mut response),
+    )
+    .await
+    .expect("Agent should close the connection");
+    assert_eq!(read.unwrap_or(0), 0, "got '{}'", response);
+
+    cleanup_agent(handle).await;
+}
+
+// Test bind_retry_ms waits for a busy port instead of failing at once
+#[tokio::test]
+async fn test_bind_retry_waits_for_busy_port() {
+    let occupant = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = occupant.local_addr().unwrap().port();
+    let config = |bind_retry_ms| AgentConfig {
+        server_bind_address: "127.0.0.1".to_string(),
+        server_port: port,
+        bind_retry_ms,
+        ..AgentConfig::default()
+    };
+
+    let Err(error) = AgentServer::new(config(0)).start().await else {
+        panic!("Bind to a busy port should fail without bind_retry_ms");
+    };
+    assert!(
+        format!("{:#}", error).contains("Failed to bind"),
+        "{:#}",
+        error
+    );
+
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        drop(occupant);
+    });
+    let handle = AgentServer::new(config(5000))
+        .start()
+        .await
+        .expect("Bind should succeed once the port is released");
+    assert_eq!(handle.local_addr().unwrap().port(), port);
+    release.await.unwrap();
+    cleanup_agent(handle).await;
+}
+
+// Test shutdown waits for a check in flight and still delivers its answer
+#[tokio::test]
+async fn test_shutdown_drains_in_flight_check() {
+    let (silent_backend, silent_port) = silent_backend().await;
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        grpc_rpc_timeout_ms: 300,
+        ..AgentConfig::default()
+    })
+    .await;
+
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    stream
+        .write_all(format!("127.0.0.1 {} no-ssl localhost\n", silent_port).as_bytes())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let started = std::time::Instant::now();
+    handle.shutdown().await.unwrap();
+    assert!(
+        started.elapsed() >= std::time::Duration::from_millis(200),
+        "shutdown returned before the check finished"
+    );
+
+    let mut response = String::new();
+    BufReader::new(stream)
+        .read_line(&mut response)
+        .await
+        .unwrap();
+    assert_eq!(response.trim(), "down");
+    drop(silent_backend);
 }
 
 // Test unreachable backend
@@ -103,5 +238,1102 @@ async fn test_unreachable_backend() {
     let response = send_check(agent_addr, "nonexistent.example.com", 9999).await;
     assert_eq!(response, "down", "Unreachable backend should return 'down'");
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
+}
+
+/// Log writer that keeps everything written to it
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Test that logs from deep inside a connection carry its peer and trace_id
+#[tokio::test]
+async fn test_connection_span_on_check_logs() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_current_span(false)
+        .with_span_list(true)
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    // The current-thread test runtime polls the connection task on this thread
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let closed_port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let (handle, agent_addr) = start_agent().await;
+    let response = send_check(agent_addr, "127.0.0.1", closed_port).await;
+    assert_eq!(response, "down");
+    cleanup_agent(handle).await;
+
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let failure: serde_json::Value = output
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .find(|log: &serde_json::Value| log["fields"]["message"] == "Health check failed")
+        .unwrap_or_else(|| panic!("no check failure logged:\n{}", output));
+    let connection = failure["spans"]
+        .as_array()
+        .and_then(|spans| {
+            spans
+                .iter()
+                .find(|span| span["name"] == "handle_connection")
+        })
+        .unwrap_or_else(|| panic!("check failure outside the connection span: {}", failure));
+    assert!(
+        connection["peer"]
+            .as_str()
+            .is_some_and(|peer| peer.starts_with("127.0.0.1:")),
+        "{}",
+        connection
+    );
+    assert!(connection["trace_id"].as_str().is_some(), "{}", connection);
+}
+
+/// Half-close the write side of a fresh connection and time how long the agent takes to close
+async fn time_close_after_eof(agent_addr: std::net::SocketAddr) -> std::time::Duration {
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    stream.shutdown().await.unwrap();
+
+    let start = std::time::Instant::now();
+    let mut buf = Vec::new();
+    let read = tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut buf);
+    tokio::time::timeout(std::time::Duration::from_secs(5), read)
+        .await
+        .expect("agent never closed the connection")
+        .unwrap();
+    start.elapsed()
+}
+
+// Test connections from outside client_allowlist are closed without a response
+#[tokio::test]
+async fn test_client_allowlist() {
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        client_allowlist: vec!["192.0.2.0/24".to_string()],
+        ..AgentConfig::default()
+    })
+    .await;
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    let _ = stream.write_all(b"stats\n").await;
+    let mut response = Vec::new();
+    // Closed with unread input, so the close may arrive as a reset
+    let read = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut response),
+    )
+    .await
+    .expect("rejected connection should be closed");
+    assert!(read.is_err() || response.is_empty());
+    cleanup_agent(handle).await;
+
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        client_allowlist: vec!["192.0.2.0/24".to_string(), "127.0.0.0/8".to_string()],
+        ..AgentConfig::default()
+    })
+    .await;
+    assert!(
+        send_raw_request(agent_addr, "stats\n")
+            .await
+            .starts_with("uptime=")
+    );
+    cleanup_agent(handle).await;
+}
+
+// Test the agent closes at once on EOF by default, and only after the grace when configured
+#[tokio::test]
+async fn test_eof_grace() {
+    let (handle, agent_addr) = start_agent().await;
+    assert!(time_close_after_eof(agent_addr).await < std::time::Duration::from_millis(200));
+    cleanup_agent(handle).await;
+
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        eof_grace_ms: 300,
+        ..AgentConfig::default()
+    })
+    .await;
+    assert!(time_close_after_eof(agent_addr).await >= std::time::Duration::from_millis(300));
+    cleanup_agent(handle).await;
+}
+
+// Test a check in flight when the peer half-closes is answered within eof_grace_ms
+#[tokio::test]
+async fn test_eof_grace_mid_check() {
+    let (silent_backend, silent_port) = silent_backend().await;
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        eof_grace_ms: 1000,
+        grpc_rpc_timeout_ms: 200,
+        ..AgentConfig::default()
+    })
+    .await;
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    let request = format!("127.0.0.1 {} no-ssl localhost\n", silent_port);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    stream.shutdown().await.unwrap();
+
+    let mut buf = String::new();
+    let read = tokio::io::AsyncReadExt::read_to_string(&mut stream, &mut buf);
+    tokio::time::timeout(std::time::Duration::from_secs(5), read)
+        .await
+        .expect("agent never closed the connection")
+        .unwrap();
+    assert_eq!(buf.trim(), "down");
+
+    drop(silent_backend);
+    cleanup_agent(handle).await;
+}
+
+// Test the stats command summarizes answered checks and can be disabled
+#[tokio::test]
+async fn test_stats_command() {
+    let (handle, agent_addr) = start_agent().await;
+
+    assert_eq!(send_check(agent_addr, "127.0.0.1", 1).await, "down");
+    let stats = send_raw_request(agent_addr, "stats\n").await;
+    assert!(stats.starts_with("uptime="), "{}", stats);
+    assert!(
+        stats.ends_with("checks=1 up=0 down=1 other=0 channels=0"),
+        "{}",
+        stats
+    );
+    cleanup_agent(handle).await;
+
+    let config = AgentConfig {
+        stats_command: String::new(),
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+    assert_eq!(send_raw_request(agent_addr, "stats\n").await, "down");
+    cleanup_agent(handle).await;
+}
+
+// Test verbose responses append a failure reason comment
+#[tokio::test]
+async fn test_verbose_response_reason() {
+    let config = AgentConfig {
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let response = send_raw_request(agent_addr, "invalid request\n").await;
+    assert_eq!(response, "down # protocol error");
+
+    let response = send_check(agent_addr, "127.0.0.1", 1).await;
+    assert!(
+        response.starts_with("down # "),
+        "Unreachable backend should report a reason, got '{}'",
+        response
+    );
+
+    cleanup_agent(handle).await;
+}
+
+// Test report-only mode answers up whatever the check found
+#[tokio::test]
+async fn test_report_only() {
+    let config = AgentConfig {
+        report_only: true,
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    assert_eq!(send_check(agent_addr, "127.0.0.1", 1).await, "up");
+    assert_eq!(
+        send_raw_request(agent_addr, "invalid request\n").await,
+        "up"
+    );
+    // The stats summary still records what the check found
+    let stats = send_raw_request(agent_addr, "stats\n").await;
+    assert!(stats.contains("down=1"), "{}", stats);
+
+    cleanup_agent(handle).await;
+}
+
+// Test a connection that opens with the auth token is served as usual
+#[tokio::test]
+async fn test_auth_token_valid() {
+    let config = AgentConfig {
+        auth_token: "s3cret".to_string(),
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let response = send_raw_request(agent_addr, "s3cret\nstats\n").await;
+    assert!(
+        response.starts_with("uptime="),
+        "Authenticated connection should get the stats line, got '{}'",
+        response
+    );
+
+    let response = send_raw_request(agent_addr, "s3cret\n127.0.0.1 1 no-ssl localhost\n").await;
+    assert_eq!(response, "down");
+
+    cleanup_agent(handle).await;
+}
+
+// Test a connection without the auth token is closed without an answer
+#[tokio::test]
+async fn test_auth_token_invalid() {
+    let config = AgentConfig {
+        auth_token: "s3cret".to_string(),
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    assert_eq!(send_raw_request(agent_addr, "wrong\nstats\n").await, "");
+    assert_eq!(send_raw_request(agent_addr, "s3cre\nstats\n").await, "");
+    // A check request in place of the token is not answered either
+    assert_eq!(
+        send_raw_request(agent_addr, "127.0.0.1 1 no-ssl localhost\n").await,
+        ""
+    );
+
+    // A peer that never finishes the token line doesn't hold the connection open
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    stream.write_all(b"s3c").await.unwrap();
+    let mut response = Vec::new();
+    let read = tokio::time::timeout(
+        std::time::Duration::from_secs(3),
+        tokio::io::AsyncReadExt::read_to_end(&mut stream, &mut response),
+    )
+    .await
+    .expect("connection without a token line should be closed");
+    assert!(read.is_err() || response.is_empty());
+
+    cleanup_agent(handle).await;
+}
+
+// Test check=http requests and an http check_protocol default go through the HTTP probe
+#[tokio::test]
+async fn test_http_check_protocol() {
+    // Answers every GET /healthz with 200 and anything else with 503
+    let http_backend = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let http_port = http_backend.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = http_backend.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                let reply = if request_line.starts_with("GET /healthz ") {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"
+                } else {
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n"
+                };
+                let _ = reader.get_mut().write_all(reply.as_bytes()).await;
+            });
+        }
+    });
+
+    let (handle, agent_addr) = start_agent().await;
+    let check = |extra: &str| format!("127.0.0.1 {} no-ssl localhost{}\n", http_port, extra);
+    assert_eq!(
+        send_raw_request(agent_addr, &check(" check=http")).await,
+        "up"
+    );
+    assert_eq!(
+        send_raw_request(agent_addr, &check(" /ready check=http")).await,
+        "down"
+    );
+    // Without the token the agent speaks gRPC, which an HTTP/1.1 server can't answer
+    assert_eq!(send_raw_request(agent_addr, &check("")).await, "down");
+    cleanup_agent(handle).await;
+
+    let config = AgentConfig {
+        check_protocol: CheckProtocol::Http,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+    assert_eq!(send_raw_request(agent_addr, &check("")).await, "up");
+    cleanup_agent(handle).await;
+}
+
+// Test the checker records the last result per backend for the admin endpoint
+#[tokio::test]
+async fn test_backend_state_recorded() {
+    let checker = GrpcHealthChecker::new(AgentConfig::default());
+    assert!(checker.backend_states().is_empty());
+
+    let request = protocol::parse_request("127.0.0.1 1 no-ssl localhost\n").unwrap();
+    checker.check_backend(&request).await;
+
+    let states = checker.backend_states();
+    assert_eq!(states.len(), 1);
+    assert_eq!(states[0].server, "127.0.0.1");
+    assert_eq!(states[0].port, 1);
+    assert!(!states[0].ssl);
+    assert_eq!(states[0].status, HealthStatus::Down);
+    assert!(states[0].last_error.is_some());
+    assert!(states[0].last_check_unix_ms > 0);
+}
+
+// Test checks beyond max_concurrent_checks get the over-capacity response
+#[tokio::test]
+async fn test_over_capacity_response() {
+    // A backend that accepts TCP but never speaks HTTP/2 keeps the first check in flight
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    let config = AgentConfig {
+        max_concurrent_checks: 1,
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let in_flight =
+        tokio::spawn(async move { send_check(agent_addr, "127.0.0.1", silent_port).await });
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let response = send_check(agent_addr, "127.0.0.1", silent_port).await;
+    assert_eq!(response, "down # over capacity");
+
+    assert!(in_flight.await.unwrap().starts_with("down"));
+    drop(silent_backend);
+    cleanup_agent(handle).await;
+}
+
+// Test the response deadline answers down before the connect and RPC timeouts expire
+#[tokio::test]
+async fn test_response_deadline_exceeded() {
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        response_deadline_ms: 100,
+        ..AgentConfig::default()
+    });
+
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", silent_port)).unwrap();
+    let started = std::time::Instant::now();
+    let response = checker.check_backend(&request).await;
+
+    assert!(started.elapsed() < std::time::Duration::from_millis(500));
+    assert_eq!(response.status, HealthStatus::Down);
+    assert_eq!(response.reason, Some("response deadline exceeded"));
+    assert!(checker.backend_states()[0].last_error.is_some());
+    drop(silent_backend);
+}
+
+// Test response_min_latency_ms holds a fast answer back until the floor has passed
+#[tokio::test]
+async fn test_response_min_latency_floor() {
+    let config = AgentConfig {
+        response_min_latency_ms: 200,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    // A protocol violation is answered without a backend round trip
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    let started = std::time::Instant::now();
+    stream.write_all(b"not a request\n").await.unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "down");
+    assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+
+    cleanup_agent(handle).await;
+}
+
+// Test hostnames differing only in case share one cached channel unless keys are exact
+#[tokio::test]
+async fn test_channel_key_case_insensitive_dedup() {
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    for (mode, expected_channels) in [(ChannelKeyMode::Lowercase, 1), (ChannelKeyMode::Exact, 2)] {
+        let checker = GrpcHealthChecker::new(AgentConfig {
+            channel_key_mode: mode,
+            grpc_rpc_timeout_ms: 50,
+            ..AgentConfig::default()
+        });
+
+        for server in ["localhost", "LocalHost"] {
+            let request =
+                protocol::parse_request(&format!("{} {} no-ssl localhost\n", server, silent_port))
+                    .unwrap();
+            checker.check_backend(&request).await;
+        }
+
+        assert_eq!(
+            checker.cached_channel_count(),
+            expected_channels,
+            "{:?}",
+            mode
+        );
+        assert_eq!(
+            checker.backend_states().len(),
+            expected_channels,
+            "{:?}",
+            mode
+        );
+    }
+    drop(silent_backend);
+}
+
+// Test identical concurrent checks share a single backend check
+#[tokio::test]
+async fn test_concurrent_identical_checks_coalesced() {
+    // Counts connections and holds them open without answering
+    let (silent_backend, silent_port) = silent_backend().await;
+    let accepted = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = Arc::clone(&accepted);
+    let backend = tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((stream, _)) = silent_backend.accept().await {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            streams.push(stream);
+        }
+    });
+
+    // Without channel caching, every check that reaches the backend opens its own connection
+    let checker = Arc::new(GrpcHealthChecker::new(AgentConfig {
+        grpc_channel_cache_enabled: false,
+        grpc_rpc_timeout_ms: 200,
+        ..AgentConfig::default()
+    }));
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", silent_port)).unwrap();
+    #[cfg(feature = "metrics")]
+    let queued_before = haproxy_grpc_agent::metrics::CHECK_QUEUE_WAIT_SECONDS.get_sample_count();
+
+    let checks: Vec<_> = (0..5)
+        .map(|_| {
+            let checker = Arc::clone(&checker);
+            let request = request.clone();
+            tokio::spawn(async move { checker.check_backend(&request).await })
+        })
+        .collect();
+    for check in checks {
+        assert_eq!(check.await.unwrap().status, HealthStatus::Down);
+    }
+
+    assert_eq!(accepted.load(std::sync::atomic::Ordering::SeqCst), 1);
+    // The four checks that joined the first one each record how long they queued
+    #[cfg(feature = "metrics")]
+    assert!(
+        haproxy_grpc_agent::metrics::CHECK_QUEUE_WAIT_SECONDS.get_sample_count() - queued_before
+            >= 4
+    );
+    backend.abort();
+}
+
+// Test a failed connect suppresses reconnects until the backoff elapses
+#[tokio::test]
+async fn test_connect_backoff_skips_reconnect() {
+    // Bind then drop to get a port nothing listens on
+    let closed_port = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        connect_backoff_ms: 200,
+        ..AgentConfig::default()
+    });
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", closed_port)).unwrap();
+
+    let first = checker.check_backend(&request).await;
+    assert_eq!(first.reason, Some("connect failed"));
+
+    let second = checker.check_backend(&request).await;
+    assert_eq!(second.status, HealthStatus::Down);
+    assert_eq!(second.reason, Some("connect backoff"));
+
+    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    let third = checker.check_backend(&request).await;
+    assert_eq!(third.reason, Some("connect failed"));
+}
+
+// Test the first failure of a newly seen backend is held back, and only that one
+#[tokio::test]
+async fn test_first_failure_response() {
+    let closed_port = {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        first_failure_response: FirstFailureResponse::Up,
+        ..AgentConfig::default()
+    });
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", closed_port)).unwrap();
+    assert_eq!(
+        checker.check_backend(&request).await.status,
+        HealthStatus::Up
+    );
+    assert_eq!(
+        checker.check_backend(&request).await.status,
+        HealthStatus::Down
+    );
+
+    // noop closes the connection without answering
+    let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+        first_failure_response: FirstFailureResponse::Noop,
+        ..AgentConfig::default()
+    })
+    .await;
+    assert_eq!(send_check(agent_addr, "127.0.0.1", closed_port).await, "");
+    assert_eq!(
+        send_check(agent_addr, "127.0.0.1", closed_port).await,
+        "down"
+    );
+    cleanup_agent(handle).await;
+}
+
+// Test the least recently used channel is evicted once max_cached_channels is reached
+#[tokio::test]
+async fn test_max_cached_channels_evicts_lru() {
+    let mut backends = Vec::new();
+    for _ in 0..3 {
+        backends.push(silent_backend().await);
+    }
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        max_cached_channels: 2,
+        grpc_rpc_timeout_ms: 50,
+        ..AgentConfig::default()
+    });
+
+    for (_, port) in &backends {
+        let request =
+            protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", port)).unwrap();
+        checker.check_backend(&request).await;
+        assert!(checker.cached_channel_count() <= 2);
+    }
+    assert_eq!(checker.cached_channel_count(), 2);
+
+    // The first backend was least recently used
+    let mut ports: Vec<u16> = checker
+        .cached_channel_keys()
+        .iter()
+        .map(|key| key.port)
+        .collect();
+    ports.sort_unstable();
+    let mut expected: Vec<u16> = backends[1..].iter().map(|(_, port)| *port).collect();
+    expected.sort_unstable();
+    assert_eq!(ports, expected);
+
+    assert_eq!(checker.evict_all_channels(), 2);
+    assert_eq!(checker.cached_channel_count(), 0);
+}
+
+// Test the channel sweep evicts idle channels whose backend stopped answering
+#[tokio::test]
+async fn test_channel_sweep_evicts_dead_channel() {
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    let checker = Arc::new(GrpcHealthChecker::new(AgentConfig {
+        channel_sweep_interval_ms: 50,
+        grpc_rpc_timeout_ms: 50,
+        ..AgentConfig::default()
+    }));
+    let sweep = checker.spawn_channel_sweep().expect("sweep enabled");
+
+    let request =
+        protocol::parse_request(&format!("127.0.0.1 {} no-ssl localhost\n", silent_port)).unwrap();
+    checker.check_backend(&request).await;
+    assert_eq!(checker.cached_channel_count(), 1);
+
+    tokio::time::timeout(std::time::Duration::from_secs(2), async {
+        while checker.cached_channel_count() > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("idle channel was not swept");
+
+    sweep.abort();
+    drop(silent_backend);
+}
+
+// Test fault injection delays checks and answers down without touching the backend
+#[cfg(feature = "chaos")]
+#[tokio::test]
+async fn test_chaos_fault_injection() {
+    let mut config = AgentConfig::default();
+    config.chaos.latency_probability = 1.0;
+    config.chaos.latency_ms = 100;
+    config.chaos.down_probability = 1.0;
+    let checker = GrpcHealthChecker::new(config);
+
+    let request = protocol::parse_request("127.0.0.1 1 no-ssl localhost\n").unwrap();
+    let started = std::time::Instant::now();
+    let response = checker.check_backend(&request).await;
+
+    assert!(started.elapsed() >= std::time::Duration::from_millis(100));
+    assert_eq!(response.status, HealthStatus::Down);
+    assert_eq!(response.reason, Some("injected fault"));
+    assert!(checker.backend_states().is_empty());
+}
+
+// Test a cached result is reused within its TTL instead of re-checking the backend
+#[tokio::test]
+async fn test_result_cache_reuses_result() {
+    let checker = GrpcHealthChecker::new(AgentConfig {
+        result_cache_ttl_ms: 60_000,
+        ..AgentConfig::default()
+    });
+
+    let request = protocol::parse_request("127.0.0.1 1 no-ssl localhost\n").unwrap();
+    checker.check_backend(&request).await;
+    let first_check = checker.backend_states()[0].last_check_unix_ms;
+
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    let response = checker.check_backend(&request).await;
+    assert_eq!(response.status, HealthStatus::Down);
+    assert_eq!(checker.backend_states()[0].last_check_unix_ms, first_check);
+
+    // A short interval hint overrides the configured TTL
+    let hinted = protocol::parse_request("127.0.0.1 1 no-ssl localhost interval=2\n").unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    checker.check_backend(&hinted).await;
+    assert!(checker.backend_states()[0].last_check_unix_ms > first_check);
+}
+
+// Test a check is cancelled when HAProxy closes the connection mid-check
+#[tokio::test]
+async fn test_mid_check_disconnect_cancels_check() {
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    let config = AgentConfig {
+        max_concurrent_checks: 1,
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    // Send a check and hang up without waiting for the answer
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    stream
+        .write_all(format!("127.0.0.1 {} no-ssl localhost\n", silent_port).as_bytes())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    drop(stream);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    // The abandoned check released its slot, so this one isn't rejected as over capacity
+    let response = send_check(agent_addr, "127.0.0.1", silent_port).await;
+    assert_ne!(response, "down # over capacity");
+
+    drop(silent_backend);
+    cleanup_agent(handle).await;
+}
+
+/// Checker that answers from the requested port instead of probing a backend
+struct PortParityChecker;
+
+impl HealthChecker for PortParityChecker {
+    async fn check(&self, request: &HealthCheckRequest) -> HealthCheckResponse {
+        if request.backend_port.is_multiple_of(2) {
+            HealthCheckResponse::new(HealthStatus::Up)
+        } else {
+            HealthCheckResponse::new(HealthStatus::Drain)
+        }
+    }
+}
+
+// Test the server can run with a custom HealthChecker implementation
+#[tokio::test]
+async fn test_custom_health_checker() {
+    let config = AgentConfig {
+        server_port: 0,
+        server_bind_address: "127.0.0.1".to_string(),
+        ..AgentConfig::default()
+    };
+    let handle = AgentServer::with_checker(config, Arc::new(PortParityChecker))
+        .start()
+        .await
+        .unwrap();
+    let agent_addr = handle.local_addr().unwrap();
+
+    assert_eq!(send_check(agent_addr, "backend", 8080).await, "up");
+    assert_eq!(send_check(agent_addr, "backend", 8081).await, "drain");
+
+    cleanup_agent(handle).await;
+}
+
+// Test null-delimited requests are accepted when configured
+#[tokio::test]
+async fn test_null_request_delimiter() {
+    let config = AgentConfig {
+        request_delimiter: haproxy_grpc_agent::config::RequestDelimiter::Null,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let response = send_raw_request(agent_addr, "invalid\0").await;
+    assert_eq!(response, "down");
+
+    let response = send_raw_request(agent_addr, "backend 50051 no-ssl backend\0").await;
+    assert_eq!(response, "down", "Unreachable backend should return 'down'");
+
+    cleanup_agent(handle).await;
+}
+
+// Test connections beyond max_connections get overload_response without a check
+#[tokio::test]
+async fn test_overload_response() {
+    let config = AgentConfig {
+        max_connections: 1,
+        overload_response: haproxy_grpc_agent::config::OverloadResponse::Up,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    // Hold the only connection slot; answering stats proves it was accepted
+    let held = TcpStream::connect(agent_addr).await.unwrap();
+    let mut held = BufReader::new(held);
+    held.get_mut().write_all(b"stats\n").await.unwrap();
+    let mut line = String::new();
+    held.read_line(&mut line).await.unwrap();
+    assert!(line.starts_with("uptime="), "{}", line);
+
+    // An unreachable backend would answer down if it were actually checked
+    let response = send_raw_request(agent_addr, "127.0.0.1 9 no-ssl localhost\n").await;
+    assert_eq!(response, "up");
+
+    // The slot frees up once the held connection closes
+    drop(held);
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    let response = send_raw_request(agent_addr, "127.0.0.1 9 no-ssl localhost\n").await;
+    assert_eq!(response, "down");
+
+    cleanup_agent(handle).await;
+}
+
+// Test report-only mode answers up for connections beyond max_connections too
+#[tokio::test]
+async fn test_report_only_overload_response() {
+    let config = AgentConfig {
+        max_connections: 1,
+        overload_response: haproxy_grpc_agent::config::OverloadResponse::Down,
+        report_only: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    // Hold the only connection slot; answering stats proves it was accepted
+    let held = TcpStream::connect(agent_addr).await.unwrap();
+    let mut held = BufReader::new(held);
+    held.get_mut().write_all(b"stats\n").await.unwrap();
+    let mut line = String::new();
+    held.read_line(&mut line).await.unwrap();
+    assert!(line.starts_with("uptime="), "{}", line);
+
+    let response = send_raw_request(agent_addr, "127.0.0.1 9 no-ssl localhost\n").await;
+    assert_eq!(response, "up");
+
+    drop(held);
+    cleanup_agent(handle).await;
+}
+
+// Test the server returns on its own once idle for idle_shutdown_ms
+#[tokio::test]
+async fn test_idle_shutdown() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let agent_addr = listener.local_addr().unwrap();
+    let server = AgentServer::new(AgentConfig {
+        idle_shutdown_ms: Some(300),
+        ..AgentConfig::default()
+    });
+    let task = tokio::spawn(async move { server.run_with_listener(listener).await });
+
+    // Activity pushes the deadline back
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let response = send_raw_request(agent_addr, "stats\n").await;
+    assert!(response.starts_with("uptime="), "{}", response);
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!task.is_finished());
+
+    tokio::time::timeout(std::time::Duration::from_secs(2), task)
+        .await
+        .expect("server should stop once idle")
+        .unwrap()
+        .unwrap();
+}
+
+// Test length-prefixed requests are read as one frame each
+#[tokio::test]
+async fn test_length_prefixed_request_framing() {
+    let config = AgentConfig {
+        request_framing: haproxy_grpc_agent::config::RequestFraming::LengthPrefixed,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    let mut frames = Vec::new();
+    for request in ["stats", "invalid"] {
+        frames.extend_from_slice(&(request.len() as u16).to_be_bytes());
+        frames.extend_from_slice(request.as_bytes());
+    }
+    stream.write_all(&frames).await.unwrap();
+
+    let mut lines = BufReader::new(stream).lines();
+    let stats = lines.next_line().await.unwrap().unwrap();
+    assert!(stats.starts_with("uptime="), "{}", stats);
+    assert_eq!(lines.next_line().await.unwrap().unwrap(), "down");
+
+    cleanup_agent(handle).await;
+}
+
+// Test responses on one connection come back in request order, serially and pipelined
+#[tokio::test]
+async fn test_responses_in_request_order() {
+    for pipeline_depth in [1, 4] {
+        let config = AgentConfig {
+            force_up_backends: vec!["pinned.example".to_string()],
+            force_down_backends: vec!["drained.example".to_string()],
+            verbose_response: true,
+            pipeline_depth,
+            ..AgentConfig::default()
+        };
+        let (handle, agent_addr) = start_agent_with_config(config).await;
+
+        // Each request gets a distinguishable answer, so any reordering shows
+        let requests = [
+            (
+                "drained.example 50051 no-ssl localhost",
+                "down # forced down",
+            ),
+            ("pinned.example 50051 no-ssl localhost", "up"),
+            ("invalid", "down # protocol error"),
+            ("stats", "uptime="),
+            ("pinned.example 50051 no-ssl localhost 50%", "up"),
+            (
+                "drained.example 50051 no-ssl localhost",
+                "down # forced down",
+            ),
+        ];
+        let mut batch = String::new();
+        for (request, _) in requests {
+            batch.push_str(request);
+            batch.push('\n');
+        }
+
+        let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+        stream.write_all(batch.as_bytes()).await.unwrap();
+        stream.shutdown().await.unwrap();
+
+        let mut lines = BufReader::new(stream).lines();
+        for (request, expected) in requests {
+            let line = lines.next_line().await.unwrap().unwrap();
+            assert!(
+                line.starts_with(expected),
+                "depth {}: {:?} answered {:?}",
+                pipeline_depth,
+                request,
+                line
+            );
+        }
+        assert!(lines.next_line().await.unwrap().is_none());
+
+        cleanup_agent(handle).await;
+    }
+}
+
+// Test pipelined requests are checked concurrently and answered in request order
+#[tokio::test]
+async fn test_pipelined_requests_answered_in_order() {
+    let (silent_backend, silent_port) = silent_backend().await;
+
+    let config = AgentConfig {
+        pipeline_depth: 4,
+        response_deadline_ms: 400,
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    // Two slow checks, then a stats line that is ready long before them
+    let check = format!("127.0.0.1 {} no-ssl localhost\n", silent_port);
+    let mut stream = TcpStream::connect(agent_addr).await.unwrap();
+    let started = std::time::Instant::now();
+    stream
+        .write_all(format!("{0}{0}stats\n", check).as_bytes())
+        .await
+        .unwrap();
+    stream.shutdown().await.unwrap();
+
+    let mut lines = BufReader::new(stream).lines();
+    assert_eq!(
+        lines.next_line().await.unwrap().unwrap(),
+        "down # response deadline exceeded"
+    );
+    assert_eq!(
+        lines.next_line().await.unwrap().unwrap(),
+        "down # response deadline exceeded"
+    );
+    let stats = lines.next_line().await.unwrap().unwrap();
+    assert!(stats.starts_with("uptime="), "{}", stats);
+    assert!(lines.next_line().await.unwrap().is_none());
+
+    // Run one after the other, the two checks would take at least 800ms
+    assert!(started.elapsed() < std::time::Duration::from_millis(700));
+
+    drop(silent_backend);
+    cleanup_agent(handle).await;
+}
+
+// Test blank lines are ignored by default and answered per blank_line_response otherwise
+#[tokio::test]
+async fn test_blank_line_response() {
+    let (handle, agent_addr) = start_agent().await;
+    // The blank lines get no answer, so the first response line is the stats summary
+    let response = send_raw_request(agent_addr, "\n  \t\nstats\n").await;
+    assert!(response.starts_with("uptime="), "{}", response);
+    cleanup_agent(handle).await;
+
+    for (mode, expected) in [
+        (BlankLineResponse::Up, "up"),
+        (BlankLineResponse::Down, "down"),
+    ] {
+        let (handle, agent_addr) = start_agent_with_config(AgentConfig {
+            blank_line_response: mode,
+            ..AgentConfig::default()
+        })
+        .await;
+        assert_eq!(send_raw_request(agent_addr, "\n").await, expected);
+        cleanup_agent(handle).await;
+    }
+}
+
+// Test force lists override the real check result
+#[tokio::test]
+async fn test_forced_backend_overrides() {
+    let config = AgentConfig {
+        force_up_backends: vec!["pinned.*".to_string(), "10.0.0.1:*".to_string()],
+        force_down_backends: vec!["10.0.0.1:50051".to_string()],
+        ..AgentConfig::default()
+    };
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    assert_eq!(send_check(agent_addr, "pinned.example", 50051).await, "up");
+    assert_eq!(send_check(agent_addr, "10.0.0.1", 50051).await, "down");
+    assert_eq!(send_check(agent_addr, "10.0.0.1", 50052).await, "up");
+
+    cleanup_agent(handle).await;
+}
+
+// Test hostname backends are resolved through the configured nameservers
+#[tokio::test]
+async fn test_custom_dns_resolver_used() {
+    // A nameserver that never answers makes resolution fail quickly and observably
+    let silent_dns = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut config = AgentConfig {
+        verbose_response: true,
+        ..AgentConfig::default()
+    };
+    config.dns.nameservers = vec![silent_dns.local_addr().unwrap().to_string()];
+    config.dns.timeout_ms = 100;
+    let (handle, agent_addr) = start_agent_with_config(config).await;
+
+    let response = send_check(agent_addr, "backend.internal", 50051).await;
+    assert_eq!(response, "down # dns resolution failed");
+
+    cleanup_agent(handle).await;
+}
+
+// Test the in-process server handle reports its bound port and stops cleanly
+#[tokio::test]
+async fn test_server_handle_shutdown() {
+    let (handle, agent_addr) = start_agent().await;
+    assert_ne!(agent_addr.port(), 0, "Handle should expose the bound port");
+
+    let response = send_raw_request(agent_addr, "invalid request\n").await;
+    assert_eq!(response, "down");
+
+    cleanup_agent(handle).await;
+
+    let result = TcpStream::connect(agent_addr).await;
+    assert!(
+        result.is_err(),
+        "Agent should stop accepting connections after shutdown"
+    );
+}
+
+// Test shutdown closes idle persistent connections instead of leaving them waiting
+#[tokio::test]
+async fn test_shutdown_closes_open_connections() {
+    let (handle, agent_addr) = start_agent().await;
+
+    let stream = TcpStream::connect(agent_addr).await.unwrap();
+    let mut reader = BufReader::new(stream);
+    reader
+        .get_mut()
+        .write_all(b"invalid request\n")
+        .await
+        .unwrap();
+    let mut line = String::new();
+    reader.read_line(&mut line).await.unwrap();
+    assert_eq!(line, "down\n");
+
+    cleanup_agent(handle).await;
+
+    let mut line = String::new();
+    let bytes_read = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        reader.read_line(&mut line),
+    )
+    .await
+    .expect("Connection should close promptly after shutdown")
+    .unwrap();
+    assert_eq!(bytes_read, 0, "Connection should see EOF after shutdown");
+}
+
+// Test the agent can listen on a Linux abstract-namespace Unix socket
+#[cfg(all(target_os = "linux", feature = "unix-socket"))]
+#[tokio::test]
+async fn test_abstract_unix_socket_listener() {
+    use haproxy_grpc_agent::server::AgentServer;
+    use std::os::linux::net::SocketAddrExt;
+
+    let name = format!("haproxy-grpc-agent-test-{}", std::process::id());
+    let config = AgentConfig {
+        server_bind_address: format!("@{}", name),
+        ..AgentConfig::default()
+    };
+    let handle = AgentServer::new(config)
+        .start()
+        .await
+        .expect("Failed to start agent on abstract socket");
+    assert!(handle.local_addr().is_none());
+
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+    let std_stream = std::os::unix::net::UnixStream::connect_addr(&addr)
+        .expect("Should connect to abstract socket");
+    std_stream.set_nonblocking(true).unwrap();
+    let stream = tokio::net::UnixStream::from_std(std_stream).unwrap();
+
+    let mut reader = BufReader::new(stream);
+    reader
+        .get_mut()
+        .write_all(b"invalid request\n")
+        .await
+        .expect("Should write request");
+    let mut response = String::new();
+    reader
+        .read_line(&mut response)
+        .await
+        .expect("Should read response");
+    assert_eq!(response.trim(), "down");
+
+    cleanup_agent(handle).await;
 }
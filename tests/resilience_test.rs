@@ -67,7 +67,7 @@ async fn test_backend_disconnect() {
         "Agent should report 'down' after backend disconnect"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test that agent recovers after backend restart
@@ -110,7 +110,7 @@ async fn test_backend_recovery() {
         "Agent should report 'up' after backend recovery"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test that cached gRPC channel is invalidated on disconnect
@@ -137,7 +137,7 @@ async fn test_cached_connection_invalidated_on_disconnect() {
         "Agent should report 'down' (not stale 'up' from cache) after disconnect"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test that agent reflects changed health status after backend reload
@@ -168,7 +168,7 @@ async fn test_backend_status_change_on_reload() {
         "Agent should report 'down' for NOT_SERVING backend"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }
 
 // Test that agent reconnects after backend restart with same status
@@ -204,5 +204,5 @@ async fn test_backend_restart_with_same_status() {
         "Agent should report 'up' after restart with same status"
     );
 
-    cleanup_agent(handle);
+    cleanup_agent(handle).await;
 }